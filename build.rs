@@ -9,10 +9,6 @@ fn main() {
 
     fs::copy("./bin/libt4eeprom.a", out_dir.join("libt4eeprom.a")).unwrap();
 
-    if env::var("CARGO_FEATURE_USB_LOGGING").is_ok() {
-        fs::copy("./bin/libt4usb.a", out_dir.join("libt4usb.a")).unwrap();
-    }
-
     let link_x = include_bytes!("t4link.x");
     let mut script = File::create(out_dir.join("t4link.x")).unwrap();
     script.write_all(link_x).unwrap();
@@ -0,0 +1,52 @@
+//! Demonstrates the HardFault handler.
+//!
+//! On the first boot, the example waits, then reads from an invalid
+//! address. The HardFault handler saves the fault state, and resets the
+//! processor. After the reset, the example logs the fault record.
+//!
+//! Success criteria: when connecting to the Teensy 4 using a serial
+//! console, you observe a fault report that indicates a data bus error,
+//! followed by the faulting program counter and a slice of the stack.
+//! The LED turns on after the report.
+//!
+//! Requires the `"fault-handler"` feature.
+
+#![no_std]
+#![no_main]
+
+mod systick;
+mod usb_io;
+
+use teensy4_bsp as bsp;
+use teensy4_panic as _;
+
+const DELAY_MS: u32 = 5_000;
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut p = bsp::Peripherals::take().unwrap();
+    let pins = bsp::pins::t40::from_pads(p.iomuxc);
+    let mut systick = systick::new(cortex_m::Peripherals::take().unwrap().SYST);
+    p.ccm
+        .pll1
+        .set_arm_clock(bsp::hal::ccm::PLL1::ARM_HZ, &mut p.ccm.handle, &mut p.dcdc);
+    usb_io::init().unwrap();
+    systick.delay_ms(DELAY_MS);
+
+    if bsp::fault::report() {
+        let mut led = bsp::configure_led(pins.p13);
+        led.set();
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+
+    log::info!("Reading from an invalid address...");
+    systick.delay_ms(100);
+    // Safety: it's not safe! We expect this to fault.
+    let value = unsafe { core::ptr::read_volatile(0x1000_0000 as *const u32) };
+    log::info!("Unexpectedly read {:#X}", value);
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
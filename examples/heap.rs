@@ -0,0 +1,55 @@
+//! Demonstrates heap allocation using the BSP's allocators.
+//!
+//! Success criteria: when connecting to the Teensy 4 using a serial
+//! console, you observe log messages describing a growing `Vec`, and
+//! the heap usage for the OCRAM2 and DTCM heaps.
+//!
+//! Requires the `"alloc"` feature.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+mod systick;
+mod usb_io;
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::alloc::{GlobalAlloc, Layout};
+use teensy4_bsp as bsp;
+use teensy4_panic as _;
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let mut p = bsp::Peripherals::take().unwrap();
+    let mut systick = systick::new(cortex_m::Peripherals::take().unwrap().SYST);
+    p.ccm
+        .pll1
+        .set_arm_clock(bsp::hal::ccm::PLL1::ARM_HZ, &mut p.ccm.handle, &mut p.dcdc);
+    usb_io::init().unwrap();
+    systick.delay_ms(5_000);
+
+    let greeting = Box::new(String::from("Hello from the heap!"));
+    log::info!("{}", greeting);
+
+    let dtcm = bsp::heap::dtcm();
+    let layout = Layout::new::<[u32; 64]>();
+    // Safety: the layout has a non-zero size, and we
+    // deallocate the memory using the same layout.
+    let block = unsafe { dtcm.alloc(layout) };
+    log::info!("DTCM block at {:?}, {} bytes used", block, dtcm.used());
+    unsafe { dtcm.dealloc(block, layout) };
+
+    let mut values: Vec<u32> = Vec::new();
+    loop {
+        values.push(values.len() as u32);
+        log::info!(
+            "Vec has {} elements, capacity {}. OCRAM2 heap: {} used, {} free",
+            values.len(),
+            values.capacity(),
+            bsp::heap::ocram().used(),
+            bsp::heap::ocram().free()
+        );
+        systick.delay_ms(500);
+    }
+}
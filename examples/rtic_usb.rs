@@ -56,7 +56,12 @@ mod app {
         led.set_high().unwrap();
 
         // Initialize the USB system
-        let (poller, _) = bsp::usb::init(USB1::take().unwrap(), Default::default()).unwrap();
+        let (poller, _, _) = bsp::usb::init(
+            USB1::take().unwrap(),
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
 
         (
             Shared {},
@@ -45,7 +45,8 @@ mod app {
         let mut led = bsp::configure_led(pins.p13);
         led.set();
 
-        let (poller, reader, writer) = bsp::usb::split(USB1::take().unwrap()).unwrap();
+        let (poller, reader, writer) =
+            bsp::usb::split(USB1::take().unwrap(), Default::default()).unwrap();
 
         (
             Shared {},
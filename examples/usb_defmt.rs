@@ -0,0 +1,50 @@
+//! Demonstrates `defmt` logging over USB.
+//!
+//! Build with the `defmt.x` linker script, in addition to `t4link.x`:
+//!
+//! ```text
+//! RUSTFLAGS="-C link-arg=-Tt4link.x -C link-arg=-Tdefmt.x" \
+//!     cargo build --example usb_defmt --features rt,defmt --target thumbv7em-none-eabihf --release
+//! ```
+//!
+//! Success criteria: after you program your Teensy, `defmt-monitor`
+//! prints the decoded log messages.
+//!
+//! ```text
+//! cargo run --package tools --features defmt-monitor --bin defmt-monitor -- \
+//!     target/thumbv7em-none-eabihf/release/examples/usb_defmt /dev/ttyACM0
+//! ```
+
+#![no_std]
+#![no_main]
+
+mod systick;
+mod usb_io;
+
+use teensy4_panic as _;
+
+use cortex_m_rt as rt;
+use teensy4_bsp as bsp;
+
+#[rt::entry]
+fn main() -> ! {
+    let p = bsp::Peripherals::take().unwrap();
+    let pins = bsp::pins::t40::from_pads(p.iomuxc);
+    let mut systick = systick::new(cortex_m::Peripherals::take().unwrap().SYST);
+    // Initializes both loggers. We only use defmt.
+    let _ = usb_io::init().unwrap();
+
+    systick.delay_ms(2000);
+    let mut led = bsp::configure_led(pins.p13);
+    let mut counter: u32 = 0;
+    loop {
+        defmt::error!("Something terrible happened! Count {=u32}", counter);
+        defmt::warn!("Something happened, but we fixed it");
+        defmt::info!("It's {=i32}'C outside", 31);
+        defmt::debug!("Sleeping for 500ms...");
+        defmt::trace!("{} + {} = {}", 3, 2, 3 + 2);
+        counter = counter.wrapping_add(1);
+        led.toggle();
+        systick.delay_ms(500);
+    }
+}
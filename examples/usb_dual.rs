@@ -0,0 +1,44 @@
+//! Demonstrates two USB serial ports. Log messages appear on
+//! the first serial port, and the second serial port echoes
+//! everything that it receives.
+
+#![no_std]
+#![no_main]
+
+mod systick;
+mod usb_io;
+
+use teensy4_panic as _;
+
+use cortex_m_rt as rt;
+use teensy4_bsp as bsp;
+
+#[rt::entry]
+fn main() -> ! {
+    let p = bsp::Peripherals::take().unwrap();
+    let pins = bsp::pins::t40::from_pads(p.iomuxc);
+    let mut systick = systick::new(cortex_m::Peripherals::take().unwrap().SYST);
+    let (mut reader, mut writer) = usb_io::init_dual().unwrap();
+
+    systick.delay_ms(2000);
+    let mut led = bsp::configure_led(pins.p13);
+    let mut buffer = [0; 256];
+    let mut echoed: usize = 0;
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(bytes_read) if bytes_read > 0 => {
+                let mut offset = 0;
+                while offset < bytes_read {
+                    match writer.write(&buffer[offset..bytes_read]) {
+                        Ok(written) => offset += written,
+                        Err(_) => break,
+                    }
+                }
+                echoed += bytes_read;
+                log::info!("Echoed {} bytes ({} total)", bytes_read, echoed);
+                led.toggle();
+            }
+            _ => systick.delay_ms(10),
+        }
+    }
+}
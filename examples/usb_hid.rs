@@ -0,0 +1,66 @@
+//! Demonstrates a USB keyboard and mouse. Every five seconds, the
+//! Teensy types a message, then moves the mouse in a small square.
+//!
+//! Make sure that the keyboard's focus is somewhere harmless, like
+//! a text editor, before you run this example.
+
+#![no_std]
+#![no_main]
+
+mod systick;
+mod usb_io;
+
+use teensy4_panic as _;
+
+use bsp::usb::hid;
+use cortex_m_rt as rt;
+use teensy4_bsp as bsp;
+
+const MESSAGE: &str = "Hello from the Teensy 4!\n";
+
+#[rt::entry]
+fn main() -> ! {
+    let p = bsp::Peripherals::take().unwrap();
+    let pins = bsp::pins::t40::from_pads(p.iomuxc);
+    let mut systick = systick::new(cortex_m::Peripherals::take().unwrap().SYST);
+    let devices = usb_io::split_hid(hid::Config {
+        keyboard: true,
+        mouse: true,
+        ..Default::default()
+    })
+    .unwrap();
+    let keyboard = devices.keyboard.unwrap();
+    let mouse = devices.mouse.unwrap();
+
+    let mut led = bsp::configure_led(pins.p13);
+    loop {
+        systick.delay_ms(5000);
+        led.toggle();
+
+        for ch in MESSAGE.chars() {
+            if let Some(report) = hid::KeyboardReport::ascii(ch) {
+                send(&mut systick, || keyboard.send(&report));
+                // Release the key, so that repeated characters are typed.
+                send(&mut systick, || keyboard.send(&Default::default()));
+            }
+        }
+
+        for &(x, y) in [(10, 0), (0, 10), (-10, 0), (0, -10)].iter() {
+            for _ in 0..10 {
+                let report = hid::MouseReport {
+                    x,
+                    y,
+                    ..Default::default()
+                };
+                send(&mut systick, || mouse.send(&report));
+            }
+        }
+    }
+}
+
+/// Retry `send` until the host accepts the report
+fn send(systick: &mut systick::SysTick, mut send: impl FnMut() -> Result<bool, bsp::usb::Error>) {
+    while let Ok(false) = send() {
+        systick.delay_ms(1);
+    }
+}
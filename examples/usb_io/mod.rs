@@ -26,7 +26,7 @@ use teensy4_bsp as bsp;
 /// Panics if the imxrt-ral USB1 instance is already taken.
 pub fn init() -> Result<bsp::usb::Reader, bsp::usb::Error> {
     let inst = USB1::take().unwrap();
-    bsp::usb::init(inst, Default::default()).map(|(poller, reader)| {
+    bsp::usb::init(inst, Default::default(), Default::default()).map(|(poller, reader, _)| {
         setup(poller);
         reader
     })
@@ -44,12 +44,130 @@ pub fn init() -> Result<bsp::usb::Reader, bsp::usb::Error> {
 /// Panics if the imxrt-ral USB1 instance is already taken.
 pub fn split() -> Result<(bsp::usb::Reader, bsp::usb::Writer), bsp::usb::Error> {
     let inst = USB1::take().unwrap();
-    bsp::usb::split(inst).map(|(poller, reader, writer)| {
+    bsp::usb::split(inst, Default::default()).map(|(poller, reader, writer)| {
         setup(poller);
         (reader, writer)
     })
 }
 
+/// Initialize the USB logging system on one serial port, and return
+/// the reader and writer for a second serial port. Prepares the USB ISR
+/// with the poller
+///
+/// When `init_dual` returns, the USB interrupt will be enabled,
+/// and the host may begin to interface the device.
+/// You should only call this once.
+///
+/// # Panics
+///
+/// Panics if the imxrt-ral USB1 instance is already taken.
+pub fn init_dual() -> Result<(bsp::usb::Reader, bsp::usb::Writer), bsp::usb::Error> {
+    let inst = USB1::take().unwrap();
+    let identity = bsp::usb::UsbIdentity {
+        // Teensyduino's product ID for dual serial
+        product_id: 0x048B,
+        product: "Dual Serial",
+        ..Default::default()
+    };
+    bsp::usb::init_dual(inst, identity, Default::default()).map(|(poller, reader, writer, _)| {
+        setup(poller);
+        (reader, writer)
+    })
+}
+
+/// Initialize the USB stack with HID interfaces, and prepares
+/// the USB ISR with the poller
+///
+/// When `split_hid` returns, the USB interrupt will be enabled,
+/// and the host may begin to interface the device.
+/// You should only call this once.
+///
+/// # Panics
+///
+/// Panics if the imxrt-ral USB1 instance is already taken.
+pub fn split_hid(config: bsp::usb::hid::Config) -> Result<bsp::usb::hid::Devices, bsp::usb::Error> {
+    let inst = USB1::take().unwrap();
+    let identity = bsp::usb::UsbIdentity {
+        // Teensyduino's product ID for keyboard, mouse, and joystick
+        product_id: 0x0482,
+        product: "Keyboard/Mouse/Joystick",
+        ..Default::default()
+    };
+    bsp::usb::split_hid(inst, identity, config).map(|(poller, devices)| {
+        setup(poller);
+        devices
+    })
+}
+
+/// Initialize the USB stack with a MIDI interface, and prepares
+/// the USB ISR with the poller
+///
+/// When `split_midi` returns, the USB interrupt will be enabled,
+/// and the host may begin to interface the device.
+/// You should only call this once.
+///
+/// # Panics
+///
+/// Panics if the imxrt-ral USB1 instance is already taken.
+pub fn split_midi() -> Result<bsp::usb::midi::Midi, bsp::usb::Error> {
+    let inst = USB1::take().unwrap();
+    let identity = bsp::usb::UsbIdentity {
+        // Teensyduino's product ID for MIDI
+        product_id: 0x0485,
+        product: "MIDI",
+        ..Default::default()
+    };
+    bsp::usb::split_midi(inst, identity).map(|(poller, midi)| {
+        setup(poller);
+        midi
+    })
+}
+
+/// Initialize the USB stack with a mass storage interface, and prepares
+/// the USB ISR with the poller
+///
+/// When `split_msc` returns, the USB interrupt will be enabled,
+/// and the host may begin to interface the device.
+/// You should only call this once.
+///
+/// # Panics
+///
+/// Panics if the imxrt-ral USB1 instance is already taken.
+pub fn split_msc(
+    storage: &'static mut dyn bsp::usb::msc::BlockDevice,
+) -> Result<(), bsp::usb::Error> {
+    let inst = USB1::take().unwrap();
+    let identity = bsp::usb::UsbIdentity {
+        product_id: 0x0488,
+        product: "Mass Storage",
+        ..Default::default()
+    };
+    bsp::usb::split_msc(inst, identity, storage).map(setup)
+}
+
+/// Initialize the USB stack with a network interface, and prepares
+/// the USB ISR with the poller
+///
+/// When `split_net` returns, the USB interrupt will be enabled,
+/// and the host may begin to interface the device.
+/// You should only call this once.
+///
+/// # Panics
+///
+/// Panics if the imxrt-ral USB1 instance is already taken.
+pub fn split_net() -> Result<bsp::usb::net::Net, bsp::usb::Error> {
+    let inst = USB1::take().unwrap();
+    let identity = bsp::usb::UsbIdentity {
+        product_id: 0x0490,
+        product: "Network",
+        ..Default::default()
+    };
+    bsp::usb::split_net(inst, identity).map(|(poller, net)| {
+        setup(poller);
+        net
+    })
+}
+
 /// Setup the USB ISR with the USB poller
 fn setup(poller: bsp::usb::Poller) {
     static POLLER: Mutex<RefCell<Option<bsp::usb::Poller>>> = Mutex::new(RefCell::new(None));
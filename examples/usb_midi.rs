@@ -0,0 +1,62 @@
+//! Demonstrates a USB MIDI device. The Teensy plays a C major
+//! scale on channel one, and toggles the LED for each note. It
+//! answers a SysEx identity request with an identity reply.
+//!
+//! Connect the Teensy to a synthesizer, or a MIDI monitor, to
+//! hear or see the notes.
+
+#![no_std]
+#![no_main]
+
+mod systick;
+mod usb_io;
+
+use teensy4_panic as _;
+
+use bsp::usb::midi::{MidiMessage, SysExReader};
+use cortex_m_rt as rt;
+use teensy4_bsp as bsp;
+
+const SCALE: [u8; 8] = [60, 62, 64, 65, 67, 69, 71, 72];
+
+/// A universal non-realtime identity request, without the start and end bytes
+const IDENTITY_REQUEST: [u8; 4] = [0x7E, 0x7F, 0x06, 0x01];
+/// The identity reply, using the non-commercial manufacturer ID
+const IDENTITY_REPLY: [u8; 13] = [
+    0x7E, 0x7F, 0x06, 0x02, 0x7D, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+];
+
+#[rt::entry]
+fn main() -> ! {
+    let p = bsp::Peripherals::take().unwrap();
+    let pins = bsp::pins::t40::from_pads(p.iomuxc);
+    let mut systick = systick::new(cortex_m::Peripherals::take().unwrap().SYST);
+    let mut midi = usb_io::split_midi().unwrap();
+    let mut sysex: SysExReader<32> = SysExReader::new();
+
+    let mut led = bsp::configure_led(pins.p13);
+    loop {
+        for &note in SCALE.iter() {
+            led.toggle();
+            let on = MidiMessage::NoteOn {
+                channel: 0,
+                note,
+                velocity: 100,
+            };
+            midi.send(0, &on).ok();
+            systick.delay_ms(250);
+            let off = MidiMessage::NoteOff {
+                channel: 0,
+                note,
+                velocity: 0,
+            };
+            midi.send(0, &off).ok();
+
+            while let Ok(Some(packet)) = midi.read() {
+                if sysex.push(packet) == Some(&IDENTITY_REQUEST[..]) {
+                    midi.send_sysex(packet.cable(), &IDENTITY_REPLY).ok();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Demonstrates a USB drive backed by RAM. The host sees a
+//! 128 KiB drive, and offers to format it. The LED turns on
+//! once the host configures the device.
+//!
+//! The drive's contents are lost when the Teensy resets.
+
+#![no_std]
+#![no_main]
+
+mod usb_io;
+
+use teensy4_panic as _;
+
+use bsp::usb::msc::RamDisk;
+use cortex_m_rt as rt;
+use teensy4_bsp as bsp;
+
+const BLOCK_SIZE: usize = 512;
+const BLOCK_COUNT: usize = 256;
+
+#[rt::entry]
+fn main() -> ! {
+    static mut BLOCKS: [u8; BLOCK_SIZE * BLOCK_COUNT] = [0; BLOCK_SIZE * BLOCK_COUNT];
+    static mut DISK: Option<RamDisk<'static>> = None;
+
+    let p = bsp::Peripherals::take().unwrap();
+    let pins = bsp::pins::t40::from_pads(p.iomuxc);
+    let disk = DISK.insert(RamDisk::new(BLOCKS, BLOCK_SIZE));
+    usb_io::split_msc(disk).unwrap();
+
+    let mut led = bsp::configure_led(pins.p13);
+    loop {
+        if bsp::usb::state() == bsp::usb::DeviceState::Configured {
+            led.set();
+        } else {
+            led.clear();
+        }
+        cortex_m::asm::wfi();
+    }
+}
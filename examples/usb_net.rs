@@ -0,0 +1,92 @@
+//! Demonstrates a USB network adapter, and a smoltcp HTTP server.
+//! The Teensy serves a small web page on port 80 of 192.168.7.1.
+//! The LED turns on while the host enables the network interface.
+//!
+//! There's no DHCP server. Give the host's new network interface
+//! an address on the same subnet, like 192.168.7.2/24, then browse
+//! to http://192.168.7.1/. On Linux,
+//!
+//!     ip addr add 192.168.7.2/24 dev <interface>
+//!     ip link set <interface> up
+//!     curl http://192.168.7.1/
+
+#![no_std]
+#![no_main]
+
+mod systick;
+mod usb_io;
+
+use teensy4_panic as _;
+
+use cortex_m_rt as rt;
+use smoltcp::iface::{Config, Interface, SocketSet, SocketStorage};
+use smoltcp::socket::tcp;
+use smoltcp::time::Instant;
+use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr};
+use teensy4_bsp as bsp;
+
+const ADDRESS: IpAddress = IpAddress::v4(192, 168, 7, 1);
+const PORT: u16 = 80;
+
+const RESPONSE: &[u8] = b"HTTP/1.0 200 OK\r\n\
+Content-Type: text/html\r\n\
+Connection: close\r\n\
+\r\n\
+<!DOCTYPE html>\
+<html><head><title>Teensy 4</title></head>\
+<body><h1>Hello from a Teensy 4!</h1>\
+<p>This page came over a USB network interface.</p></body></html>";
+
+#[rt::entry]
+fn main() -> ! {
+    static mut SOCKETS: [SocketStorage<'static>; 1] = [SocketStorage::EMPTY; 1];
+    static mut RX: [u8; 1024] = [0; 1024];
+    static mut TX: [u8; 1024] = [0; 1024];
+
+    let p = bsp::Peripherals::take().unwrap();
+    let pins = bsp::pins::t40::from_pads(p.iomuxc);
+    let mut systick = systick::new(cortex_m::Peripherals::take().unwrap().SYST);
+    let mut led = bsp::configure_led(pins.p13);
+
+    let mut net = usb_io::split_net().unwrap();
+    let config = Config::new(EthernetAddress(net.mac_address()).into());
+    let mut iface = Interface::new(config, &mut net, Instant::ZERO);
+    iface.update_ip_addrs(|addrs| {
+        addrs.push(IpCidr::new(ADDRESS, 24)).unwrap();
+    });
+
+    let mut sockets = SocketSet::new(&mut SOCKETS[..]);
+    let server = sockets.add(tcp::Socket::new(
+        tcp::SocketBuffer::new(&mut RX[..]),
+        tcp::SocketBuffer::new(&mut TX[..]),
+    ));
+
+    // SysTick delays keep time, so the clock runs a little slow.
+    let mut millis: i64 = 0;
+    loop {
+        if net.is_connected() {
+            led.set();
+        } else {
+            led.clear();
+        }
+
+        iface.poll(Instant::from_millis(millis), &mut net, &mut sockets);
+        let socket = sockets.get_mut::<tcp::Socket>(server);
+        if !socket.is_open() {
+            socket.listen(PORT).unwrap();
+        }
+        // Answer any request once it arrives, then close the connection.
+        if socket.may_recv() {
+            let received = socket.recv(|data| (data.len(), data.len())).unwrap_or(0);
+            if received > 0 && socket.can_send() {
+                socket.send_slice(RESPONSE).ok();
+                socket.close();
+            }
+        } else if socket.may_send() {
+            socket.close();
+        }
+
+        systick.delay_ms(1);
+        millis += 1;
+    }
+}
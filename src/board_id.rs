@@ -0,0 +1,122 @@
+//! Board identification from the OCOTP fuses
+
+use core::fmt;
+
+const OCOTP_CFG0: *const u32 = 0x401F_4410 as *const u32;
+const OCOTP_CFG1: *const u32 = 0x401F_4420 as *const u32;
+const OCOTP_MAC0: *const u32 = 0x401F_4620 as *const u32;
+const OCOTP_MAC1: *const u32 = 0x401F_4630 as *const u32;
+
+/// Identifies a Teensy 4
+///
+/// Acquire a `BoardId` with [`board_id`](crate::board_id()). The values
+/// come from fuses that are programmed during manufacturing, so they don't
+/// change across resets or reprogramming.
+///
+/// The `Display` implementation shows the [serial number](BoardId::serial_number).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardId {
+    unique_id: u64,
+    mac0: u32,
+    mac1: u32,
+}
+
+impl BoardId {
+    const fn from_fuses(cfg0: u32, cfg1: u32, mac0: u32, mac1: u32) -> Self {
+        BoardId {
+            unique_id: ((cfg1 as u64) << 32) | cfg0 as u64,
+            mac0,
+            mac1,
+        }
+    }
+
+    /// Returns the i.MX RT processor's 64-bit unique ID
+    pub const fn unique_id(&self) -> u64 {
+        self.unique_id
+    }
+
+    /// Returns the Ethernet MAC address assigned by PJRC
+    ///
+    /// This is the same MAC address that Teensyduino's Ethernet
+    /// libraries use.
+    pub const fn mac_address(&self) -> [u8; 6] {
+        [
+            (self.mac1 >> 8) as u8,
+            self.mac1 as u8,
+            (self.mac0 >> 24) as u8,
+            (self.mac0 >> 16) as u8,
+            (self.mac0 >> 8) as u8,
+            self.mac0 as u8,
+        ]
+    }
+
+    /// Returns the Teensy's serial number
+    ///
+    /// This is the serial number that Teensyduino derives from the MAC
+    /// address. The Teensy Loader, and the Teensyduino USB stack, show
+    /// this value. The BSP's USB stack uses this serial number by default.
+    pub const fn serial_number(&self) -> u32 {
+        let num = self.mac0 & 0xFF_FFFF;
+        if num < 10_000_000 {
+            num * 10
+        } else {
+            num
+        }
+    }
+}
+
+impl fmt::Display for BoardId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.serial_number())
+    }
+}
+
+/// Read the board's identity from the OCOTP fuses
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+///
+/// let id = bsp::board_id();
+/// let serial: u32 = id.serial_number();
+/// let mac: [u8; 6] = id.mac_address();
+/// ```
+pub fn board_id() -> BoardId {
+    // Safety: atomic reads of read-only fuse shadow registers.
+    unsafe {
+        BoardId::from_fuses(
+            OCOTP_CFG0.read_volatile(),
+            OCOTP_CFG1.read_volatile(),
+            OCOTP_MAC0.read_volatile(),
+            OCOTP_MAC1.read_volatile(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoardId;
+
+    #[test]
+    fn serial_number() {
+        // Teensyduino multiplies serial numbers with fewer
+        // than eight digits by ten.
+        let id = BoardId::from_fuses(0, 0, 0x0004_D2F1, 0);
+        assert_eq!(id.serial_number(), 3_161_450);
+        let id = BoardId::from_fuses(0, 0, 0xAB98_9680, 0);
+        assert_eq!(id.serial_number(), 10_000_000);
+        let id = BoardId::from_fuses(0, 0, 0x0098_967F, 0);
+        assert_eq!(id.serial_number(), 99_999_990);
+    }
+
+    #[test]
+    fn mac_address() {
+        let id = BoardId::from_fuses(0, 0, 0xE512_3456, 0x0000_04E9);
+        assert_eq!(id.mac_address(), [0x04, 0xE9, 0xE5, 0x12, 0x34, 0x56]);
+    }
+
+    #[test]
+    fn unique_id() {
+        let id = BoardId::from_fuses(0x89AB_CDEF, 0x0123_4567, 0, 0);
+        assert_eq!(id.unique_id(), 0x0123_4567_89AB_CDEF);
+    }
+}
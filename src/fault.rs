@@ -0,0 +1,638 @@
+//! HardFault capture and reporting
+//!
+//! When the `"fault-handler"` feature is enabled, the BSP defines the `HardFault`
+//! exception handler. The handler captures
+//!
+//! - the stacked exception frame
+//! - the fault status and address registers (CFSR, HFSR, MMFAR, BFAR)
+//! - the exception or interrupt that was active when the fault occurred
+//! - a bounded slice of the stack, starting just above the exception frame
+//!
+//! It saves this [`FaultRecord`] into a small region of OCRAM that survives a software
+//! reset, then resets the processor. On the next boot, use [`take`] to acquire the record,
+//! or [`report`] to log it with the `log` crate. Call `report` once your logger can reach
+//! the host; when logging over USB, that's typically after the host configures the device.
+//!
+//! The decoding methods on [`FaultRecord`] work on any target, so you can decode records
+//! that you transferred off of the board.
+
+use core::fmt;
+
+/// The maximum number of stack words captured in a [`FaultRecord`]
+pub const STACK_WORDS: usize = 32;
+
+/// The registers that the processor pushes onto the stack when it
+/// takes an exception
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StackedFrame {
+    pub r0: u32,
+    pub r1: u32,
+    pub r2: u32,
+    pub r3: u32,
+    pub r12: u32,
+    pub lr: u32,
+    pub pc: u32,
+    pub xpsr: u32,
+}
+
+/// The execution context that was active when the fault occurred
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// The processor was in thread mode, typically running `main()`
+    Thread,
+    /// The processor was handling a system exception, like `SysTick`
+    ///
+    /// The value is the exception number.
+    Exception(u8),
+    /// The processor was handling an interrupt
+    ///
+    /// The value is the IRQ number, which you may compare against
+    /// [`interrupt`](crate::interrupt) values.
+    Interrupt(u16),
+}
+
+/// A fault cause decoded from the CFSR and HFSR
+///
+/// Use [`FaultRecord::causes`] to iterate over all causes in a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cause {
+    /// MemManage: instruction access violation
+    InstructionAccessViolation,
+    /// MemManage: data access violation
+    DataAccessViolation,
+    /// MemManage: fault on unstacking for an exception return
+    MemManageUnstacking,
+    /// MemManage: fault on stacking for exception entry
+    MemManageStacking,
+    /// MemManage: fault during floating-point lazy state preservation
+    MemManageLazyStacking,
+    /// BusFault: instruction bus error
+    InstructionBusError,
+    /// BusFault: precise data bus error
+    PreciseDataBusError,
+    /// BusFault: imprecise data bus error
+    ImpreciseDataBusError,
+    /// BusFault: fault on unstacking for an exception return
+    BusFaultUnstacking,
+    /// BusFault: fault on stacking for exception entry
+    BusFaultStacking,
+    /// BusFault: fault during floating-point lazy state preservation
+    BusFaultLazyStacking,
+    /// UsageFault: undefined instruction
+    UndefinedInstruction,
+    /// UsageFault: invalid state, like executing with EPSR.T cleared
+    InvalidState,
+    /// UsageFault: invalid PC load, typically a bad EXC_RETURN value
+    InvalidPc,
+    /// UsageFault: no coprocessor
+    NoCoprocessor,
+    /// UsageFault: unaligned access
+    Unaligned,
+    /// UsageFault: divide by zero
+    DivideByZero,
+    /// HardFault: bus fault on a vector table read
+    VectorTable,
+    /// HardFault: escalated from a configurable fault
+    Forced,
+    /// HardFault: debug event
+    Debug,
+}
+
+impl Cause {
+    /// A short description of the cause
+    pub const fn description(self) -> &'static str {
+        match self {
+            Cause::InstructionAccessViolation => "MemManage: instruction access violation",
+            Cause::DataAccessViolation => "MemManage: data access violation",
+            Cause::MemManageUnstacking => "MemManage: fault on exception return unstacking",
+            Cause::MemManageStacking => "MemManage: fault on exception entry stacking",
+            Cause::MemManageLazyStacking => "MemManage: fault on lazy FP state preservation",
+            Cause::InstructionBusError => "BusFault: instruction bus error",
+            Cause::PreciseDataBusError => "BusFault: precise data bus error",
+            Cause::ImpreciseDataBusError => "BusFault: imprecise data bus error",
+            Cause::BusFaultUnstacking => "BusFault: fault on exception return unstacking",
+            Cause::BusFaultStacking => "BusFault: fault on exception entry stacking",
+            Cause::BusFaultLazyStacking => "BusFault: fault on lazy FP state preservation",
+            Cause::UndefinedInstruction => "UsageFault: undefined instruction",
+            Cause::InvalidState => "UsageFault: invalid state",
+            Cause::InvalidPc => "UsageFault: invalid PC load",
+            Cause::NoCoprocessor => "UsageFault: no coprocessor",
+            Cause::Unaligned => "UsageFault: unaligned access",
+            Cause::DivideByZero => "UsageFault: divide by zero",
+            Cause::VectorTable => "HardFault: vector table read error",
+            Cause::Forced => "HardFault: escalated from a configurable fault",
+            Cause::Debug => "HardFault: debug event",
+        }
+    }
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+//
+// CFSR and HFSR bits. See the ARMv7-M Architecture Reference
+// Manual, B3.2.15 and B3.2.16.
+//
+
+/// CFSR.MMARVALID: MMFAR holds a valid fault address
+const MMARVALID: u32 = 1 << 7;
+/// CFSR.BFARVALID: BFAR holds a valid fault address
+const BFARVALID: u32 = 1 << 15;
+
+const CFSR_CAUSES: [(u32, Cause); 17] = [
+    (1 << 0, Cause::InstructionAccessViolation),
+    (1 << 1, Cause::DataAccessViolation),
+    (1 << 3, Cause::MemManageUnstacking),
+    (1 << 4, Cause::MemManageStacking),
+    (1 << 5, Cause::MemManageLazyStacking),
+    (1 << 8, Cause::InstructionBusError),
+    (1 << 9, Cause::PreciseDataBusError),
+    (1 << 10, Cause::ImpreciseDataBusError),
+    (1 << 11, Cause::BusFaultUnstacking),
+    (1 << 12, Cause::BusFaultStacking),
+    (1 << 13, Cause::BusFaultLazyStacking),
+    (1 << 16, Cause::UndefinedInstruction),
+    (1 << 17, Cause::InvalidState),
+    (1 << 18, Cause::InvalidPc),
+    (1 << 19, Cause::NoCoprocessor),
+    (1 << 24, Cause::Unaligned),
+    (1 << 25, Cause::DivideByZero),
+];
+
+const HFSR_CAUSES: [(u32, Cause); 3] = [
+    (1 << 1, Cause::VectorTable),
+    (1 << 30, Cause::Forced),
+    (1 << 31, Cause::Debug),
+];
+
+/// The state captured by the HardFault handler
+///
+/// See the [module-level documentation](mod@crate::fault) for more information.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultRecord {
+    /// The stacked exception frame
+    pub frame: StackedFrame,
+    /// Configurable Fault Status Register
+    pub cfsr: u32,
+    /// HardFault Status Register
+    pub hfsr: u32,
+    /// MemManage Fault Address Register
+    ///
+    /// Only meaningful if [`mmfar_valid()`](FaultRecord::mmfar_valid) is `true`.
+    pub mmfar: u32,
+    /// BusFault Address Register
+    ///
+    /// Only meaningful if [`bfar_valid()`](FaultRecord::bfar_valid) is `true`.
+    pub bfar: u32,
+    /// The address of the first word in `stack`
+    pub stack_address: u32,
+    stack_len: u32,
+    stack: [u32; STACK_WORDS],
+}
+
+impl FaultRecord {
+    /// Create a record from the exception frame and fault registers
+    ///
+    /// The record has an empty stack. Use [`push_stack`](FaultRecord::push_stack)
+    /// to capture stack words.
+    pub const fn new(frame: StackedFrame, cfsr: u32, hfsr: u32, mmfar: u32, bfar: u32) -> Self {
+        FaultRecord {
+            frame,
+            cfsr,
+            hfsr,
+            mmfar,
+            bfar,
+            stack_address: 0,
+            stack_len: 0,
+            stack: [0; STACK_WORDS],
+        }
+    }
+
+    /// Append a word to the captured stack
+    ///
+    /// Returns `false` if the stack slice is full, and the word was not saved.
+    pub fn push_stack(&mut self, word: u32) -> bool {
+        match self.stack.get_mut(self.stack_len as usize) {
+            Some(slot) => {
+                *slot = word;
+                self.stack_len += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The stack words captured just above the exception frame
+    ///
+    /// The first word is at [`stack_address`](FaultRecord::stack_address).
+    pub fn stack(&self) -> &[u32] {
+        let len = (self.stack_len as usize).min(STACK_WORDS);
+        &self.stack[..len]
+    }
+
+    /// Returns `true` if MMFAR holds the faulting address
+    pub const fn mmfar_valid(&self) -> bool {
+        self.cfsr & MMARVALID != 0
+    }
+
+    /// Returns `true` if BFAR holds the faulting address
+    pub const fn bfar_valid(&self) -> bool {
+        self.cfsr & BFARVALID != 0
+    }
+
+    /// The faulting data address, if the processor recorded one
+    pub const fn fault_address(&self) -> Option<u32> {
+        if self.mmfar_valid() {
+            Some(self.mmfar)
+        } else if self.bfar_valid() {
+            Some(self.bfar)
+        } else {
+            None
+        }
+    }
+
+    /// The execution context that was active when the fault occurred
+    ///
+    /// This is decoded from the exception number in the stacked xPSR.
+    pub const fn context(&self) -> Context {
+        match self.frame.xpsr & 0x1FF {
+            0 => Context::Thread,
+            exc @ 1..=15 => Context::Exception(exc as u8),
+            exc => Context::Interrupt((exc - 16) as u16),
+        }
+    }
+
+    /// Iterate over all causes indicated by the CFSR and HFSR
+    pub fn causes(&self) -> impl Iterator<Item = Cause> {
+        let cfsr = self.cfsr;
+        let hfsr = self.hfsr;
+        CFSR_CAUSES
+            .iter()
+            .filter(move |(mask, _)| cfsr & mask != 0)
+            .chain(HFSR_CAUSES.iter().filter(move |(mask, _)| hfsr & mask != 0))
+            .map(|(_, cause)| *cause)
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Context::Thread => write!(f, "thread mode"),
+            Context::Exception(exc) => write!(f, "exception {}", exc),
+            Context::Interrupt(irq) => write!(f, "IRQ {}", irq),
+        }
+    }
+}
+
+// The retained record is only used by the HardFault handler. It's always
+// compiled so that we can test it on the host.
+
+/// Marks a valid record in retained memory. Spells "FALT."
+#[cfg_attr(
+    not(all(target_arch = "arm", feature = "fault-handler")),
+    allow(dead_code)
+)]
+const MAGIC: u32 = 0x4641_4C54;
+
+/// A fault record, as it's stored in retained memory
+#[cfg_attr(
+    not(all(target_arch = "arm", feature = "fault-handler")),
+    allow(dead_code)
+)]
+#[repr(C)]
+struct Retained {
+    magic: u32,
+    record: FaultRecord,
+    checksum: u32,
+}
+
+/// Compute the checksum of a record
+///
+/// The checksum is a CRC-32 (IEEE) over the record's words. It guards against
+/// treating the random contents of memory after a power cycle as a record.
+#[cfg_attr(
+    not(all(target_arch = "arm", feature = "fault-handler")),
+    allow(dead_code)
+)]
+fn checksum(record: &FaultRecord) -> u32 {
+    const WORDS: usize = core::mem::size_of::<FaultRecord>() / 4;
+    // Safety: FaultRecord is repr(C), and only contains u32s.
+    let words: &[u32; WORDS] = unsafe { &*(record as *const FaultRecord as *const [u32; WORDS]) };
+    let mut crc: u32 = !0;
+    for byte in words.iter().flat_map(|word| word.to_le_bytes()) {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+impl Retained {
+    #[cfg_attr(
+        not(all(target_arch = "arm", feature = "fault-handler")),
+        allow(dead_code)
+    )]
+    fn is_valid(&self) -> bool {
+        self.magic == MAGIC && self.checksum == checksum(&self.record)
+    }
+}
+
+#[cfg(all(target_arch = "arm", feature = "fault-handler"))]
+mod handler {
+    use super::{checksum, FaultRecord, Retained, StackedFrame, MAGIC};
+    use core::mem::MaybeUninit;
+    use cortex_m::peripheral::SCB;
+    use cortex_m_rt::{exception, ExceptionFrame};
+
+    /// Placed at the top of OCRAM2, outside of the heap. See `t4link.x`.
+    ///
+    /// The runtime doesn't touch this memory during startup, so a record written
+    /// before a software reset is available in the next boot.
+    #[link_section = ".retained"]
+    static mut RETAINED: MaybeUninit<Retained> = MaybeUninit::uninit();
+
+    /// Start of DTCM, which holds the stack
+    const DTCM_START: u32 = 0x2000_0000;
+
+    /// Returns the address just beyond the top of the stack
+    fn stack_top() -> u32 {
+        extern "C" {
+            static __stack_start: u32;
+        }
+        unsafe { &__stack_start as *const u32 as u32 }
+    }
+
+    #[exception]
+    unsafe fn HardFault(ef: &ExceptionFrame) -> ! {
+        let scb = &*SCB::PTR;
+        let frame = StackedFrame {
+            r0: ef.r0(),
+            r1: ef.r1(),
+            r2: ef.r2(),
+            r3: ef.r3(),
+            r12: ef.r12(),
+            lr: ef.lr(),
+            pc: ef.pc(),
+            xpsr: ef.xpsr(),
+        };
+        let mut record = FaultRecord::new(
+            frame,
+            scb.cfsr.read(),
+            scb.hfsr.read(),
+            scb.mmfar.read(),
+            scb.bfar.read(),
+        );
+
+        // Only capture the stack if it looks like the stack we allocated
+        // in DTCM. If the stack pointer is corrupt, reading through it could
+        // fault again.
+        let mut addr =
+            ef as *const ExceptionFrame as u32 + core::mem::size_of::<ExceptionFrame>() as u32;
+        record.stack_address = addr;
+        let top = stack_top();
+        if (DTCM_START..top).contains(&addr) {
+            while addr < top && record.push_stack((addr as *const u32).read_volatile()) {
+                addr += 4;
+            }
+        }
+
+        let retained = core::ptr::addr_of_mut!(RETAINED).cast::<Retained>();
+        core::ptr::write_volatile(
+            retained,
+            Retained {
+                magic: MAGIC,
+                checksum: checksum(&record),
+                record,
+            },
+        );
+
+        // The record must reach memory before the reset.
+        cortex_m::Peripherals::steal()
+            .SCB
+            .clean_dcache_by_address(retained as usize, core::mem::size_of::<Retained>());
+        SCB::sys_reset()
+    }
+
+    pub fn take() -> Option<FaultRecord> {
+        cortex_m::interrupt::free(|_| unsafe {
+            let retained = core::ptr::addr_of_mut!(RETAINED).cast::<Retained>();
+            let candidate: Retained = core::ptr::read_volatile(retained);
+            // Invalidate the record so that we only report it once.
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*retained).magic), 0);
+            if candidate.is_valid() {
+                Some(candidate.record)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Take the fault record saved before the last reset
+///
+/// Returns `None` if there was no fault before the last reset, or if the record was
+/// already taken.
+#[cfg(all(target_arch = "arm", feature = "fault-handler"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "fault-handler")))]
+pub fn take() -> Option<FaultRecord> {
+    handler::take()
+}
+
+/// Log the fault record saved before the last reset
+///
+/// Takes the record with [`take`], and logs it at the error level. Returns `true` if
+/// there was a record to report. The logging target is `teensy4_bsp::fault`.
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+/// use bsp::hal::ral::usb::USB1;
+///
+/// let (poller, reader, filters) =
+///     bsp::usb::init(USB1::take().unwrap(), Default::default(), Default::default()).unwrap();
+/// // Prepare the USB ISR, and wait for the host...
+///
+/// bsp::fault::report();
+/// ```
+#[cfg(all(target_arch = "arm", feature = "fault-handler"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "fault-handler")))]
+pub fn report() -> bool {
+    match take() {
+        Some(record) => {
+            log_record(&record);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(all(target_arch = "arm", feature = "fault-handler"))]
+fn log_record(record: &FaultRecord) {
+    let frame = &record.frame;
+    log::error!(
+        "HardFault in {} before the last reset. PC = {:#010X}, LR = {:#010X}",
+        record.context(),
+        frame.pc,
+        frame.lr
+    );
+    log::error!(
+        "R0 = {:#010X}, R1 = {:#010X}, R2 = {:#010X}, R3 = {:#010X}, R12 = {:#010X}, xPSR = {:#010X}",
+        frame.r0,
+        frame.r1,
+        frame.r2,
+        frame.r3,
+        frame.r12,
+        frame.xpsr
+    );
+    log::error!(
+        "CFSR = {:#010X}, HFSR = {:#010X}, MMFAR = {:#010X}, BFAR = {:#010X}",
+        record.cfsr,
+        record.hfsr,
+        record.mmfar,
+        record.bfar
+    );
+    for cause in record.causes() {
+        log::error!("{}", cause);
+    }
+    if let Some(addr) = record.fault_address() {
+        log::error!("Faulting address: {:#010X}", addr);
+    }
+    for (idx, row) in record.stack().chunks(4).enumerate() {
+        let addr = record.stack_address + (idx * 16) as u32;
+        match row {
+            [a, b, c, d] => log::error!("{:#010X}: {:08X} {:08X} {:08X} {:08X}", addr, a, b, c, d),
+            [a, b, c] => log::error!("{:#010X}: {:08X} {:08X} {:08X}", addr, a, b, c),
+            [a, b] => log::error!("{:#010X}: {:08X} {:08X}", addr, a, b),
+            [a] => log::error!("{:#010X}: {:08X}", addr, a),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use std::vec::Vec;
+
+    use super::{
+        checksum, Cause, Context, FaultRecord, Retained, StackedFrame, MAGIC, STACK_WORDS,
+    };
+
+    fn record(cfsr: u32, hfsr: u32) -> FaultRecord {
+        FaultRecord::new(StackedFrame::default(), cfsr, hfsr, 0, 0)
+    }
+
+    #[test]
+    fn no_causes() {
+        assert_eq!(record(0, 0).causes().count(), 0);
+    }
+
+    #[test]
+    fn forced_precise_bus_fault() {
+        // PRECISERR | BFARVALID, escalated to HardFault
+        let mut rec = record(0x0000_8200, 0x4000_0000);
+        rec.bfar = 0xDEAD_BEEF;
+        let mut causes = rec.causes();
+        assert_eq!(causes.next(), Some(Cause::PreciseDataBusError));
+        assert_eq!(causes.next(), Some(Cause::Forced));
+        assert_eq!(causes.next(), None);
+        assert!(rec.bfar_valid());
+        assert!(!rec.mmfar_valid());
+        assert_eq!(rec.fault_address(), Some(0xDEAD_BEEF));
+    }
+
+    #[test]
+    fn data_access_violation() {
+        // DACCVIOL | MMARVALID
+        let mut rec = record(0x0000_0082, 0);
+        rec.mmfar = 0x1234_5678;
+        assert_eq!(
+            rec.causes().collect::<Vec<_>>(),
+            [Cause::DataAccessViolation]
+        );
+        assert_eq!(rec.fault_address(), Some(0x1234_5678));
+    }
+
+    #[test]
+    fn usage_faults() {
+        let rec = record(0x0301_0000, 0);
+        assert_eq!(
+            rec.causes().collect::<Vec<_>>(),
+            [
+                Cause::UndefinedInstruction,
+                Cause::Unaligned,
+                Cause::DivideByZero
+            ]
+        );
+        assert_eq!(rec.fault_address(), None);
+    }
+
+    #[test]
+    fn imprecise_bus_fault_has_no_address() {
+        let rec = record(0x0000_0400, 0);
+        assert_eq!(
+            rec.causes().collect::<Vec<_>>(),
+            [Cause::ImpreciseDataBusError]
+        );
+        assert!(!rec.bfar_valid());
+        assert_eq!(rec.fault_address(), None);
+    }
+
+    #[test]
+    fn vector_table_and_debug() {
+        let rec = record(0, 0x8000_0002);
+        assert_eq!(
+            rec.causes().collect::<Vec<_>>(),
+            [Cause::VectorTable, Cause::Debug]
+        );
+    }
+
+    #[test]
+    fn context() {
+        let mut rec = record(0, 0);
+        rec.frame.xpsr = 0x0100_0000;
+        assert_eq!(rec.context(), Context::Thread);
+        rec.frame.xpsr = 0x0100_000F;
+        assert_eq!(rec.context(), Context::Exception(15));
+        rec.frame.xpsr = 0x0100_0000 | (16 + 113);
+        assert_eq!(rec.context(), Context::Interrupt(113));
+    }
+
+    #[test]
+    fn stack_is_bounded() {
+        let mut rec = record(0, 0);
+        assert!(rec.stack().is_empty());
+        for word in 0..STACK_WORDS as u32 {
+            assert!(rec.push_stack(word));
+        }
+        assert!(!rec.push_stack(0xFFFF_FFFF));
+        assert_eq!(rec.stack().len(), STACK_WORDS);
+        assert_eq!(rec.stack().last(), Some(&(STACK_WORDS as u32 - 1)));
+    }
+
+    #[test]
+    fn retained_validity() {
+        let mut rec = record(0x0000_8200, 0x4000_0000);
+        rec.push_stack(0x2000_1000);
+        let mut retained = Retained {
+            magic: MAGIC,
+            checksum: checksum(&rec),
+            record: rec,
+        };
+        assert!(retained.is_valid());
+
+        retained.record.stack[0] ^= 1;
+        assert!(!retained.is_valid());
+        retained.record.stack[0] ^= 1;
+        assert!(retained.is_valid());
+
+        retained.magic = 0;
+        assert!(!retained.is_valid());
+    }
+}
@@ -0,0 +1,205 @@
+//! Heap allocators
+//!
+//! When the `"alloc"` feature is enabled, the BSP registers a `#[global_allocator]`
+//! over the OCRAM2 heap. The heap spans [`heap_start()`](crate::heap_start) through
+//! the end of OCRAM2, and it's ready before your `main()` runs. Use the `alloc` crate's
+//! collections without any other setup:
+//!
+//! ```no_run
+//! extern crate alloc;
+//! use alloc::{boxed::Box, vec::Vec};
+//!
+//! let mut values: Vec<u32> = Vec::new();
+//! values.push(5);
+//! let boxed = Box::new(values);
+//! ```
+//!
+//! [`dtcm()`] returns a second allocator that uses DTCM. The DTCM heap starts at
+//! [`dtcm_heap_start()`](crate::dtcm_heap_start), and it grows towards the stack.
+//! It will not grow beyond the current stack pointer, minus [`STACK_GUARD`] bytes.
+//! Note that the stack may still grow into DTCM heap allocations after they're made.
+//!
+//! # Allocation failures
+//!
+//! When an allocation fails, Rust's default allocation error handler invokes the
+//! panic handler with the message `"memory allocation of N bytes failed"`. If you're
+//! using `teensy4-panic`, you'll see the S.O.S. routine, and maybe a log message.
+//! The default allocation error handler requires Rust 1.68.
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::RefCell,
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+};
+use cortex_m::interrupt::{self, Mutex};
+use linked_list_allocator::Heap;
+
+/// The OCRAM2 heap allocator
+///
+/// The BSP registers this as the `#[global_allocator]`.
+pub struct Allocator(());
+
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator(());
+
+/// State for the OCRAM2 heap
+///
+/// Lives in `.uninit` so that it's initialized before `main()`, and
+/// cortex-m-rt doesn't zero it after we initialize it. See [`init`].
+#[link_section = ".uninit.teensy4_bsp.OCRAM_HEAP"]
+static mut OCRAM_HEAP: MaybeUninit<Mutex<RefCell<Heap>>> = MaybeUninit::uninit();
+
+/// Initialize the OCRAM2 heap
+///
+/// # Safety
+///
+/// Called once by the runtime, before `main()`. The heap
+/// state is in `.uninit`, so this may run before `.bss` is zeroed.
+pub(crate) unsafe fn init() {
+    let mut heap = Heap::empty();
+    heap.init(crate::heap_start() as *mut u8, crate::heap_len());
+    ptr::write(
+        ptr::addr_of_mut!(OCRAM_HEAP).cast(),
+        Mutex::new(RefCell::new(heap)),
+    );
+}
+
+fn ocram_heap() -> &'static Mutex<RefCell<Heap>> {
+    // Safety: initialized before main, and only accessed through
+    // a shared reference thereafter.
+    unsafe { &*ptr::addr_of!(OCRAM_HEAP).cast() }
+}
+
+impl Allocator {
+    /// Returns the number of bytes that are allocated
+    pub fn used(&self) -> usize {
+        interrupt::free(|cs| ocram_heap().borrow(cs).borrow().used())
+    }
+
+    /// Returns the number of bytes that are available for allocation
+    pub fn free(&self) -> usize {
+        interrupt::free(|cs| ocram_heap().borrow(cs).borrow().free())
+    }
+}
+
+unsafe impl GlobalAlloc for Allocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        interrupt::free(|cs| {
+            ocram_heap()
+                .borrow(cs)
+                .borrow_mut()
+                .allocate_first_fit(layout)
+                .map_or(ptr::null_mut(), NonNull::as_ptr)
+        })
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        interrupt::free(|cs| {
+            ocram_heap()
+                .borrow(cs)
+                .borrow_mut()
+                .deallocate(NonNull::new_unchecked(ptr), layout)
+        })
+    }
+}
+
+/// Returns the global allocator
+///
+/// Use this to query the OCRAM2 heap's usage.
+pub fn ocram() -> &'static Allocator {
+    &ALLOCATOR
+}
+
+/// The number of bytes kept free between the DTCM heap and the stack pointer
+///
+/// The DTCM heap won't grow to within this many bytes of the stack pointer,
+/// leaving room for the stack to grow.
+pub const STACK_GUARD: usize = 4 * 1024;
+
+/// The DTCM heap allocator
+///
+/// Acquire the allocator with [`dtcm()`]. `DtcmAllocator` implements `GlobalAlloc`,
+/// so you may use it to allocate memory for your own collections.
+pub struct DtcmAllocator {
+    heap: Mutex<RefCell<Heap>>,
+}
+
+static DTCM_ALLOCATOR: DtcmAllocator = DtcmAllocator {
+    heap: Mutex::new(RefCell::new(Heap::empty())),
+};
+
+/// Returns the DTCM heap allocator
+pub fn dtcm() -> &'static DtcmAllocator {
+    &DTCM_ALLOCATOR
+}
+
+impl DtcmAllocator {
+    /// Returns the number of bytes that are allocated
+    pub fn used(&self) -> usize {
+        interrupt::free(|cs| self.heap.borrow(cs).borrow().used())
+    }
+
+    /// Returns the number of bytes that are available without growing the heap
+    pub fn free(&self) -> usize {
+        interrupt::free(|cs| self.heap.borrow(cs).borrow().free())
+    }
+}
+
+/// Returns the highest address the DTCM heap may use
+fn dtcm_limit() -> usize {
+    let sp = cortex_m::register::msp::read() as usize;
+    sp.saturating_sub(STACK_GUARD) & !(core::mem::size_of::<usize>() - 1)
+}
+
+/// Grow the heap towards the stack so that it can hold `layout`
+///
+/// Returns `false` if the heap cannot grow.
+fn grow(heap: &mut Heap, layout: Layout) -> bool {
+    let limit = dtcm_limit();
+    // Worst case, the allocation needs padding for alignment,
+    // and room for the allocator's bookkeeping.
+    let needed = layout.size() + layout.align() + 2 * core::mem::size_of::<usize>();
+    if heap.bottom().is_null() {
+        let bottom = crate::dtcm_heap_start() as usize;
+        if bottom + needed > limit {
+            return false;
+        }
+        // Safety: the memory between the heap start and the stack is unused.
+        unsafe { heap.init(bottom as *mut u8, needed) };
+    } else {
+        let top = heap.top() as usize;
+        if top + needed > limit {
+            return false;
+        }
+        // Safety: the memory between the heap top and the stack is unused.
+        unsafe { heap.extend(needed) };
+    }
+    true
+}
+
+unsafe impl GlobalAlloc for DtcmAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        interrupt::free(|cs| {
+            let mut heap = self.heap.borrow(cs).borrow_mut();
+            if let Ok(ptr) = heap.allocate_first_fit(layout) {
+                return ptr.as_ptr();
+            }
+            if grow(&mut heap, layout) {
+                heap.allocate_first_fit(layout)
+                    .map_or(ptr::null_mut(), NonNull::as_ptr)
+            } else {
+                ptr::null_mut()
+            }
+        })
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        interrupt::free(|cs| {
+            self.heap
+                .borrow(cs)
+                .borrow_mut()
+                .deallocate(NonNull::new_unchecked(ptr), layout)
+        })
+    }
+}
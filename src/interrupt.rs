@@ -0,0 +1,164 @@
+//! Interrupt numbers, and runtime interrupt handler registration
+//!
+//! This module exports the i.MX RT interrupt numbers, like `USB_OTG1`. Use them
+//! with `cortex_m::peripheral::NVIC`, or to name your `#[interrupt]` handlers.
+//! The interrupt type is [`Interrupt`].
+//!
+//! # Runtime handlers
+//!
+//! When the `"rt"` feature is enabled, the BSP's reset handler copies the vector table
+//! into DTCM, and points VTOR at that copy. Use [`set_handler`] to install an interrupt
+//! handler while your program is running. This lets a driver library install its own
+//! interrupt handler, without requiring an `#[interrupt]` function in your application.
+//!
+//! The [`HandlerToken`] returned by `set_handler` represents ownership of the vector.
+//! While you hold the token, nothing else can set a handler for that interrupt. When
+//! you drop or [`restore`](HandlerToken::restore) the token, the BSP restores the handler
+//! that was linked into your program. That's either your `#[interrupt]` handler, or
+//! `cortex-m-rt`'s default handler. To keep your handler installed forever, `forget`
+//! the token.
+//!
+//! Runtime handlers replace the handlers that RTIC binds to interrupts. Don't install
+//! runtime handlers for interrupts that your RTIC application uses.
+
+pub use crate::hal::ral::interrupt as Interrupt;
+#[doc(no_inline)]
+pub use crate::hal::ral::interrupt::*;
+
+#[cfg(all(target_arch = "arm", feature = "rt"))]
+pub use self::vectors::{set_handler, HandlerToken};
+
+#[cfg(all(target_arch = "arm", feature = "rt"))]
+mod vectors {
+    use super::Interrupt;
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use cortex_m::interrupt::InterruptNumber;
+
+    /// The number of interrupts supported by the processor. Keep this
+    /// in sync with NUM_VECTORS in `start.s`.
+    const NUM_INTERRUPTS: usize = 158;
+
+    /// The number of exception vectors that precede the first interrupt vector
+    const NUM_EXCEPTIONS: usize = 16;
+
+    /// One bit for each interrupt with a runtime handler
+    static REGISTERED: [AtomicU32; (NUM_INTERRUPTS + 31) / 32] = [
+        AtomicU32::new(0),
+        AtomicU32::new(0),
+        AtomicU32::new(0),
+        AtomicU32::new(0),
+        AtomicU32::new(0),
+    ];
+
+    /// Returns the vector table in RAM
+    ///
+    /// VTOR points to the DTCM vector table, which was copied by the reset handler.
+    fn ram_vectors() -> *mut usize {
+        // Safety: atomic read of a read-only register.
+        unsafe { (*cortex_m::peripheral::SCB::PTR).vtor.read() as *mut usize }
+    }
+
+    /// Returns the vector table in FLASH
+    ///
+    /// This is the vector table that was linked into the program.
+    fn flash_vectors() -> *const usize {
+        extern "C" {
+            static __sivectors: usize;
+        }
+        unsafe { &__sivectors }
+    }
+
+    /// Write `vector` into the RAM vector table for `irq`
+    fn write_vector(irq: usize, vector: usize) {
+        // Safety: irq is in bounds of the vector table. A word write
+        // is atomic with respect to the processor's vector fetch.
+        unsafe {
+            ram_vectors()
+                .add(NUM_EXCEPTIONS + irq)
+                .write_volatile(vector)
+        };
+        // Make sure the write completes before the next exception entry.
+        cortex_m::asm::dsb();
+    }
+
+    /// Install `handler` for `interrupt` in the RAM vector table
+    ///
+    /// Returns `None` if a runtime handler is already set for this interrupt, and
+    /// its [`HandlerToken`] is still live. Otherwise, returns a token that represents
+    /// ownership of the interrupt vector.
+    ///
+    /// `set_handler` does not unmask the interrupt. You're responsible for unmasking
+    /// the interrupt once your handler is ready to run.
+    ///
+    /// The processor calls `handler` directly from the vector table, so it must use
+    /// the C ABI; that's why it's an `extern "C" fn()`. A Rust `fn()` has no stable ABI,
+    /// and wrapping it would need a trampoline that finds the handler for the active
+    /// interrupt on every exception entry.
+    ///
+    /// ```no_run
+    /// use teensy4_bsp as bsp;
+    /// use bsp::interrupt;
+    ///
+    /// extern "C" fn on_gpt1() {
+    ///     // Handle the GPT1 interrupt...
+    /// }
+    ///
+    /// let token = interrupt::set_handler(interrupt::GPT1, on_gpt1).unwrap();
+    /// // Safety: the GPT1 handler is ready to run.
+    /// unsafe { cortex_m::peripheral::NVIC::unmask(interrupt::GPT1) };
+    ///
+    /// // Later, restore the handler that was linked into the program.
+    /// cortex_m::peripheral::NVIC::mask(interrupt::GPT1);
+    /// token.restore();
+    /// ```
+    pub fn set_handler(interrupt: Interrupt, handler: extern "C" fn()) -> Option<HandlerToken> {
+        let irq = interrupt.number() as usize;
+        let mask = 1 << (irq % 32);
+        let registered = &REGISTERED[irq / 32];
+        if registered.fetch_or(mask, Ordering::SeqCst) & mask != 0 {
+            return None;
+        }
+        write_vector(irq, handler as usize);
+        Some(HandlerToken { interrupt })
+    }
+
+    /// Represents ownership of an interrupt vector
+    ///
+    /// Acquire a `HandlerToken` from [`set_handler`]. When the token drops, the
+    /// interrupt's linked handler is restored.
+    #[derive(Debug)]
+    pub struct HandlerToken {
+        interrupt: Interrupt,
+    }
+
+    impl HandlerToken {
+        /// Returns the interrupt that this token controls
+        pub fn interrupt(&self) -> Interrupt {
+            self.interrupt
+        }
+
+        /// Replace the handler for this interrupt
+        ///
+        /// Like [`set_handler`], `handler` must use the C ABI.
+        pub fn replace(&mut self, handler: extern "C" fn()) {
+            write_vector(self.interrupt.number() as usize, handler as usize);
+        }
+
+        /// Restore the handler that was linked into the program
+        ///
+        /// This is the same as dropping the token. After this call,
+        /// you may use [`set_handler`] to install a new handler.
+        pub fn restore(self) {}
+    }
+
+    impl Drop for HandlerToken {
+        fn drop(&mut self) {
+            let irq = self.interrupt.number() as usize;
+            // Safety: irq is in bounds of the vector table. The FLASH
+            // vector table is never modified.
+            let linked = unsafe { flash_vectors().add(NUM_EXCEPTIONS + irq).read() };
+            write_vector(irq, linked);
+            REGISTERED[irq / 32].fetch_and(!(1 << (irq % 32)), Ordering::SeqCst);
+        }
+    }
+}
@@ -32,11 +32,15 @@
 //!
 //! The `teensy4-bsp` supports these features:
 //!
-//! | Flag            |         Description                                                   | Default? |
-//! | --------------- | --------------------------------------------------------------------- | -------- |
-//! | `"usb-logging"` | Adds support for logging over USB with the `log` crate                | ✓        |
-//! | `"rt"`          | Adds runtime support using `cortex-m-rt`                              |          |
-//! | `"rtic"`        | Adds support for using the BSP peripherals with RTIC                  |          |
+//! | Flag              |         Description                                                   | Default? |
+//! | ----------------- | --------------------------------------------------------------------- | -------- |
+//! | `"usb-logging"`   | Adds support for logging over USB with the `log` crate                | ✓        |
+//! | `"rt"`            | Adds runtime support using `cortex-m-rt`                              |          |
+//! | `"rtic"`          | Adds support for using the BSP peripherals with RTIC                  |          |
+//! | `"fault-handler"` | Adds a HardFault handler that reports the fault after a reset         |          |
+//! | `"alloc"`         | Registers a global allocator over the OCRAM2 heap; see [`heap`]       |          |
+//! | `"defmt"`         | Adds a `defmt` global logger over USB; implies `"usb-logging"`        |          |
+//! | `"usb-net"`       | Adds a `smoltcp` device over USB networking; implies `"usb-logging"`  |          |
 //!
 //! Proper RTIC support requires that you disable the BSP's default features. You may combine `"rtic"` with
 //! either `"rt"` and `"usb-logging"`.
@@ -82,6 +86,9 @@
 // Need to reference this so that it doesn't get stripped out
 #[cfg(target_arch = "arm")]
 extern crate teensy4_fcb;
+// The defmt macros expect a crate named `defmt`.
+#[cfg(feature = "defmt")]
+extern crate defmt_crate as defmt;
 
 pub use teensy4_pins as pins;
 
@@ -91,12 +98,21 @@ mod rt;
 #[cfg_attr(docsrs, doc(cfg(feature = "usb-logging")))]
 pub mod usb;
 
+mod board_id;
 mod eeprom;
+pub mod fault;
+#[cfg(all(target_arch = "arm", feature = "alloc"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub mod heap;
+pub mod power;
+mod reboot;
+pub use board_id::{board_id, BoardId};
 pub use eeprom::{Eeprom, EepromError, EEPROM_CAPACITY};
+pub use reboot::{reboot, reboot_to_bootloader};
 #[cfg(all(target_arch = "arm", feature = "rt"))]
 pub use rt::{dtcm_heap_start, heap_len, heap_start};
 
-pub use hal::ral::interrupt;
+pub mod interrupt;
 // `rtic` expects these in the root.
 #[doc(hidden)]
 #[cfg(feature = "rtic")]
@@ -0,0 +1,549 @@
+//! Low-power modes and wakeup sources
+//!
+//! Use [`Power`] to put the processor into one of three low-power modes:
+//!
+//! | Mode      | Method                            | Core clock | Wakes from...                 | Resumes after WFI? |
+//! | --------- | --------------------------------- | ---------- | ----------------------------- | ------------------ |
+//! | WAIT      | [`wait`](Power::wait)             | Gated      | Any selected wakeup source    | ✓                  |
+//! | STOP      | [`stop`](Power::stop)             | Off        | Any selected wakeup source    | ✓                  |
+//! | SNVS-off  | [`snvs_off`](Power::snvs_off)     | Off        | ON/OFF button, SRTC alarm     | No; the chip boots |
+//!
+//! # Wakeup sources
+//!
+//! Describe the interrupts that can wake the processor with [`WakeupSources`]. The
+//! BSP unmasks only those interrupts in the General Power Controller (GPC) while the
+//! processor sleeps, then restores the GPC state after wakeup. You're still
+//! responsible for configuring the peripheral to generate the interrupt, and for
+//! unmasking the interrupt in the NVIC. Your interrupt handler runs after the BSP
+//! restores the clocks.
+//!
+//! Not all sources can wake the processor from all modes:
+//!
+//! - GPIO edges only wake the processor when the pin is driven by GPIO1 through GPIO5.
+//!   The fast GPIOs, GPIO6 through GPIO9, cannot wake the processor.
+//! - The PIT can only wake the processor from WAIT. In STOP, the 24MHz oscillator may
+//!   be off, so the PIT doesn't count.
+//! - GPTs can wake the processor from STOP if they're clocked by the 32KHz clock. If you
+//!   select the PIT or a GPT as a wakeup source, the BSP keeps the 24MHz oscillator on
+//!   during STOP.
+//! - Use [`Power::set_srtc_alarm`] to prepare the SRTC alarm.
+//!
+//! # Run mode
+//!
+//! When the BSP runtime starts, it configures the processor to stay in run mode
+//! when executing WFI. See [issue #76](https://github.com/mciantyre/teensy4-rs/issues/76)
+//! for the rationale. `Power` only selects a low-power mode immediately before it
+//! executes WFI, and it restores run mode once the processor wakes. This includes the
+//! workaround for erratum ERR007265, which would otherwise let the processor enter the
+//! low-power mode before WFI. A plain `cortex_m::asm::wfi()` continues to behave the
+//! way it does without this module.
+//!
+//! # Example
+//!
+//! Sleep in STOP mode until the SRTC alarm fires, or until a GPIO2 edge:
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::power::{GpioPort, Power, WakeupSources};
+//!
+//! let mut power = Power::new().unwrap();
+//! let wakeup = WakeupSources::new()
+//!     .srtc_alarm()
+//!     .gpio(GpioPort::Gpio2);
+//!
+//! // Wake in 60 seconds. Assume the SRTC reads 1000 seconds...
+//! power.set_srtc_alarm(1000 + 60);
+//! power.stop(&wakeup);
+//! // Clocks are restored; we're back in run mode.
+//! ```
+
+use crate::interrupt::Interrupt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use cortex_m::interrupt::InterruptNumber;
+
+/// A GPIO port that can wake the processor
+///
+/// Only GPIO1 through GPIO5 are connected to the GPC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpioPort {
+    Gpio1,
+    Gpio2,
+    Gpio3,
+    Gpio4,
+    Gpio5,
+}
+
+//
+// Interrupt numbers for wakeup sources. See the
+// i.MX RT1060 reference manual, table 4-2.
+//
+
+/// IOMUXC GPR interrupt, used for the ERR007265 workaround
+const IRQ_GPR: u16 = 41;
+/// SNVS functional interrupt, which includes the SRTC alarm
+const IRQ_SNVS_HP_WRAPPER: u16 = 46;
+/// GPIO1 combined 0-15. GPIO2 through GPIO5 follow in pairs.
+const IRQ_GPIO1_COMBINED_0_15: u16 = 80;
+const IRQ_GPT1: u16 = 100;
+const IRQ_GPT2: u16 = 101;
+const IRQ_USB_OTG1: u16 = 113;
+const IRQ_PIT: u16 = 122;
+
+/// The GPC cannot mask interrupts below this number
+const GPC_FIRST_IRQ: u16 = 32;
+/// The number of GPC IMR registers
+const GPC_IMR_COUNT: usize = 4;
+
+/// A collection of interrupts that can wake the processor
+///
+/// Use the builder methods to select wakeup sources, then supply the collection
+/// to [`Power::wait`] or [`Power::stop`].
+///
+/// ```
+/// use teensy4_bsp as bsp;
+/// use bsp::power::{GpioPort, WakeupSources};
+///
+/// let wakeup = WakeupSources::new()
+///     .gpio(GpioPort::Gpio1)
+///     .gpt1()
+///     .usb_resume();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WakeupSources {
+    /// One bit for each unmasked interrupt, starting at
+    /// interrupt 32. Same layout as the GPC IMR registers,
+    /// but inverted.
+    enabled: [u32; GPC_IMR_COUNT],
+    /// Set if a source needs the 24MHz oscillator in STOP
+    needs_oscillator: bool,
+}
+
+impl WakeupSources {
+    /// Create an empty collection of wakeup sources
+    ///
+    /// If you enter a low-power mode with no wakeup sources, the processor will
+    /// not wake up.
+    pub const fn new() -> Self {
+        WakeupSources {
+            enabled: [0; GPC_IMR_COUNT],
+            needs_oscillator: false,
+        }
+    }
+
+    fn irq(mut self, irq: u16) -> Self {
+        if let Some(offset) = irq.checked_sub(GPC_FIRST_IRQ) {
+            if let Some(word) = self.enabled.get_mut(offset as usize / 32) {
+                *word |= 1 << (offset % 32);
+            }
+        }
+        self
+    }
+
+    /// Wake on any edge interrupt from a GPIO port
+    ///
+    /// Configure the pin's interrupt with the HAL's GPIO API.
+    pub fn gpio(self, port: GpioPort) -> Self {
+        let low = IRQ_GPIO1_COMBINED_0_15 + 2 * port as u16;
+        self.irq(low).irq(low + 1)
+    }
+
+    /// Wake on a GPT1 interrupt
+    pub fn gpt1(mut self) -> Self {
+        self.needs_oscillator = true;
+        self.irq(IRQ_GPT1)
+    }
+
+    /// Wake on a GPT2 interrupt
+    pub fn gpt2(mut self) -> Self {
+        self.needs_oscillator = true;
+        self.irq(IRQ_GPT2)
+    }
+
+    /// Wake on a PIT interrupt
+    ///
+    /// The PIT can only wake the processor from WAIT.
+    pub fn pit(mut self) -> Self {
+        self.needs_oscillator = true;
+        self.irq(IRQ_PIT)
+    }
+
+    /// Wake when the SRTC alarm fires
+    ///
+    /// Set the alarm with [`Power::set_srtc_alarm`].
+    pub fn srtc_alarm(self) -> Self {
+        self.irq(IRQ_SNVS_HP_WRAPPER)
+    }
+
+    /// Wake when the USB host resumes the bus, or on any other USB interrupt
+    pub fn usb_resume(self) -> Self {
+        self.irq(IRQ_USB_OTG1)
+    }
+
+    /// Wake on any interrupt that's routed through the GPC
+    ///
+    /// Interrupts 0 through 31 (the DMA interrupts) cannot wake the processor.
+    pub fn interrupt(self, interrupt: Interrupt) -> Self {
+        self.irq(interrupt.number())
+    }
+
+    /// Returns `true` if there are no wakeup sources
+    pub fn is_empty(&self) -> bool {
+        self.enabled.iter().all(|word| *word == 0)
+    }
+
+    /// Returns the GPC IMR values for these sources
+    ///
+    /// A set bit in the IMR masks the interrupt.
+    fn imr(&self) -> [u32; GPC_IMR_COUNT] {
+        let mut imr = [0; GPC_IMR_COUNT];
+        for (imr, enabled) in imr.iter_mut().zip(self.enabled.iter()) {
+            *imr = !enabled;
+        }
+        imr
+    }
+}
+
+//
+// Registers. See the i.MX RT1060 reference manual.
+//
+
+const CCM_CBCDR: *mut u32 = 0x400F_C014 as *mut u32;
+const CCM_CBCMR: *mut u32 = 0x400F_C018 as *mut u32;
+const CCM_CDHIPR: *const u32 = 0x400F_C048 as *const u32;
+const CCM_CLPCR: *mut u32 = 0x400F_C054 as *mut u32;
+
+const CBCDR_PERIPH_CLK_SEL: u32 = 1 << 25;
+const CBCDR_PERIPH_CLK2_PODF_MASK: u32 = 0b111 << 27;
+const CBCMR_PERIPH_CLK2_SEL_MASK: u32 = 0b11 << 12;
+const CBCMR_PERIPH_CLK2_SEL_OSC: u32 = 0b01 << 12;
+const CDHIPR_PERIPH_CLK_SEL_BUSY: u32 = 1 << 5;
+
+const CLPCR_LPM_MASK: u32 = 0b11;
+const CLPCR_LPM_WAIT: u32 = 0b01;
+const CLPCR_LPM_STOP: u32 = 0b10;
+const CLPCR_ARM_CLK_DIS_ON_LPM: u32 = 1 << 5;
+const CLPCR_SBYOS: u32 = 1 << 6;
+const CLPCR_VSTBY: u32 = 1 << 8;
+const CLPCR_STBY_COUNT_MASK: u32 = 0b11 << 9;
+const CLPCR_BYPASS_LPM_HS1: u32 = 1 << 19;
+const CLPCR_BYPASS_LPM_HS0: u32 = 1 << 21;
+const CLPCR_MASK_SCU_IDLE: u32 = 1 << 26;
+const CLPCR_MASK_L2CC_IDLE: u32 = 1 << 27;
+
+const CCM_ANALOG_PLL_ARM: *const u32 = 0x400D_8000 as *const u32;
+const CCM_ANALOG_PLL_USB1: *const u32 = 0x400D_8010 as *const u32;
+const CCM_ANALOG_PLL_SYS: *const u32 = 0x400D_8030 as *const u32;
+const PLL_POWERDOWN: u32 = 1 << 12;
+const PLL_USB1_POWER: u32 = 1 << 12;
+const PLL_ENABLE: u32 = 1 << 13;
+const PLL_BYPASS: u32 = 1 << 16;
+const PLL_LOCK: u32 = 1 << 31;
+
+const GPC_IMR1: *mut u32 = 0x400F_4008 as *mut u32;
+
+const IOMUXC_GPR_GPR1: *mut u32 = 0x400A_C004 as *mut u32;
+const GPR1_GINT: u32 = 1 << 12;
+
+const SNVS_LPCR: *mut u32 = 0x400D_4038 as *mut u32;
+const SNVS_LPTAR: *mut u32 = 0x400D_4058 as *mut u32;
+const LPCR_LPTA_EN: u32 = 1 << 1;
+const LPCR_LPWUI_EN: u32 = 1 << 3;
+const LPCR_DP_EN: u32 = 1 << 5;
+const LPCR_TOP: u32 = 1 << 6;
+
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Controls the processor's low-power modes
+///
+/// There's only one of these available in a given program. See the
+/// [module-level documentation](mod@crate::power) for more information.
+pub struct Power(());
+
+impl Power {
+    // The safety of this implementation depends on `Power` being
+    // the only code that modifies CLPCR, the GPC IMRs, and the
+    // clock root selections while it's sleeping. Users who take
+    // `Power` accept that contract.
+    //
+    // Refer to this notice when you see undocumented `unsafe` code in
+    // this block.
+
+    /// Create a `Power` that controls the processor's low-power modes
+    ///
+    /// Returns `None` if the `Power` has already been created.
+    pub fn new() -> Option<Self> {
+        let taken = TAKEN.swap(true, Ordering::SeqCst);
+        if taken {
+            None
+        } else {
+            unsafe { set_bits(IOMUXC_GPR_GPR1, GPR1_GINT) };
+            Some(Power(()))
+        }
+    }
+
+    /// Enter WAIT mode until one of the `wakeup` sources signals
+    ///
+    /// In WAIT, the core clock is gated. Peripherals continue to run.
+    pub fn wait(&mut self, wakeup: &WakeupSources) {
+        let clpcr = unsafe { CCM_CLPCR.read_volatile() };
+        let clpcr = (clpcr & !(CLPCR_LPM_MASK | CLPCR_ARM_CLK_DIS_ON_LPM))
+            | CLPCR_LPM_WAIT
+            | CLPCR_ARM_CLK_DIS_ON_LPM
+            | CLPCR_MASK_SCU_IDLE
+            | CLPCR_MASK_L2CC_IDLE
+            | CLPCR_STBY_COUNT_MASK
+            | CLPCR_BYPASS_LPM_HS0
+            | CLPCR_BYPASS_LPM_HS1;
+        unsafe { sleep(wakeup, clpcr, false) };
+    }
+
+    /// Enter STOP mode until one of the `wakeup` sources signals
+    ///
+    /// In STOP, all clocks are off, and the PLLs power down. Before entering
+    /// STOP, the BSP switches the core to the 24MHz oscillator. After wakeup, the
+    /// BSP waits for the PLLs to lock, then restores the core clock.
+    pub fn stop(&mut self, wakeup: &WakeupSources) {
+        let clpcr = unsafe { CCM_CLPCR.read_volatile() };
+        let mut clpcr = (clpcr & !(CLPCR_LPM_MASK | CLPCR_ARM_CLK_DIS_ON_LPM | CLPCR_SBYOS))
+            | CLPCR_LPM_STOP
+            | CLPCR_ARM_CLK_DIS_ON_LPM
+            | CLPCR_MASK_SCU_IDLE
+            | CLPCR_MASK_L2CC_IDLE
+            | CLPCR_VSTBY
+            | CLPCR_STBY_COUNT_MASK
+            | CLPCR_BYPASS_LPM_HS0
+            | CLPCR_BYPASS_LPM_HS1;
+        if !wakeup.needs_oscillator {
+            clpcr |= CLPCR_SBYOS;
+        }
+        unsafe { sleep(wakeup, clpcr, true) };
+    }
+
+    /// Set the SRTC alarm
+    ///
+    /// `seconds` is compared against the SRTC's seconds counter. When they're equal,
+    /// the alarm fires. Use the HAL's SRTC driver to read the current time.
+    ///
+    /// Supply [`WakeupSources::srtc_alarm`] to wake from WAIT or STOP. SNVS-off always
+    /// wakes on the alarm.
+    pub fn set_srtc_alarm(&mut self, seconds: u32) {
+        unsafe {
+            // The alarm must be disabled while we change its value. These registers
+            // are in the 32KHz domain, so wait for each write to take effect.
+            clear_bits(SNVS_LPCR, LPCR_LPTA_EN);
+            while SNVS_LPCR.read_volatile() & LPCR_LPTA_EN != 0 {}
+            SNVS_LPTAR.write_volatile(seconds);
+            while SNVS_LPTAR.read_volatile() != seconds {}
+            set_bits(SNVS_LPCR, LPCR_LPTA_EN | LPCR_LPWUI_EN);
+            while SNVS_LPCR.read_volatile() & LPCR_LPTA_EN == 0 {}
+        }
+    }
+
+    /// Turn off all power domains except SNVS
+    ///
+    /// This is the lowest power mode. The processor powers off, and only the SRTC keeps
+    /// running. Pressing the ON/OFF button, or the SRTC alarm set with
+    /// [`set_srtc_alarm`](Power::set_srtc_alarm), turns the processor back on. The
+    /// processor then boots as if it was reset. Any state that's not in SNVS is lost.
+    pub fn snvs_off(&mut self) -> ! {
+        cortex_m::interrupt::disable();
+        unsafe { set_bits(SNVS_LPCR, LPCR_DP_EN | LPCR_TOP) };
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+}
+
+unsafe fn set_bits(reg: *mut u32, bits: u32) {
+    reg.write_volatile(reg.read_volatile() | bits);
+}
+
+unsafe fn clear_bits(reg: *mut u32, bits: u32) {
+    reg.write_volatile(reg.read_volatile() & !bits);
+}
+
+/// Unmask `irq` in the GPC
+unsafe fn gpc_unmask(irq: u16) {
+    let offset = irq - GPC_FIRST_IRQ;
+    clear_bits(GPC_IMR1.add(offset as usize / 32), 1 << (offset % 32));
+}
+
+/// Mask `irq` in the GPC
+unsafe fn gpc_mask(irq: u16) {
+    let offset = irq - GPC_FIRST_IRQ;
+    set_bits(GPC_IMR1.add(offset as usize / 32), 1 << (offset % 32));
+}
+
+/// Write CLPCR with a low-power mode
+///
+/// ERR007265: the processor may enter the low-power mode before it executes
+/// WFI. The workaround keeps GPR_IRQ pending (see `Power::new`), and unmasks it
+/// in the GPC while we write CLPCR.
+unsafe fn set_low_power_mode(clpcr: u32) {
+    gpc_unmask(IRQ_GPR);
+    CCM_CLPCR.write_volatile(clpcr);
+    gpc_mask(IRQ_GPR);
+}
+
+/// Clock state that's saved before STOP, and restored after
+struct Clocks {
+    cbcdr: u32,
+    cbcmr: u32,
+    /// PLLs that were locked before STOP
+    plls: [(*const u32, bool); 3],
+}
+
+impl Clocks {
+    /// Save the clock state, then run the core from the oscillator
+    unsafe fn save_and_bypass() -> Self {
+        let pll_running = |pll: *const u32, power: u32, powered_when_set: bool| {
+            let value = pll.read_volatile();
+            let powered = (value & power != 0) == powered_when_set;
+            powered && value & PLL_ENABLE != 0 && value & PLL_BYPASS == 0
+        };
+        let clocks = Clocks {
+            cbcdr: CCM_CBCDR.read_volatile(),
+            cbcmr: CCM_CBCMR.read_volatile(),
+            plls: [
+                (
+                    CCM_ANALOG_PLL_ARM,
+                    pll_running(CCM_ANALOG_PLL_ARM, PLL_POWERDOWN, false),
+                ),
+                (
+                    CCM_ANALOG_PLL_USB1,
+                    pll_running(CCM_ANALOG_PLL_USB1, PLL_USB1_POWER, true),
+                ),
+                (
+                    CCM_ANALOG_PLL_SYS,
+                    pll_running(CCM_ANALOG_PLL_SYS, PLL_POWERDOWN, false),
+                ),
+            ],
+        };
+
+        // periph_clk2 = oscillator, undivided; then periph_clk = periph_clk2
+        CCM_CBCMR.write_volatile(
+            (clocks.cbcmr & !CBCMR_PERIPH_CLK2_SEL_MASK) | CBCMR_PERIPH_CLK2_SEL_OSC,
+        );
+        CCM_CBCDR
+            .write_volatile((clocks.cbcdr & !CBCDR_PERIPH_CLK2_PODF_MASK) | CBCDR_PERIPH_CLK_SEL);
+        while CCM_CDHIPR.read_volatile() & CDHIPR_PERIPH_CLK_SEL_BUSY != 0 {}
+        clocks
+    }
+
+    /// Wait for the PLLs to lock, then restore the clock roots
+    unsafe fn restore(self) {
+        for (pll, running) in self.plls.iter() {
+            if *running {
+                while pll.read_volatile() & PLL_LOCK == 0 {}
+            }
+        }
+        CCM_CBCMR.write_volatile(self.cbcmr);
+        CCM_CBCDR.write_volatile(self.cbcdr);
+        while CCM_CDHIPR.read_volatile() & CDHIPR_PERIPH_CLK_SEL_BUSY != 0 {}
+    }
+}
+
+/// Sleep until a wakeup source signals
+///
+/// # Safety
+///
+/// Caller must own the CCM low-power and clock root settings,
+/// and the GPC.
+unsafe fn sleep(wakeup: &WakeupSources, clpcr: u32, deep: bool) {
+    let scb = &*cortex_m::peripheral::SCB::PTR;
+    let run_clpcr = CCM_CLPCR.read_volatile() & !CLPCR_LPM_MASK;
+
+    // Interrupts stay disabled until we restore the clocks. A pending,
+    // unmasked interrupt still wakes the core from WFI.
+    cortex_m::interrupt::free(|_| {
+        let mut saved_imr = [0u32; GPC_IMR_COUNT];
+        for (idx, (saved, imr)) in saved_imr.iter_mut().zip(wakeup.imr().iter()).enumerate() {
+            *saved = GPC_IMR1.add(idx).read_volatile();
+            GPC_IMR1.add(idx).write_volatile(*imr);
+        }
+
+        let clocks = if deep {
+            Some(Clocks::save_and_bypass())
+        } else {
+            None
+        };
+
+        set_low_power_mode(clpcr);
+        if deep {
+            scb.scr.modify(|scr| scr | SCR_SLEEPDEEP);
+        } else {
+            scb.scr.modify(|scr| scr & !SCR_SLEEPDEEP);
+        }
+        cortex_m::asm::dsb();
+        cortex_m::asm::isb();
+        cortex_m::asm::wfi();
+
+        // Awake. Return to run mode before anything else.
+        CCM_CLPCR.write_volatile(run_clpcr);
+        scb.scr.modify(|scr| scr & !SCR_SLEEPDEEP);
+        if let Some(clocks) = clocks {
+            clocks.restore();
+        }
+        for (idx, saved) in saved_imr.iter().enumerate() {
+            GPC_IMR1.add(idx).write_volatile(*saved);
+        }
+    });
+}
+
+/// SCB SCR SLEEPDEEP bit
+const SCR_SLEEPDEEP: u32 = 1 << 2;
+
+#[cfg(test)]
+mod tests {
+    use super::{GpioPort, WakeupSources};
+
+    #[test]
+    fn empty() {
+        let wakeup = WakeupSources::new();
+        assert!(wakeup.is_empty());
+        assert_eq!(wakeup.imr(), [u32::MAX; 4]);
+    }
+
+    #[test]
+    fn gpio_ports() {
+        // GPIO1 combined interrupts are 80 and 81, which is IMR2 bits 16 and 17.
+        let wakeup = WakeupSources::new().gpio(GpioPort::Gpio1);
+        assert_eq!(wakeup.imr(), [u32::MAX, !(0b11 << 16), u32::MAX, u32::MAX]);
+        // GPIO5 combined interrupts are 88 and 89.
+        let wakeup = WakeupSources::new().gpio(GpioPort::Gpio5);
+        assert_eq!(wakeup.imr(), [u32::MAX, !(0b11 << 24), u32::MAX, u32::MAX]);
+    }
+
+    #[test]
+    fn timers_need_oscillator() {
+        assert!(!WakeupSources::new().srtc_alarm().needs_oscillator);
+        assert!(!WakeupSources::new().usb_resume().needs_oscillator);
+        assert!(WakeupSources::new().gpt1().needs_oscillator);
+        assert!(WakeupSources::new().gpt2().needs_oscillator);
+        assert!(WakeupSources::new().pit().needs_oscillator);
+    }
+
+    #[test]
+    fn combined_sources() {
+        let wakeup = WakeupSources::new()
+            .srtc_alarm() // 46 => IMR1 bit 14
+            .gpt2() // 101 => IMR3 bit 5
+            .usb_resume() // 113 => IMR3 bit 17
+            .pit(); // 122 => IMR3 bit 26
+        assert_eq!(
+            wakeup.imr(),
+            [
+                !(1 << 14),
+                u32::MAX,
+                !((1 << 5) | (1 << 17) | (1 << 26)),
+                u32::MAX
+            ]
+        );
+    }
+
+    #[test]
+    fn dma_interrupts_cannot_wake() {
+        let wakeup = WakeupSources::new().irq(0).irq(31);
+        assert!(wakeup.is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+//! Software reboots
+
+/// Reset the processor
+///
+/// The processor restarts your program, as if you pressed the reset button.
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+///
+/// bsp::reboot();
+/// ```
+pub fn reboot() -> ! {
+    cortex_m::peripheral::SCB::sys_reset()
+}
+
+/// Reboot into the Teensy bootloader
+///
+/// The Teensy's bootloader chip takes control of the processor, and waits for a new
+/// program. This is the same as pressing the program button. Use this to reprogram your
+/// Teensy without touching the board.
+///
+/// If you're using the USB stack, the host can also request this reboot by opening the
+/// serial port at 134 baud. See the [`usb`](crate::usb) module for more information.
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+///
+/// bsp::reboot_to_bootloader();
+/// ```
+pub fn reboot_to_bootloader() -> ! {
+    cortex_m::interrupt::disable();
+    #[cfg(target_arch = "arm")]
+    // Safety: the bootloader chip catches this breakpoint, and
+    // takes control of the processor. We never return.
+    unsafe {
+        core::arch::asm!("bkpt #251", options(nomem, nostack));
+    }
+    loop {
+        cortex_m::asm::nop();
+    }
+}
@@ -7,7 +7,8 @@
 //!
 //! Code that's in this module is running before `.data` is initialized,
 //! and before `.bss` is zeroed. This code should only touch ARM and
-//! peripheral memory.
+//! peripheral memory. The exception is the heap allocator, which keeps
+//! its state in `.uninit`.
 
 use core::arch::global_asm;
 pub use cortex_m_rt::*;
@@ -37,10 +38,14 @@ unsafe extern "C" fn t4_init() {
     // Ideally, we could figure out an approach that keeps the processor
     // as close to the reset state as possible, while avoiding #76.
     // For now, we'll go with this, which simplifies some downstream
-    // code.
+    // code. The `power` module selects a low-power mode only around its
+    // own WFI, and restores run mode on wakeup.
     const CCM_CLPCR: *mut u32 = 0x400F_C054 as *mut _;
     CCM_CLPCR.write_volatile(CCM_CLPCR.read_volatile() & !0b11);
 
+    #[cfg(feature = "alloc")]
+    crate::heap::init();
+
     extern "C" {
         fn Reset();
     }
@@ -1,4 +1,4 @@
-//! Teensy 4 USB, taken from the original Teensy 4 C libraries
+//! Teensy 4 USB serial, implemented with [`usb-device`]
 //!
 //! The USB stack provides a [`log`] implementation for logging over USB.
 //! It also provides a simpler [`Reader`] and [`Writer`] for performing
@@ -7,11 +7,18 @@
 //! and [`Writer`] to perform your own USB I/O.
 //!
 //! [`log`]: https://crates.io/crates/log
+//! [`usb-device`]: https://crates.io/crates/usb-device
 //!
 //! You're responsible for driving the USB driver with repeated calls to
 //! [`poll`]. See the `poll` documentation for considerations on where to
 //! call `poll`.
 //!
+//! Logging doesn't disable interrupts, so it's safe to log from time-critical
+//! interrupt handlers. The logger formats each record into a lock-free queue,
+//! and pends the USB interrupt. `poll` sends the queued records to the host.
+//! Records longer than 128 bytes are truncated. If you log faster than `poll`
+//! can send records, the logger drops records; see [`dropped_records`].
+//!
 //! Most initialization functions require the `imxrt_ral`'s `USB1` instance.
 //! You can acquire the instance through the HAL, which is exported by the
 //! BSP:
@@ -49,8 +56,9 @@
 //!     });
 //! }
 //!
-//! let (poller, _) = bsp::usb::init(
+//! let (poller, _, _) = bsp::usb::init(
 //!     USB1::take().unwrap(),
+//!     Default::default(),
 //!     bsp::usb::LoggingConfig {
 //!         filters: &[("motor", None)],
 //!         ..Default::default()
@@ -78,7 +86,8 @@
 //! use bsp::hal::ral::usb::USB1;
 //! use core::fmt::Write;
 //!
-//! let (mut poller, mut reader, mut writer) = bsp::usb::split(USB1::take().unwrap()).unwrap();
+//! let (mut poller, mut reader, mut writer) =
+//!     bsp::usb::split(USB1::take().unwrap(), Default::default()).unwrap();
 //!
 //! write!(writer, "Hello world! 3 + 2 = {}", 5).unwrap();
 //!
@@ -93,6 +102,63 @@
 //!     }
 //! }
 //! ```
+//!
+//! # Async I/O
+//!
+//! [`Reader::read_async`], [`Writer::write_async`], and [`Writer::flush_async`]
+//! wait for the USB serial port, instead of returning zero. `poll` wakes the
+//! waiting tasks when transfers complete, so call `poll` from the `USB_OTG1`
+//! interrupt. The futures only use `core::task` wakers, and they work with any
+//! executor.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//!
+//! async fn echo(mut reader: bsp::usb::Reader, mut writer: bsp::usb::Writer) {
+//!     let mut buffer = [0; 64];
+//!     loop {
+//!         let count = reader.read_async(&mut buffer).await.unwrap();
+//!         writer.write_all_async(&buffer[..count]).await.unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! # `defmt`
+//!
+//! When you enable the `"defmt"` feature, the USB stack also provides a `defmt`
+//! global logger. The logger writes `defmt` frames to the logging port, the same
+//! port used by the `log` implementation. Initialize the USB stack with [`init`]
+//! or [`init_dual`], and drive it with `poll`, as you would for `log`. Don't use
+//! `log` and `defmt` on the same port; the host can't decode the mix.
+//!
+//! You'll need to link with `defmt.x`, in addition to `t4link.x`. On the host,
+//! decode the frames with the `defmt-monitor` from this project's `tools` package:
+//!
+//! ```text
+//! cargo run --package tools --features defmt-monitor --bin defmt-monitor -- \
+//!     path/to/elf /dev/ttyACM0
+//! ```
+//!
+//! # Bootloader requests
+//!
+//! When the host opens the serial port at 134 baud, the USB stack reboots the Teensy
+//! into its bootloader. This is how the Teensy Loader and Teensyduino reprogram a
+//! running Teensy. The reboot happens shortly after the request, while you're calling
+//! `poll`. To ignore these requests, use [`Poller::set_reboot_on_134_baud`].
+//!
+//! # Device classes
+//!
+//! Instead of a serial port, the USB device can present HID interfaces ([`split_hid`]),
+//! a MIDI interface ([`split_midi`]), a USB drive ([`split_msc`]), a network adapter
+//! ([`split_net`]), or a DFU mode interface ([`split_dfu`]). You may only call one of
+//! the `init` and `split` functions.
+//!
+//! These devices have no serial port, so there's no USB logging, and the host can't
+//! reboot the Teensy into its bootloader. Press the program button, or call
+//! [`reboot_to_bootloader`](crate::reboot_to_bootloader), to reprogram the Teensy.
+//!
+//! Hosts may cache the interfaces of a product ID, so don't use the serial port's
+//! product ID in your [`UsbIdentity`]. DFU mode is the exception; see [`split_dfu`].
 
 //
 // Developer notes:
@@ -102,15 +168,56 @@
 //   else, unless they mix-and-match RALs, or use another register access
 //   layer.
 //
+// - The USB device and its serial class live in a static, and are only
+//   accessed in critical sections. The `Poller`, `Reader`, and `Writer`
+//   are handles to that static state.
+//
+// - The logger only touches the log queue, which is lock free. `poll` is
+//   the queue's only consumer.
+//
+
+use core::{
+    cell::RefCell,
+    fmt,
+    sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+};
+use cortex_m::interrupt::{self, Mutex};
+use usb_device::{
+    bus::UsbBusAllocator,
+    device::{StringDescriptors, UsbDevice, UsbDeviceBuilder, UsbDeviceState, UsbVidPid},
+};
 
-use core::fmt;
-mod bindings;
+mod bus;
+mod cdc;
+#[cfg(feature = "defmt")]
+mod defmt_logger;
+pub mod dfu;
 mod filters;
+pub mod format;
+pub mod framed;
+pub mod hid;
+mod log_buffer;
+mod log_queue;
+pub mod midi;
+pub mod msc;
+pub mod net;
+mod queue;
+mod registers;
+mod ring;
+mod traits;
+mod wakers;
 
-pub use filters::Filter;
-use filters::Filters;
+pub use cdc::{LineCoding, Parity, StopBits};
+pub use filters::{Filter, FilterError, MAX_FILTERS, MAX_TARGET_LEN};
+use filters::{Filters, SharedFilters};
+use format::{Clock, Formatted, Formatter};
+use log_buffer::LogBuffer;
+pub use log_buffer::Overflow;
+use log_queue::LogQueue;
 
 use crate::hal::ral::usb::{Instance, USB1};
+use bus::Bus;
+use cdc::Cdc;
 
 /// Logging configuration
 ///
@@ -134,7 +241,53 @@ pub struct LoggingConfig {
     /// If set to an empty slice (default), the logger performs no
     /// filtering. Otherwise, we filter the specified targets by
     /// the accompanying log level. If there is no level, we default
+    ///
+    /// You may specify up to [`MAX_FILTERS`] targets, each up to
+    /// [`MAX_TARGET_LEN`] bytes long. Change the filters at runtime with
+    /// the [`LogFilters`] returned by `init`. See [`Filter`] for how filters
+    /// match targets.
     pub filters: &'static [Filter],
+    /// The level for targets that don't match any of the `filters`
+    ///
+    /// Defaults to `Off`, which drops records from unmatched targets. Has no
+    /// effect when there are no `filters`.
+    pub default_level: ::log::LevelFilter,
+    /// Memory for log records that the host isn't ready to receive
+    ///
+    /// If set to `None` (default), the logger drops records until the host
+    /// configures the USB device. Otherwise, the logger saves records in this
+    /// buffer while the host hasn't configured the device, or while the serial
+    /// port's DTR is low. Once a terminal opens the serial port, `poll` sends the
+    /// saved records in order, before any new records.
+    ///
+    /// ```no_run
+    /// use teensy4_bsp as bsp;
+    ///
+    /// static mut LOG_BUFFER: [u8; 4096] = [0; 4096];
+    ///
+    /// let config = bsp::usb::LoggingConfig {
+    ///     // Safety: we only use this buffer for logging.
+    ///     buffer: Some(unsafe { &mut *core::ptr::addr_of_mut!(LOG_BUFFER) }),
+    ///     overflow: bsp::usb::Overflow::DropOldest,
+    ///     ..Default::default()
+    /// };
+    /// ```
+    pub buffer: Option<&'static mut [u8]>,
+    /// Selects which records to drop when the `buffer` is full
+    ///
+    /// By default, the logger drops new records, so that you'll see the
+    /// first records after a reset. Use [`dropped_records`] to learn how
+    /// many records were dropped.
+    pub overflow: Overflow,
+    /// Writes each record's text
+    ///
+    /// Defaults to [`format::default`]. See the [`format`] module for the
+    /// other presets, and for writing your own formatter.
+    pub formatter: Formatter,
+    /// Provides timestamps to the `formatter`
+    ///
+    /// If `None` (default), formatters don't show timestamps.
+    pub clock: Option<Clock>,
 }
 
 impl Default for LoggingConfig {
@@ -142,6 +295,63 @@ impl Default for LoggingConfig {
         LoggingConfig {
             max_level: ::log::STATIC_MAX_LEVEL,
             filters: &[],
+            default_level: ::log::LevelFilter::Off,
+            buffer: None,
+            overflow: Overflow::DropNewest,
+            formatter: format::default,
+            clock: None,
+        }
+    }
+}
+
+/// How the USB device identifies itself to the host
+///
+/// The identity is fixed when you call [`init`] or [`split`]. The default identity
+/// matches a Teensy running a Teensyduino USB serial program, so the host can't tell
+/// your Teensy apart from any other Teensy. Set your own vendor and product IDs, and
+/// strings, to give your device a distinct identity.
+///
+/// ```
+/// use teensy4_bsp as bsp;
+///
+/// let identity = bsp::usb::UsbIdentity {
+///     vendor_id: 0x1209,
+///     product_id: 0x0001,
+///     manufacturer: "Example Co.",
+///     product: "Example Instrument",
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct UsbIdentity {
+    /// The USB vendor ID (VID)
+    pub vendor_id: u16,
+    /// The USB product ID (PID)
+    pub product_id: u16,
+    /// The manufacturer string
+    pub manufacturer: &'static str,
+    /// The product string
+    pub product: &'static str,
+    /// The serial number string
+    ///
+    /// If `None` (default), the serial number is the Teensy's serial number, formatted
+    /// the same way that Teensyduino formats it. See [`board_id`](crate::board_id()).
+    pub serial_number: Option<&'static str>,
+    /// The device release number, in binary-coded decimal (`bcdDevice`)
+    pub device_release: u16,
+}
+
+impl Default for UsbIdentity {
+    fn default() -> UsbIdentity {
+        UsbIdentity {
+            vendor_id: 0x16C0,
+            product_id: 0x0483,
+            manufacturer: "Teensyduino",
+            product: "USB Serial",
+            serial_number: None,
+            // Teensyduino uses this release number to identify
+            // a Teensy 4.
+            device_release: 0x0279,
         }
     }
 }
@@ -170,6 +380,14 @@ pub enum Error {
     ///
     /// Any USB CDC I/O method may return this error.
     Io,
+    /// There are too many logging filters, or a filter's target is too long
+    ///
+    /// The [`init`] function may return this error.
+    InvalidFilters,
+    /// The configuration doesn't select any interfaces
+    ///
+    /// The [`split_hid`] function may return this error.
+    NoInterfaces,
 }
 
 impl From<::log::SetLoggerError> for Error {
@@ -182,7 +400,8 @@ impl From<::log::SetLoggerError> for Error {
 /// that can read USB serial messages.
 ///
 /// To select the default logger behavior, specify `Default::default()` as the
-/// argument for `config`.
+/// argument for `config`. The `identity` describes the USB device to the host;
+/// see [`UsbIdentity`] for more information.
 ///
 /// The `inst` argument must be the `imxrt_ral`'s `USB1` instance. An incorrect instance
 /// results in a [`Error::WrongInstance`] error.
@@ -193,50 +412,585 @@ impl From<::log::SetLoggerError> for Error {
 /// logger.
 ///
 /// See the [module-level documentation](mod@crate::usb) for an example.
-pub fn init(inst: Instance, config: LoggingConfig) -> Result<(Poller, Reader), Error> {
+pub fn init(
+    inst: Instance,
+    identity: UsbIdentity,
+    config: LoggingConfig,
+) -> Result<(Poller, Reader, LogFilters), Error> {
     if &*inst as *const _ != USB1 {
         return Err(Error::WrongInstance);
     }
     unsafe {
-        LOGGER.enabled = true;
-        LOGGER.filters = Filters::new(config.filters);
+        set_logger(config)?;
+        start(identity, |alloc| Classes::Serial(Cdc::new(alloc, false)));
+    }
+    Ok((
+        Poller(core::marker::PhantomData),
+        Reader::new(Port::Primary),
+        LogFilters::new(),
+    ))
+}
 
-        ::log::set_logger(&LOGGER).map(|_| ::log::set_max_level(config.max_level))?;
-        start();
+/// Initializes the USB stack with two serial ports: one for logging, and one for your
+/// application
+///
+/// The first serial port is for the logger. The second serial port is for the returned
+/// `Reader` and `Writer`, so your data never mixes with log messages. The host sees a
+/// composite device with two CDC ACM interfaces; on Linux, these are typically
+/// `/dev/ttyACM0` and `/dev/ttyACM1`. Use the [`PollStatus`] `cdc_*` flags to learn
+/// about your serial port, and the `logger_*` flags to learn about the logging port.
+///
+/// Otherwise, `init_dual` behaves like [`init`], and returns the same errors. You may
+/// only call one of `init`, `init_dual`, or [`split`]. Teensyduino uses product ID
+/// `0x048B` for its dual serial devices; consider using that value, or your own, in
+/// your `identity`.
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+/// use bsp::hal::ral::usb::USB1;
+///
+/// let (poller, reader, writer, filters) =
+///     bsp::usb::init_dual(USB1::take().unwrap(), Default::default(), Default::default())
+///         .unwrap();
+/// // Prepare the USB ISR...
+///
+/// log::info!("This message goes to the first serial port");
+/// ```
+pub fn init_dual(
+    inst: Instance,
+    identity: UsbIdentity,
+    config: LoggingConfig,
+) -> Result<(Poller, Reader, Writer, LogFilters), Error> {
+    if &*inst as *const _ != USB1 {
+        return Err(Error::WrongInstance);
+    }
+    unsafe {
+        set_logger(config)?;
+        start(identity, |alloc| {
+            Classes::Dual(Cdc::new(alloc, true), Cdc::new(alloc, true))
+        });
     }
     Ok((
         Poller(core::marker::PhantomData),
-        Reader(core::marker::PhantomData),
+        Reader::new(Port::Secondary),
+        unsafe { Writer::new(Port::Secondary) },
+        LogFilters::new(),
     ))
 }
 
 /// Splits the USB stack into reading and writing halves, and returns both halves
 ///
 /// The `inst` argument must be the `imxrt_ral`'s `USB1` instance. An incorrect instance
-/// results in a [`Error::WrongInstance`] error.
+/// results in a [`Error::WrongInstance`] error. The `identity` describes the USB device
+/// to the host; see [`UsbIdentity`] for more information.
 ///
 /// See the [module-level documentation](mod@crate::usb) for an example.
-pub fn split(inst: Instance) -> Result<(Poller, Reader, Writer), Error> {
+pub fn split(inst: Instance, identity: UsbIdentity) -> Result<(Poller, Reader, Writer), Error> {
     if &*inst as *const _ != USB1 {
         return Err(Error::WrongInstance);
     }
-    unsafe { start() };
+    unsafe { start(identity, |alloc| Classes::Serial(Cdc::new(alloc, false))) };
     Ok((
         Poller(core::marker::PhantomData),
-        Reader(core::marker::PhantomData),
-        unsafe { Writer::new() },
+        Reader::new(Port::Primary),
+        unsafe { Writer::new(Port::Primary) },
     ))
 }
 
+/// Splits the USB stack like [`split`], and adds a DFU runtime interface
+///
+/// The runtime interface lets DFU tools, like `dfu-util`, ask the device to detach.
+/// Once the host asks, `poll` reboots the Teensy. Then, [`dfu::take_detach_request`]
+/// returns `true`, and you should call [`split_dfu`]. See the [`dfu`] module for more
+/// information.
+///
+/// The host sees a composite device with a CDC ACM interface and a DFU interface.
+/// Otherwise, `split_with_dfu` behaves like [`split`], and returns the same errors.
+pub fn split_with_dfu(
+    inst: Instance,
+    identity: UsbIdentity,
+) -> Result<(Poller, Reader, Writer), Error> {
+    if &*inst as *const _ != USB1 {
+        return Err(Error::WrongInstance);
+    }
+    unsafe {
+        start(identity, |alloc| {
+            Classes::SerialDfu(Cdc::new(alloc, true), dfu::Runtime::new(alloc))
+        })
+    };
+    Ok((
+        Poller(core::marker::PhantomData),
+        Reader::new(Port::Primary),
+        unsafe { Writer::new(Port::Primary) },
+    ))
+}
+
+/// Initializes the USB stack in DFU mode, with no serial port
+///
+/// The host downloads firmware into `staging`. `poll` handles the host's
+/// requests, and calls `staging`. Once the host downloads, and the device commits,
+/// an image, `poll` reboots the Teensy after the host resets the device. See the
+/// [`dfu`] module for more information.
+///
+/// Otherwise, `split_dfu` behaves like [`split`], and returns the same errors. Use
+/// the same `identity` that you use with [`split_with_dfu`], so that the host can
+/// find the device after it detaches. See [device classes](crate::usb#device-classes).
+pub fn split_dfu(
+    inst: Instance,
+    identity: UsbIdentity,
+    staging: &'static mut dyn dfu::Staging,
+) -> Result<Poller, Error> {
+    if &*inst as *const _ != USB1 {
+        return Err(Error::WrongInstance);
+    }
+    unsafe {
+        start(identity, move |alloc| {
+            Classes::Dfu(dfu::Dfu::new(alloc, staging))
+        })
+    };
+    Ok(Poller(core::marker::PhantomData))
+}
+
+/// Initializes the USB stack with HID interfaces, and no serial port
+///
+/// `config` selects the keyboard, mouse, joystick, and raw HID interfaces. The returned
+/// [`Devices`](hid::Devices) has a handle for each selected interface. See the [`hid`]
+/// module for more information. If `config` doesn't select any interfaces, `split_hid`
+/// returns [`Error::NoInterfaces`].
+///
+/// Otherwise, `split_hid` behaves like [`split`], and returns the same errors. You may
+/// only call one of the `init` and `split` functions. Teensyduino uses product ID
+/// `0x0482` for its keyboard, mouse, and joystick devices, and `0x0486` for its raw HID
+/// devices; consider using those values, or your own, in your `identity`. See
+/// [device classes](crate::usb#device-classes).
+pub fn split_hid(
+    inst: Instance,
+    identity: UsbIdentity,
+    config: hid::Config,
+) -> Result<(Poller, hid::Devices), Error> {
+    if &*inst as *const _ != USB1 {
+        return Err(Error::WrongInstance);
+    }
+    if config == hid::Config::default() {
+        return Err(Error::NoInterfaces);
+    }
+    unsafe { start(identity, |alloc| Classes::Hid(hid::Hid::new(alloc, config))) };
+    Ok((Poller(core::marker::PhantomData), hid::Devices::new(config)))
+}
+
+/// Initializes the USB stack with a USB MIDI interface, and no serial port
+///
+/// The returned [`Midi`](midi::Midi) sends and receives MIDI packets. See the [`midi`]
+/// module for more information.
+///
+/// Otherwise, `split_midi` behaves like [`split`], and returns the same errors. You may
+/// only call one of the `init` and `split` functions. Teensyduino uses product ID
+/// `0x0485` for its MIDI devices; consider using that value, or your own, in your
+/// `identity`. See [device classes](crate::usb#device-classes).
+pub fn split_midi(inst: Instance, identity: UsbIdentity) -> Result<(Poller, midi::Midi), Error> {
+    if &*inst as *const _ != USB1 {
+        return Err(Error::WrongInstance);
+    }
+    unsafe { start(identity, |alloc| Classes::Midi(midi::MidiClass::new(alloc))) };
+    Ok((Poller(core::marker::PhantomData), midi::Midi::new()))
+}
+
+/// Initializes the USB stack with a mass storage interface, and no serial port
+///
+/// The host sees a USB drive that stores its blocks in `storage`. `poll` handles
+/// the host's commands, and calls `storage`. See the [`msc`] module for more
+/// information.
+///
+/// Otherwise, `split_msc` behaves like [`split`], and returns the same errors. You may
+/// only call one of the `init` and `split` functions. See
+/// [device classes](crate::usb#device-classes).
+///
+/// # Panics
+///
+/// Panics if the block size of `storage` is zero, or larger than
+/// [`MAX_BLOCK_SIZE`](msc::MAX_BLOCK_SIZE).
+pub fn split_msc(
+    inst: Instance,
+    identity: UsbIdentity,
+    storage: &'static mut dyn msc::BlockDevice,
+) -> Result<Poller, Error> {
+    if &*inst as *const _ != USB1 {
+        return Err(Error::WrongInstance);
+    }
+    unsafe {
+        start(identity, move |alloc| {
+            Classes::Msc(msc::Msc::new(alloc, storage))
+        })
+    };
+    Ok(Poller(core::marker::PhantomData))
+}
+
+/// Initializes the USB stack with a USB network interface, and no serial port
+///
+/// The returned [`Net`](net::Net) sends and receives Ethernet frames. See the [`net`]
+/// module for more information.
+///
+/// Otherwise, `split_net` behaves like [`split`], and returns the same errors. You may
+/// only call one of the `init` and `split` functions. See
+/// [device classes](crate::usb#device-classes).
+pub fn split_net(inst: Instance, identity: UsbIdentity) -> Result<(Poller, net::Net), Error> {
+    if &*inst as *const _ != USB1 {
+        return Err(Error::WrongInstance);
+    }
+    unsafe { start(identity, |alloc| Classes::Net(net::NcmClass::new(alloc))) };
+    Ok((Poller(core::marker::PhantomData), net::Net::new()))
+}
+
+/// Prepare, then set, the USB logger
+///
+/// # Safety
+///
+/// Must only be called once, before the logger is set.
+unsafe fn set_logger(config: LoggingConfig) -> Result<(), Error> {
+    let LoggingConfig {
+        max_level,
+        filters,
+        default_level,
+        buffer,
+        overflow,
+        formatter,
+        clock,
+    } = config;
+    let mut filters = Filters::new(filters).map_err(|_| Error::InvalidFilters)?;
+    filters.set_default_level(default_level);
+    FILTERS.modify(|shared| *shared = filters);
+    LOGGER.enabled = true;
+    LOGGER.formatter = formatter;
+    LOGGER.clock = clock;
+    if let Some(buffer) = buffer.filter(|buffer| !buffer.is_empty()) {
+        LOGGER.buffered = true;
+        let buffer = LogBuffer::new(buffer, overflow);
+        interrupt::free(|cs| *LOG_BUFFER.borrow(cs).borrow_mut() = Some(buffer));
+    }
+
+    ::log::set_logger(&*core::ptr::addr_of!(LOGGER)).map(|_| ::log::set_max_level(max_level))?;
+    Ok(())
+}
+
+/// A serial port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Port {
+    /// The only port, or the logging port when there are two ports
+    Primary,
+    /// The application's port when there are two ports
+    Secondary,
+}
+
+/// The classes presented by the USB device
+// The stack is static, so there's only ever one, and it never moves.
+#[allow(clippy::large_enum_variant)]
+enum Classes {
+    /// One serial port
+    Serial(Cdc<'static>),
+    /// Two serial ports: the logging port, then the application's port
+    Dual(Cdc<'static>, Cdc<'static>),
+    /// One serial port, and a DFU runtime interface
+    SerialDfu(Cdc<'static>, dfu::Runtime),
+    /// HID interfaces, and no serial port
+    Hid(hid::Hid<'static>),
+    /// A MIDI interface, and no serial port
+    Midi(midi::MidiClass<'static>),
+    /// A mass storage interface, and no serial port
+    Msc(msc::Msc<'static>),
+    /// A DFU mode interface, and no serial port
+    Dfu(dfu::Dfu<'static>),
+    /// A network interface, and no serial port
+    Net(net::NcmClass<'static>),
+}
+
+impl Classes {
+    /// Poll the device with all classes
+    ///
+    /// Returns `true` if a class may have new data.
+    fn poll(&mut self, device: &mut UsbDevice<'static, Bus>) -> bool {
+        match self {
+            Classes::Serial(primary) => device.poll(&mut [primary]),
+            Classes::Dual(primary, secondary) => device.poll(&mut [primary, secondary]),
+            Classes::SerialDfu(primary, runtime) => device.poll(&mut [primary, runtime]),
+            Classes::Hid(hid) => device.poll(&mut [hid]),
+            Classes::Midi(midi) => device.poll(&mut [midi]),
+            Classes::Msc(msc) => device.poll(&mut [msc]),
+            Classes::Dfu(dfu) => device.poll(&mut [dfu]),
+            Classes::Net(net) => device.poll(&mut [net]),
+        }
+    }
+
+    /// Returns the serial port, if the device has that port
+    fn port(&mut self, port: Port) -> Option<&mut Cdc<'static>> {
+        match (self, port) {
+            (Classes::Serial(primary), Port::Primary)
+            | (Classes::Dual(primary, _), Port::Primary)
+            | (Classes::SerialDfu(primary, _), Port::Primary) => Some(primary),
+            (Classes::Dual(_, secondary), Port::Secondary) => Some(secondary),
+            _ => None,
+        }
+    }
+
+    /// Returns the HID class, if the device has HID interfaces
+    fn hid(&mut self) -> Option<&mut hid::Hid<'static>> {
+        match self {
+            Classes::Hid(hid) => Some(hid),
+            _ => None,
+        }
+    }
+
+    /// Returns the MIDI class, if the device has a MIDI interface
+    fn midi(&mut self) -> Option<&mut midi::MidiClass<'static>> {
+        match self {
+            Classes::Midi(midi) => Some(midi),
+            _ => None,
+        }
+    }
+
+    /// Returns the network class, if the device has a network interface
+    fn net(&mut self) -> Option<&mut net::NcmClass<'static>> {
+        match self {
+            Classes::Net(net) => Some(net),
+            _ => None,
+        }
+    }
+}
+
+/// The USB device, and its classes
+struct Stack {
+    device: UsbDevice<'static, Bus>,
+    classes: Classes,
+    /// Number of SOFs until we reboot into the bootloader, or zero
+    /// if there's no reboot scheduled
+    reboot_countdown: u8,
+    /// Set if the scheduled reboot is a DFU detach, which restarts
+    /// the program instead of the bootloader
+    dfu_detach: bool,
+}
+
+// Safety: the stack is only accessed in critical sections, and there's
+// only one core.
+unsafe impl Send for Stack {}
+
+static mut ALLOCATOR: Option<UsbBusAllocator<Bus>> = None;
+static STACK: Mutex<RefCell<Option<Stack>>> = Mutex::new(RefCell::new(None));
+
+/// Set when the host has configured the USB device
+///
+/// It stays set while the host suspends the bus, since the configuration
+/// survives a suspend.
+static CONFIGURED: AtomicBool = AtomicBool::new(false);
+
+/// The USB device state
+///
+/// See [`state`] for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceState {
+    /// The device isn't connected to a host, or the USB stack isn't running
+    Detached,
+    /// The host reset the device, but it hasn't assigned an address
+    Default,
+    /// The host assigned an address, but it hasn't configured the device
+    Addressed,
+    /// The host configured the device; the serial ports are ready
+    Configured,
+    /// The host suspended the bus
+    Suspended,
+}
+
+/// The `UsbDeviceState` after the most recent `poll`, or `DETACHED`
+/// before the stack starts
+static DEVICE_STATE: AtomicU8 = AtomicU8::new(DETACHED);
+const DETACHED: u8 = u8::MAX;
+
+/// Number of bus resets
+static RESETS: AtomicUsize = AtomicUsize::new(0);
+/// Number of configurations after the first
+static RECONNECTS: AtomicUsize = AtomicUsize::new(0);
+/// Set once the host first configures the device
+static EVER_CONFIGURED: AtomicBool = AtomicBool::new(false);
+
+/// Returns the USB device state
+///
+/// The state reflects the most recent [`poll`], except that it's `Detached`
+/// as soon as the USB cable is disconnected. Use this to pause streaming
+/// while the host isn't listening, or to save power while the bus is suspended.
+pub fn state() -> DeviceState {
+    let state = DEVICE_STATE.load(Ordering::Relaxed);
+    if state == DETACHED || !bus::vbus_valid() {
+        return DeviceState::Detached;
+    }
+    match state {
+        state if state == UsbDeviceState::Default as u8 => DeviceState::Default,
+        state if state == UsbDeviceState::Addressed as u8 => DeviceState::Addressed,
+        state if state == UsbDeviceState::Configured as u8 => DeviceState::Configured,
+        _ => DeviceState::Suspended,
+    }
+}
+
+/// Returns the number of times that the host reset the USB bus
+///
+/// Hosts reset the bus at least once when they enumerate the device, so expect
+/// this to be non-zero after the host configures the device.
+pub fn resets() -> usize {
+    RESETS.load(Ordering::Relaxed)
+}
+
+/// Returns the number of times that the host reconnected to the device
+///
+/// This counts each time the host configures the device, after the first
+/// time. This happens when the cable is reconnected, when the host reboots,
+/// or when the host re-enumerates the device after a bus reset. Reset any
+/// protocol state when this changes.
+pub fn reconnects() -> usize {
+    RECONNECTS.load(Ordering::Relaxed)
+}
+
+/// Returns the configuration, and the device events, after a poll
+///
+/// `previous` and `state` are the device states before and after the poll.
+/// `was_configured` is the configuration before the poll, and `reset` is set
+/// if the host reset the bus. The configuration survives a suspended bus, so
+/// a suspend and a resume only produce their own events. A bus reset discards
+/// the configuration, even if the host configured the device again before the
+/// poll.
+fn derive_device_events(
+    previous: UsbDeviceState,
+    state: UsbDeviceState,
+    was_configured: bool,
+    reset: bool,
+) -> (bool, u32) {
+    let was_configured = was_configured && !reset;
+    let configured = match state {
+        UsbDeviceState::Configured => true,
+        UsbDeviceState::Suspend => was_configured,
+        UsbDeviceState::Default | UsbDeviceState::Addressed => false,
+    };
+
+    let mut events = 0;
+    if reset {
+        events |= BUS_RESET;
+    }
+    let suspended = state == UsbDeviceState::Suspend;
+    let was_suspended = previous == UsbDeviceState::Suspend;
+    if suspended && !was_suspended {
+        events |= SUSPENDED;
+    } else if was_suspended && !suspended {
+        events |= RESUMED;
+    }
+    if configured && !was_configured {
+        events |= CONFIGURED_EVENT;
+    }
+    (configured, events)
+}
+
+/// Number of SOFs to wait before rebooting into the bootloader
+///
+/// The delay lets us finish the host's control transfer.
+const REBOOT_SOF_COUNT: u8 = 80;
+
 /// Initialize the USB stack
 ///
+/// `classes` allocates the device's classes from the bus allocator. The
+/// classes also select the device class in the device descriptor.
+///
 /// # Safety
 ///
 /// Must only be called once.
-#[inline(always)]
-unsafe fn start() {
-    bindings::usb_pll_start();
-    bindings::usb_init();
+unsafe fn start(
+    identity: UsbIdentity,
+    classes: impl FnOnce(&'static UsbBusAllocator<Bus>) -> Classes,
+) {
+    // Safety: caller ensures that we're only called once, so this is the
+    // only reference to the allocator.
+    let allocator =
+        &*(*core::ptr::addr_of_mut!(ALLOCATOR)).insert(UsbBusAllocator::new(Bus::new()));
+    // Allocate the classes before building the device.
+    let classes = classes(allocator);
+    let serial_number = identity.serial_number.unwrap_or_else(serial_number);
+    let device = UsbDeviceBuilder::new(
+        allocator,
+        UsbVidPid(identity.vendor_id, identity.product_id),
+    )
+    .strings(&[StringDescriptors::default()
+        .manufacturer(identity.manufacturer)
+        .product(identity.product)
+        .serial_number(serial_number)])
+    .unwrap()
+    .device_release(identity.device_release)
+    .max_packet_size_0(64)
+    .unwrap();
+    let device = match &classes {
+        Classes::Serial(_) => device.device_class(0x02),
+        Classes::Dual(..) => device.composite_with_iads(),
+        Classes::SerialDfu(..) => device.composite_with_iads(),
+        // Each HID interface describes its class.
+        Classes::Hid(_) => device,
+        // The audio interfaces describe their class.
+        Classes::Midi(_) => device,
+        // The storage interface describes its class.
+        Classes::Msc(_) => device,
+        // The DFU interface describes its class.
+        Classes::Dfu(_) => device,
+        // The network function has an IAD, since it has two interfaces.
+        Classes::Net(_) => device.composite_with_iads(),
+    };
+    let device = device.build();
+
+    interrupt::free(|cs| {
+        *STACK.borrow(cs).borrow_mut() = Some(Stack {
+            device,
+            classes,
+            reboot_countdown: 0,
+            dfu_detach: false,
+        });
+    });
+}
+
+/// Returns the Teensy's serial number, formatted as a decimal string
+///
+/// See [`BoardId::serial_number`](crate::BoardId::serial_number).
+fn serial_number() -> &'static str {
+    static mut SERIAL: [u8; 10] = [0; 10];
+    let mut num = crate::board_id().serial_number();
+
+    // Safety: only called once, from `start`.
+    let serial = unsafe { &mut *core::ptr::addr_of_mut!(SERIAL) };
+    let mut start = serial.len();
+    loop {
+        start -= 1;
+        serial[start] = b'0' + (num % 10) as u8;
+        num /= 10;
+        if num == 0 {
+            break;
+        }
+    }
+    core::str::from_utf8(&serial[start..]).unwrap()
+}
+
+/// Run `f` with the USB stack
+///
+/// Returns [`Error::NotConfigured`] if the host hasn't configured the device, or if
+/// `f` returns `None`.
+fn with_stack<R>(f: impl FnOnce(&mut Stack) -> Option<R>) -> Result<R, Error> {
+    if !CONFIGURED.load(Ordering::Relaxed) {
+        return Err(Error::NotConfigured);
+    }
+    interrupt::free(|cs| {
+        let mut stack = STACK
+            .borrow(cs)
+            .try_borrow_mut()
+            .map_err(|_| Error::NotConfigured)?;
+        let stack = stack.as_mut().ok_or(Error::NotConfigured)?;
+        f(stack).ok_or(Error::NotConfigured)
+    })
+}
+
+/// Run `f` with a serial port
+///
+/// Returns [`Error::NotConfigured`] if the host hasn't configured the device.
+fn with_port<R>(port: Port, f: impl FnOnce(&mut Cdc<'static>) -> R) -> Result<R, Error> {
+    with_stack(|stack| stack.classes.port(port).map(f))
 }
 
 /// An object that can poll the USB device and driver
@@ -259,8 +1013,28 @@ impl Poller {
         // "owns" the state modified by the poll function.
         unsafe { poll() }
     }
+
+    /// Select if a host can reboot the Teensy into its bootloader
+    ///
+    /// When `enable` is `true` (default), the Teensy reboots into its bootloader when
+    /// the host sets the serial port's baud rate to 134. Set `enable` to `false` to
+    /// ignore these requests. You can still reboot into the bootloader by pressing the
+    /// program button, or with [`reboot_to_bootloader`](crate::reboot_to_bootloader).
+    pub fn set_reboot_on_134_baud(&mut self, enable: bool) {
+        REBOOT_ON_134_BAUD.store(enable, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if a host can reboot the Teensy into its bootloader
+    ///
+    /// See [`set_reboot_on_134_baud`](Poller::set_reboot_on_134_baud) for more information.
+    pub fn reboot_on_134_baud(&self) -> bool {
+        REBOOT_ON_134_BAUD.load(Ordering::Relaxed)
+    }
 }
 
+/// Set if the host may reboot us into the bootloader
+static REBOOT_ON_134_BAUD: AtomicBool = AtomicBool::new(true);
+
 // Safety: OK to move across execution contexts; never
 // safe to share across those contexts.
 unsafe impl Send for Poller {}
@@ -286,10 +1060,11 @@ impl PollStatus {
     ///
     /// In this context, "rx" means "USB host to USB device."
     /// Check this flag to understand if your [`Reader`]
-    /// might have data.
+    /// might have data. When you use [`init_dual`], this
+    /// describes your serial port, not the logging port.
     #[inline(always)]
     pub fn cdc_rx_complete(&self) -> bool {
-        self.flags & bindings::POLL_CDC_RX_COMPLETE != 0
+        self.flags & cdc::RX_COMPLETE != 0
     }
 
     /// Indicates if a CDC TX transfer was completed (true)
@@ -300,10 +1075,117 @@ impl PollStatus {
     /// USB logger has scheduled a transfer.
     #[inline(always)]
     pub fn cdc_tx_complete(&self) -> bool {
-        self.flags & bindings::POLL_CDC_TX_COMPLETE != 0
+        self.flags & cdc::TX_COMPLETE != 0
+    }
+
+    /// Indicates if the host changed the DTR or RTS control lines
+    /// in this poll
+    ///
+    /// Terminals typically set DTR when they open the serial port, and
+    /// clear it when they close the port. Use [`Reader::dtr`] or
+    /// [`Writer::dtr`] to learn the new state. A USB reset clears the
+    /// control lines, and also sets this flag.
+    #[inline(always)]
+    pub fn cdc_control_lines_changed(&self) -> bool {
+        self.flags & cdc::CONTROL_LINES_CHANGED != 0
+    }
+
+    /// Indicates if the host changed the line coding in this poll
+    ///
+    /// Use [`Reader::line_coding`] or [`Writer::line_coding`] to
+    /// learn the new line coding.
+    #[inline(always)]
+    pub fn cdc_line_coding_changed(&self) -> bool {
+        self.flags & cdc::LINE_CODING_CHANGED != 0
+    }
+
+    /// Indicates if the logging port received data in this poll
+    ///
+    /// When there's one serial port, this is the same as
+    /// [`cdc_rx_complete`](PollStatus::cdc_rx_complete). When you use
+    /// [`init_dual`], this describes the logging port.
+    #[inline(always)]
+    pub fn logger_rx_complete(&self) -> bool {
+        self.flags & (cdc::RX_COMPLETE << LOGGER_SHIFT) != 0
+    }
+
+    /// Indicates if the logging port finished a transfer in this poll
+    ///
+    /// When there's one serial port, this is the same as
+    /// [`cdc_tx_complete`](PollStatus::cdc_tx_complete). When you use
+    /// [`init_dual`], this describes the logging port.
+    #[inline(always)]
+    pub fn logger_tx_complete(&self) -> bool {
+        self.flags & (cdc::TX_COMPLETE << LOGGER_SHIFT) != 0
+    }
+
+    /// Indicates if the host changed the logging port's control lines
+    /// in this poll
+    ///
+    /// When there's one serial port, this is the same as
+    /// [`cdc_control_lines_changed`](PollStatus::cdc_control_lines_changed).
+    /// When you use [`init_dual`], this describes the logging port.
+    #[inline(always)]
+    pub fn logger_control_lines_changed(&self) -> bool {
+        self.flags & (cdc::CONTROL_LINES_CHANGED << LOGGER_SHIFT) != 0
+    }
+
+    /// Indicates if the host changed the logging port's line coding
+    /// in this poll
+    ///
+    /// When there's one serial port, this is the same as
+    /// [`cdc_line_coding_changed`](PollStatus::cdc_line_coding_changed).
+    /// When you use [`init_dual`], this describes the logging port.
+    #[inline(always)]
+    pub fn logger_line_coding_changed(&self) -> bool {
+        self.flags & (cdc::LINE_CODING_CHANGED << LOGGER_SHIFT) != 0
+    }
+
+    /// Indicates if the host reset the USB bus in this poll
+    ///
+    /// A reset deconfigures the device, and discards any data in the
+    /// serial ports. See [`resets`] for the number of resets.
+    #[inline(always)]
+    pub fn bus_reset(&self) -> bool {
+        self.flags & BUS_RESET != 0
+    }
+
+    /// Indicates if the host configured the device in this poll
+    ///
+    /// This happens when the host sends SET_CONFIGURATION. The serial ports
+    /// are ready. See [`reconnects`] to learn if this is a new connection.
+    #[inline(always)]
+    pub fn configured(&self) -> bool {
+        self.flags & CONFIGURED_EVENT != 0
+    }
+
+    /// Indicates if the host suspended the USB bus in this poll
+    ///
+    /// The device stays suspended until the host resumes the bus, or
+    /// resets the device.
+    #[inline(always)]
+    pub fn suspended(&self) -> bool {
+        self.flags & SUSPENDED != 0
+    }
+
+    /// Indicates if the host resumed the USB bus in this poll
+    #[inline(always)]
+    pub fn resumed(&self) -> bool {
+        self.flags & RESUMED != 0
     }
 }
 
+/// The logging port's flags are shifted by this amount in `PollStatus`
+const LOGGER_SHIFT: u32 = 8;
+/// Selects one port's flags
+const CDC_MASK: u32 = 0xFF;
+
+// Device flags are above the port flags.
+const BUS_RESET: u32 = 1 << 16;
+const CONFIGURED_EVENT: u32 = 1 << 17;
+const SUSPENDED: u32 = 1 << 18;
+const RESUMED: u32 = 1 << 19;
+
 /// Drive the USB device event loop
 ///
 /// `poll` must be called fast enough to handled the speed of your
@@ -334,49 +1216,424 @@ impl PollStatus {
 /// unsafe { cortex_m::peripheral::NVIC::unmask(interrupt::USB_OTG1) };
 /// ```
 pub unsafe fn poll() -> PollStatus {
-    let flags = bindings::poll();
+    let mut configuration_changed = false;
+    let flags = interrupt::free(|cs| {
+        let configuration_changed = &mut configuration_changed;
+        let mut stack = match STACK.borrow(cs).try_borrow_mut() {
+            Ok(stack) => stack,
+            Err(_) => return 0,
+        };
+        let Stack {
+            device,
+            classes,
+            reboot_countdown,
+            dfu_detach,
+        } = match stack.as_mut() {
+            Some(stack) => stack,
+            None => return 0,
+        };
+
+        let previous = device.state();
+        classes.poll(device);
+        // Selecting the network's alternate setting cancels its transfers.
+        if let Some(endpoints) = classes.net().and_then(|net| net.take_data_reset()) {
+            device.bus().reset_endpoints(&endpoints);
+        }
+        let state = device.state();
+        DEVICE_STATE.store(state as u8, Ordering::Relaxed);
+        let reset = device.bus().take_reset();
+        let was_configured = CONFIGURED.load(Ordering::Relaxed);
+        let (configured, device_events) =
+            derive_device_events(previous, state, was_configured, reset);
+        CONFIGURED.store(configured, Ordering::Relaxed);
+        if reset {
+            RESETS.fetch_add(1, Ordering::Relaxed);
+        }
+        // Only configure the endpoints after SET_CONFIGURATION. A resume
+        // keeps the endpoints, their data toggles, and their transfers.
+        if device_events & CONFIGURED_EVENT != 0 {
+            device.bus().configure();
+            if EVER_CONFIGURED.swap(true, Ordering::Relaxed) {
+                RECONNECTS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        if configured != was_configured || reset {
+            *configuration_changed = true;
+        }
+
+        // After manifestation, the host resets the device. Start the new image.
+        if let Classes::Dfu(dfu) = classes {
+            if dfu.take_reboot() {
+                crate::reboot();
+            }
+        }
+
+        // The remaining work is for serial ports.
+        let (primary, mut secondary, dfu_runtime) = match classes {
+            Classes::Serial(primary) => (primary, None, None),
+            Classes::Dual(primary, secondary) => (primary, Some(secondary), None),
+            Classes::SerialDfu(primary, runtime) => (primary, None, Some(runtime)),
+            _ => return device_events,
+        };
+
+        // Send saved log records once a terminal opens the logging port,
+        // then move any new records out of the log queue.
+        if let Ok(mut buffer) = LOG_BUFFER.borrow(cs).try_borrow_mut() {
+            let mut buffer = buffer.as_mut();
+            if let Some(buffer) = buffer.as_mut() {
+                if configured && primary.dtr() {
+                    drain_log_buffer(buffer, primary);
+                }
+            }
+            drain_log_queue(buffer, primary, configured);
+        }
+
+        // A bootloader request schedules a reboot, which happens after a
+        // few SOFs. We count SOFs in the SOF interrupt.
+        let reboot = REBOOT_ON_134_BAUD.load(Ordering::Relaxed);
+        let mut bootloader_request = primary.take_bootloader_request();
+        if let Some(secondary) = secondary.as_mut() {
+            bootloader_request |= secondary.take_bootloader_request();
+        }
+        // A DFU detach request also schedules a reboot, which restarts the
+        // program. It's remembered across the reboot.
+        let detach = match dfu_runtime {
+            Some(runtime) => runtime.take_detach(),
+            None => false,
+        };
+        if detach && !*dfu_detach {
+            dfu::set_detach_request();
+            *dfu_detach = true;
+            *reboot_countdown = REBOOT_SOF_COUNT;
+            device.bus().set_sof_interrupt(true);
+        } else if bootloader_request && reboot && !*dfu_detach {
+            *reboot_countdown = REBOOT_SOF_COUNT;
+            device.bus().set_sof_interrupt(true);
+        } else if *reboot_countdown > 0 && !reboot && !*dfu_detach {
+            *reboot_countdown = 0;
+            device.bus().set_sof_interrupt(false);
+        } else if *reboot_countdown > 0 && device.bus().take_sof() {
+            *reboot_countdown -= 1;
+            if *reboot_countdown == 0 && *dfu_detach {
+                crate::reboot();
+            } else if *reboot_countdown == 0 {
+                crate::reboot_to_bootloader();
+            }
+        }
+
+        // The application's flags are in the low bits, and the logger's
+        // flags are in the high bits.
+        let logger = primary.take_events();
+        device_events
+            | match secondary {
+                Some(secondary) => secondary.take_events() | logger << LOGGER_SHIFT,
+                None => logger | logger << LOGGER_SHIFT,
+            }
+    });
+
+    // Wake async readers and writers outside of the critical section. When
+    // there's one port, there are no tasks waiting on the secondary port.
+    if configuration_changed {
+        wakers::wake_all();
+    } else {
+        wakers::wake(Port::Primary, (flags >> LOGGER_SHIFT) & CDC_MASK);
+        wakers::wake(Port::Secondary, flags & CDC_MASK);
+    }
     PollStatus { flags }
 }
 
 struct Logger {
     /// Tracks if we are (not) enabled
     enabled: bool,
-    /// A collection of targets that we are expected
-    /// to filter. If this is empty, we allow everything
-    filters: Filters,
+    /// Set if there's a log buffer
+    buffered: bool,
+    /// Writes the record text
+    formatter: Formatter,
+    /// Provides record timestamps
+    clock: Option<Clock>,
 }
 
 static mut LOGGER: Logger = Logger {
     enabled: false,
-    filters: Filters::empty(),
+    buffered: false,
+    formatter: format::default,
+    clock: None,
 };
 
 impl ::log::Log for Logger {
     fn enabled(&self, metadata: &::log::Metadata) -> bool {
         self.enabled // We're enabled
-            && bindings::is_configured() // The host has configured the USB device
+            && (self.buffered || CONFIGURED.load(Ordering::Relaxed)) // We can save the record, or the host has configured the USB device
             && metadata.level() <= ::log::max_level() // The log level is appropriate
-            && self.filters.is_enabled(metadata) // The target is in the filter list
+            && FILTERS.read(|filters| filters.is_enabled(metadata)) // The target is in the filter list
     }
 
     fn log(&self, record: &::log::Record) {
         if self.enabled(record.metadata()) {
-            use core::fmt::Write;
-            let result = cortex_m::interrupt::free(|_| {
-                writeln!(
-                    unsafe { Writer::new() },
-                    "[{} {}]: {}",
-                    record.level(),
-                    record.target(),
-                    record.args()
-                )
-            });
-            assert!(result.is_ok());
+            LOG_QUEUE.push(format_args!(
+                "{}",
+                Formatted {
+                    formatter: self.formatter,
+                    record,
+                    timestamp: self.clock.as_ref().map(Clock::timestamp),
+                }
+            ));
+            // The USB ISR, or the next poll, moves the record to the host.
+            cortex_m::peripheral::NVIC::pend(crate::interrupt::USB_OTG1);
         }
     }
 
     fn flush(&self) {
-        cortex_m::interrupt::free(|_| unsafe { bindings::usb_serial_flush_output() });
+        cortex_m::peripheral::NVIC::pend(crate::interrupt::USB_OTG1);
+    }
+}
+
+/// A collection of targets that we are expected
+/// to filter. If this is empty, we allow everything
+static FILTERS: SharedFilters = SharedFilters::new();
+
+/// The longest log filter command, in bytes
+const COMMAND_LEN: usize = 64;
+
+/// Changes the logger's filters while you're running
+///
+/// Acquire `LogFilters` from [`init`] or [`init_dual`]. The logger starts with
+/// the [`LoggingConfig::filters`], and sees your changes on its next record. Like
+/// `LoggingConfig::filters`, an empty collection lets all records pass through.
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+/// use bsp::hal::ral::usb::USB1;
+/// use log::LevelFilter;
+///
+/// let (poller, mut reader, mut filters) =
+///     bsp::usb::init(USB1::take().unwrap(), Default::default(), Default::default())
+///         .unwrap();
+///
+/// filters.set("motor", Some(LevelFilter::Debug)).unwrap();
+///
+/// // Or, let the host change the filters with commands
+/// // like "log motor debug".
+/// reader.read_log_commands(&mut filters).unwrap();
+/// ```
+// Uses a raw `*const ()` to ensure that LogFilters is not Send or Sync
+pub struct LogFilters {
+    /// A partial command from `feed`
+    line: [u8; COMMAND_LEN],
+    len: usize,
+    /// Set if the partial command is too long
+    overflow: bool,
+    _not_sync: core::marker::PhantomData<*const ()>,
+}
+
+/// OK to transfer across 'thread' boundaries, but not safe for
+/// multi-threaded access (Sync).
+unsafe impl Send for LogFilters {}
+
+impl LogFilters {
+    const fn new() -> Self {
+        LogFilters {
+            line: [0; COMMAND_LEN],
+            len: 0,
+            overflow: false,
+            _not_sync: core::marker::PhantomData,
+        }
+    }
+
+    /// Add a filter for `target`, or change the level of an existing filter
+    ///
+    /// A level of `None` logs all levels from the target, subject to the max level.
+    pub fn set(
+        &mut self,
+        target: &str,
+        level: Option<::log::LevelFilter>,
+    ) -> Result<(), FilterError> {
+        FILTERS.modify(|filters| filters.set(target, level))
+    }
+
+    /// Remove the filter for `target`
+    ///
+    /// Returns `false` if there was no filter for `target`.
+    pub fn remove(&mut self, target: &str) -> bool {
+        FILTERS.modify(|filters| filters.remove(target))
+    }
+
+    /// Remove all filters, which lets all records pass through
+    pub fn clear(&mut self) {
+        FILTERS.modify(|filters| filters.clear())
+    }
+
+    /// Returns the level of the filter for `target`
+    ///
+    /// The outer `Option` is `None` if there's no filter for `target`.
+    pub fn level(&self, target: &str) -> Option<Option<::log::LevelFilter>> {
+        FILTERS.read(|filters| filters.level(target))
+    }
+
+    /// Set the level for targets that don't match any filter
+    ///
+    /// See [`LoggingConfig::default_level`] for more information.
+    pub fn set_default_level(&mut self, level: ::log::LevelFilter) {
+        FILTERS.modify(|filters| filters.set_default_level(level))
+    }
+
+    /// Returns the level for targets that don't match any filter
+    pub fn default_level(&self) -> ::log::LevelFilter {
+        FILTERS.read(|filters| filters.default_level())
+    }
+
+    /// Change the filters with a text command, like `log motor debug`
+    ///
+    /// | Command                 | Effect                                    |
+    /// | ----------------------- | ----------------------------------------- |
+    /// | `log <target> <level>`  | Set the target's level (`off` to `trace`) |
+    /// | `log <target> all`      | Log all levels from the target            |
+    /// | `log <target> remove`   | Remove the target's filter                |
+    /// | `log default <level>`   | Set the level for unmatched targets       |
+    /// | `log clear`             | Remove all filters                        |
+    pub fn command(&mut self, command: &str) -> Result<(), FilterError> {
+        FILTERS.modify(|filters| filters.command(command))
+    }
+
+    /// Run each complete line in `bytes` as a [`command`](LogFilters::command)
+    ///
+    /// Saves a partial line for the next call. Logs a warning for each
+    /// command that fails.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if byte == b'\n' || byte == b'\r' {
+                let line = &self.line[..self.len];
+                let result = if self.overflow {
+                    Err(FilterError::InvalidCommand)
+                } else {
+                    core::str::from_utf8(line)
+                        .map_err(|_| FilterError::InvalidCommand)
+                        .and_then(|command| {
+                            if command.trim().is_empty() {
+                                Ok(())
+                            } else {
+                                FILTERS.modify(|filters| filters.command(command))
+                            }
+                        })
+                };
+                if let Err(err) = result {
+                    ::log::warn!("Log filter command failed: {:?}", err);
+                }
+                self.len = 0;
+                self.overflow = false;
+            } else if self.len < COMMAND_LEN {
+                self.line[self.len] = byte;
+                self.len += 1;
+            } else {
+                self.overflow = true;
+            }
+        }
+    }
+}
+
+/// Number of records in the log queue
+const LOG_QUEUE_LEN: usize = 32;
+/// The maximum size of a log record, in bytes
+///
+/// The logger truncates longer records.
+const LOG_RECORD_SIZE: usize = 128;
+
+/// Records waiting for `poll`
+static LOG_QUEUE: LogQueue<LOG_QUEUE_LEN, LOG_RECORD_SIZE> = LogQueue::new();
+
+/// The log buffer
+static LOG_BUFFER: Mutex<RefCell<Option<LogBuffer>>> = Mutex::new(RefCell::new(None));
+
+/// Returns the number of log records dropped by the logger
+///
+/// The logger drops records when its queue is full, which happens when you log
+/// faster than `poll` can send records to the host. If there's a log buffer,
+/// this count includes the records dropped by the buffer. See
+/// [`LoggingConfig::buffer`] for more information.
+pub fn dropped_records() -> usize {
+    let buffered = interrupt::free(|cs| {
+        LOG_BUFFER
+            .borrow(cs)
+            .try_borrow()
+            .ok()
+            .and_then(|buffer| buffer.as_ref().map(LogBuffer::dropped))
+            .unwrap_or(0)
+    });
+    LOG_QUEUE.dropped() + buffered
+}
+
+/// Move queued log records into the log buffer, or the serial port
+///
+/// If the host isn't listening, or if the log buffer still has records, the
+/// records go into the log buffer. Otherwise, they go to the serial port. Without
+/// a log buffer, the records go to the serial port once the host configures the
+/// device. Stops when the serial port doesn't have room for the next record.
+///
+/// # Safety
+///
+/// Must only be called from a critical section, since this consumes the log queue.
+unsafe fn drain_log_queue(mut buffer: Option<&mut LogBuffer>, serial: &mut Cdc, configured: bool) {
+    while let Some(record) = LOG_QUEUE.front() {
+        let listening = configured && serial.dtr();
+        match buffer.as_mut() {
+            Some(buffer) if !listening || !buffer.is_empty() => {
+                // Records are formatted from strings, and truncated at character boundaries.
+                let text = core::str::from_utf8(record).unwrap_or("");
+                buffer.push(format_args!("{}\n", text));
+            }
+            _ if !configured => {}
+            _ => {
+                // Terminate the record here, so truncated records still end a line.
+                if log_text_len(record) + LINE_ENDING.len() > serial.write_available() {
+                    break;
+                }
+                write_log_text(serial, record);
+                serial.write(LINE_ENDING);
+            }
+        }
+        LOG_QUEUE.pop_front();
+    }
+}
+
+/// Move saved log records into the serial port, oldest first
+///
+/// Stops when the serial port doesn't have room for the next record.
+fn drain_log_buffer(buffer: &mut LogBuffer, serial: &mut Cdc) {
+    while let Some((first, second)) = buffer.front() {
+        let len = log_text_len(first) + log_text_len(second);
+        if len > cdc::TX_LEN {
+            // This record will never fit.
+            buffer.drop_front();
+            continue;
+        }
+        if len > serial.write_available() {
+            break;
+        }
+        write_log_text(serial, first);
+        write_log_text(serial, second);
+        buffer.pop_front();
+    }
+}
+
+/// Returns the number of bytes written by [`write_log_text`]
+fn log_text_len(text: &[u8]) -> usize {
+    text.len() + (LINE_ENDING.len() - 1) * text.iter().filter(|&&byte| byte == b'\n').count()
+}
+
+/// Replaces each newline in log text
+///
+/// The `\0` matches the `Writer`'s `fmt::Write` implementation.
+const LINE_ENDING: &[u8] = b"\0\r\n";
+
+/// Write log text, replacing each newline with [`LINE_ENDING`]
+fn write_log_text(serial: &mut Cdc, text: &[u8]) {
+    let mut lines = text.split(|&byte| byte == b'\n');
+    if let Some(line) = lines.next() {
+        serial.write(line);
+    }
+    for line in lines {
+        serial.write(LINE_ENDING);
+        serial.write(line);
     }
 }
 
@@ -384,7 +1641,7 @@ impl ::log::Log for Logger {
 /// the CR-LF chars: `\r\n`
 pub fn write_data(buffer: &[u8]) -> Result<usize, Error> {
     cortex_m::interrupt::free(|_| {
-        let mut w = unsafe { Writer::new() };
+        let mut w = unsafe { Writer::new(Port::Primary) };
         let mut offset: usize = 0;
 
         'writer: loop {
@@ -413,17 +1670,28 @@ pub fn write_data(buffer: &[u8]) -> Result<usize, Error> {
 ///
 /// Use [`Writer::write`](Writer::write()) to write byte
 /// buffers. Or, use the standard `write!()` macro to serialize data to
-/// the writer.
-pub struct Writer(core::marker::PhantomData<*const ()>);
+/// the writer. To send packets that the host can reliably separate,
+/// wrap the writer in a [`FramedWriter`](framed::FramedWriter).
+///
+/// `Writer` implements the `embedded-io` `Write` and `WriteReady` traits,
+/// and the `embedded-hal` `serial::Write` trait. The `embedded-io` methods
+/// block until there's space, so the USB interrupt must call `poll`.
+pub struct Writer {
+    port: Port,
+    _not_sync: core::marker::PhantomData<*const ()>,
+}
 
 impl Writer {
     /// # Safety
     ///
-    /// There should only be one `Writer` instance in the program. It's
-    /// either given to the user, or it's used in the logger. The implementor
-    /// must ensure that `Writer` isn't used in both places!
-    const unsafe fn new() -> Self {
-        Writer(core::marker::PhantomData)
+    /// There should only be one `Writer` instance for each port. The primary
+    /// port's writer is either given to the user, or it's used in the logger.
+    /// The implementor must ensure that `Writer` isn't used in both places!
+    const unsafe fn new(port: Port) -> Self {
+        Writer {
+            port,
+            _not_sync: core::marker::PhantomData,
+        }
     }
 
     /// Writes raw bytes to the USB serial host
@@ -433,12 +1701,7 @@ impl Writer {
     /// important that you write a complete message, you'll need to retry the
     /// call with the rest of the data.
     pub fn write<B: AsRef<[u8]>>(&mut self, buffer: B) -> Result<usize, Error> {
-        let res = unsafe { bindings::serial_write(buffer) };
-        match res {
-            bindings::SERIAL_NOT_CONFIGURED => Err(Error::NotConfigured),
-            res if res >= 0 => Ok(res as usize),
-            _ => Err(Error::Io),
-        }
+        with_port(self.port, |serial| serial.write(buffer.as_ref()))
     }
 
     /// Flush the written USB data
@@ -449,12 +1712,28 @@ impl Writer {
     /// since the driver will attempt to pack multiple writes into a
     /// single USB transfer.
     pub fn flush(&mut self) -> Result<(), Error> {
-        if !bindings::is_configured() {
-            return Err(Error::NotConfigured);
-        }
+        with_port(self.port, |serial| serial.flush())
+    }
 
-        unsafe { bindings::usb_serial_flush_output() };
-        Ok(())
+    /// Returns the state of the DTR control line
+    ///
+    /// See [`Reader::dtr`] for more information.
+    pub fn dtr(&self) -> bool {
+        with_port(self.port, |serial| serial.dtr()).unwrap_or(false)
+    }
+
+    /// Returns the state of the RTS control line
+    ///
+    /// See [`Reader::rts`] for more information.
+    pub fn rts(&self) -> bool {
+        with_port(self.port, |serial| serial.rts()).unwrap_or(false)
+    }
+
+    /// Returns the line coding requested by the host
+    ///
+    /// See [`Reader::line_coding`] for more information.
+    pub fn line_coding(&self) -> LineCoding {
+        with_port(self.port, |serial| serial.line_coding()).unwrap_or_default()
     }
 }
 
@@ -462,7 +1741,7 @@ unsafe impl Send for Writer {}
 
 impl fmt::Write for Writer {
     fn write_str(&mut self, string: &str) -> fmt::Result {
-        if !bindings::is_configured() {
+        if !CONFIGURED.load(Ordering::Relaxed) {
             return Ok(());
         }
 
@@ -493,26 +1772,126 @@ impl fmt::Write for Writer {
 }
 
 /// A type that can read USB serial messages from a host
+///
+/// `Reader` implements the `embedded-io` `Read` and `ReadReady` traits,
+/// and the `embedded-hal` `serial::Read` trait. The `embedded-io` `read`
+/// blocks until there's data, so the USB interrupt must call `poll`.
 // Uses a raw `*const ()` to ensure that Reader is not Send or Sync
-pub struct Reader(core::marker::PhantomData<*const ()>);
+pub struct Reader {
+    port: Port,
+    _not_sync: core::marker::PhantomData<*const ()>,
+}
 
 /// OK to transfer across 'thread' boundaries, but not safe for
 /// multi-threaded access (Sync).
 unsafe impl Send for Reader {}
 
 impl Reader {
+    const fn new(port: Port) -> Self {
+        Reader {
+            port,
+            _not_sync: core::marker::PhantomData,
+        }
+    }
+
     /// Read from the USB serial endpoint into buffer. Returns the number
     /// of bytes read, or zero if there is no data.
-    pub fn read<B: AsMut<[u8]>>(&mut self, buffer: B) -> Result<usize, Error> {
-        if !bindings::is_configured() {
-            return Err(Error::NotConfigured);
-        }
+    pub fn read<B: AsMut<[u8]>>(&mut self, mut buffer: B) -> Result<usize, Error> {
+        with_port(self.port, |serial| serial.read(buffer.as_mut()))
+    }
 
-        let res = unsafe { bindings::serial_read(buffer) };
-        if res < 0 {
-            Err(Error::Io)
-        } else {
-            Ok(res as usize)
+    /// Read log filter commands from the USB serial endpoint
+    ///
+    /// Reads all available data, and [`feeds`](LogFilters::feed) it to `filters`.
+    /// Use this if your serial port only receives commands. Returns the number
+    /// of bytes read.
+    pub fn read_log_commands(&mut self, filters: &mut LogFilters) -> Result<usize, Error> {
+        let mut buffer = [0; COMMAND_LEN];
+        let mut total = 0;
+        loop {
+            let count = self.read(&mut buffer)?;
+            if count == 0 {
+                return Ok(total);
+            }
+            filters.feed(&buffer[..count]);
+            total += count;
         }
     }
+
+    /// Returns the state of the data terminal ready (DTR) control line
+    ///
+    /// Most terminals set DTR when they open the serial port, and clear
+    /// DTR when they close the port. Returns `false` if the host hasn't
+    /// configured the device.
+    pub fn dtr(&self) -> bool {
+        with_port(self.port, |serial| serial.dtr()).unwrap_or(false)
+    }
+
+    /// Returns the state of the request to send (RTS) control line
+    ///
+    /// Returns `false` if the host hasn't configured the device.
+    pub fn rts(&self) -> bool {
+        with_port(self.port, |serial| serial.rts()).unwrap_or(false)
+    }
+
+    /// Returns the line coding requested by the host
+    ///
+    /// The line coding describes the baud rate, parity, stop bits, and data bits
+    /// that the host selected when it opened the serial port. The USB serial port
+    /// doesn't need these settings, but your program might use them, or forward
+    /// them to a hardware UART. Returns the default line coding if the host hasn't
+    /// configured the device.
+    pub fn line_coding(&self) -> LineCoding {
+        with_port(self.port, |serial| serial.line_coding()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{derive_device_events, BUS_RESET, CONFIGURED_EVENT, RESUMED, SUSPENDED};
+    use usb_device::device::UsbDeviceState::{Addressed, Configured, Default, Suspend};
+
+    #[test]
+    fn suspend_keeps_configuration() {
+        assert_eq!(
+            derive_device_events(Addressed, Configured, false, false),
+            (true, CONFIGURED_EVENT)
+        );
+        assert_eq!(
+            derive_device_events(Configured, Configured, true, false),
+            (true, 0)
+        );
+        assert_eq!(
+            derive_device_events(Configured, Suspend, true, false),
+            (true, SUSPENDED)
+        );
+        assert_eq!(
+            derive_device_events(Suspend, Suspend, true, false),
+            (true, 0)
+        );
+        assert_eq!(
+            derive_device_events(Suspend, Configured, true, false),
+            (true, RESUMED)
+        );
+    }
+
+    #[test]
+    fn reset_discards_configuration() {
+        assert_eq!(
+            derive_device_events(Suspend, Default, true, true),
+            (false, BUS_RESET | RESUMED)
+        );
+        assert_eq!(
+            derive_device_events(Configured, Configured, true, true),
+            (true, BUS_RESET | CONFIGURED_EVENT)
+        );
+        assert_eq!(
+            derive_device_events(Addressed, Suspend, false, false),
+            (false, SUSPENDED)
+        );
+        assert_eq!(
+            derive_device_events(Suspend, Addressed, false, false),
+            (false, RESUMED)
+        );
+    }
 }
@@ -0,0 +1,742 @@
+//! A `usb-device` bus for the USB1 controller
+//!
+//! The driver supports high speed (480Mbit/s) and full speed (12Mbit/s) devices.
+//! Each endpoint has one transfer descriptor, and one packet buffer, so there's at
+//! most one transfer in flight for each endpoint direction. Endpoint buffers, queue
+//! heads, and transfer descriptors are all in DTCM, so there's no cache maintenance.
+
+use super::queue::{QueueHeads, TransferDescriptors, MAX_ENDPOINTS, QH_COUNT};
+use super::registers as reg;
+use core::{
+    cell::RefCell,
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use cortex_m::interrupt::{self, Mutex};
+use usb_device::{
+    bus::{PollResult, UsbBus, UsbBusAllocator},
+    descriptor::DescriptorWriter,
+    endpoint::{
+        Endpoint as EndpointHandle, EndpointAddress, EndpointDirection, EndpointType, In, Out,
+    },
+    Result, UsbDirection, UsbError,
+};
+
+/// The number of bytes available for endpoint buffers
+///
+/// Each endpoint allocates its max packet size from this memory.
+const EP_MEMORY_LEN: usize = 8 * 1024;
+
+#[repr(C, align(4))]
+struct EndpointMemory([u8; EP_MEMORY_LEN]);
+
+static mut EP_MEMORY: EndpointMemory = EndpointMemory([0; EP_MEMORY_LEN]);
+static mut QUEUE_HEADS: QueueHeads = QueueHeads::new();
+static mut TRANSFERS: TransferDescriptors = TransferDescriptors::new();
+
+/// Set when the host is running the bus at high speed
+static HIGH_SPEED: AtomicBool = AtomicBool::new(false);
+
+/// Returns `true` if the device is running at high speed
+///
+/// The value is only meaningful after the host resets the device.
+pub fn is_high_speed() -> bool {
+    HIGH_SPEED.load(Ordering::Relaxed)
+}
+
+/// The max packet size of a high speed bulk endpoint
+pub const MAX_BULK_PACKET_LEN: usize = 512;
+/// The largest max packet size of a full speed bulk endpoint
+const FULL_SPEED_BULK_PACKET_LEN: usize = 64;
+
+/// Returns the bulk endpoint max packet size for the bus speed
+pub fn bulk_packet_len() -> usize {
+    if is_high_speed() {
+        MAX_BULK_PACKET_LEN
+    } else {
+        FULL_SPEED_BULK_PACKET_LEN
+    }
+}
+
+/// A bulk endpoint that describes itself for the bus speed
+///
+/// High speed bulk endpoints have 512 byte packets, and full speed bulk
+/// endpoints have at most 64 byte packets. We allocate the endpoint for
+/// high speed, then allocate an alias, with the same address, that only
+/// describes the endpoint at full speed. Classes read and write through
+/// the high speed endpoint, and use [`describe`](Self::describe) to write
+/// the endpoint descriptor.
+pub struct BulkEndpoint<'a, D: EndpointDirection> {
+    endpoint: EndpointHandle<'a, Bus, D>,
+    full_speed: EndpointHandle<'a, Bus, D>,
+}
+
+/// A bulk IN endpoint
+pub type BulkIn<'a> = BulkEndpoint<'a, In>;
+/// A bulk OUT endpoint
+pub type BulkOut<'a> = BulkEndpoint<'a, Out>;
+
+impl<'a, D: EndpointDirection> BulkEndpoint<'a, D> {
+    pub fn new(alloc: &'a UsbBusAllocator<Bus>) -> Self {
+        let endpoint = alloc.bulk(MAX_BULK_PACKET_LEN as u16);
+        let full_speed = alloc
+            .alloc(
+                Some(endpoint.address()),
+                EndpointType::Bulk,
+                FULL_SPEED_BULK_PACKET_LEN as u16,
+                0,
+            )
+            .expect("alloc_ep failed");
+        BulkEndpoint {
+            endpoint,
+            full_speed,
+        }
+    }
+
+    /// Write the endpoint descriptor for the bus speed
+    pub fn describe(&self, writer: &mut DescriptorWriter) -> Result<()> {
+        writer.endpoint(self.for_speed())
+    }
+
+    /// Write the endpoint descriptor for the bus speed, followed by
+    /// the extra data from `f`
+    pub fn describe_ex(
+        &self,
+        writer: &mut DescriptorWriter,
+        f: impl FnOnce(&mut [u8]) -> Result<usize>,
+    ) -> Result<()> {
+        writer.endpoint_ex(self.for_speed(), f)
+    }
+
+    fn for_speed(&self) -> &EndpointHandle<'a, Bus, D> {
+        if is_high_speed() {
+            &self.endpoint
+        } else {
+            &self.full_speed
+        }
+    }
+}
+
+impl<'a, D: EndpointDirection> core::ops::Deref for BulkEndpoint<'a, D> {
+    type Target = EndpointHandle<'a, Bus, D>;
+    fn deref(&self) -> &Self::Target {
+        &self.endpoint
+    }
+}
+
+/// Returns `true` if VBUS is valid, which means that we're connected to a host
+pub fn vbus_valid() -> bool {
+    // Safety: atomic read of a status register.
+    let stat = unsafe { reg::read(reg::USB1_VBUS_DETECT_STAT) };
+    stat & reg::USB1_VBUS_DETECT_STAT_VBUS_VALID != 0
+}
+
+fn queue_heads() -> &'static mut QueueHeads {
+    // Safety: only accessed by the bus, in a critical section.
+    unsafe { &mut *ptr::addr_of_mut!(QUEUE_HEADS) }
+}
+
+fn transfers() -> &'static mut TransferDescriptors {
+    // Safety: only accessed by the bus, in a critical section.
+    unsafe { &mut *ptr::addr_of_mut!(TRANSFERS) }
+}
+
+/// Returns the queue head / transfer descriptor index for an endpoint
+fn index(addr: EndpointAddress) -> usize {
+    addr.index() * 2 + addr.is_in() as usize
+}
+
+/// Returns the ENDPTPRIME / ENDPTCOMPLETE / ENDPTFLUSH bit for an index
+fn endpoint_bit(index: usize) -> u32 {
+    let number = (index / 2) as u32;
+    if index % 2 == 1 {
+        1 << (number + 16)
+    } else {
+        1 << number
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Endpoint {
+    kind: EndpointType,
+    max_packet_size: u16,
+    /// Offset of this endpoint's buffer in the endpoint memory
+    offset: usize,
+    /// Number of bytes scheduled in the most recent OUT transfer
+    primed: usize,
+}
+
+impl Endpoint {
+    /// Returns the endpoint's buffer
+    ///
+    /// # Safety
+    ///
+    /// Caller must make sure that there's only one mutable
+    /// reference to the buffer.
+    unsafe fn buffer(&self) -> &'static mut [u8] {
+        let memory = ptr::addr_of_mut!(EP_MEMORY).cast::<u8>();
+        core::slice::from_raw_parts_mut(memory.add(self.offset), self.max_packet_size as usize)
+    }
+
+    /// Returns the max packet length for the bus speed
+    ///
+    /// Full speed bulk and interrupt endpoints cannot exceed 64 bytes.
+    fn packet_len(&self, high_speed: bool) -> u16 {
+        match self.kind {
+            EndpointType::Bulk | EndpointType::Interrupt if !high_speed => {
+                self.max_packet_size.min(64)
+            }
+            EndpointType::Isochronous { .. } if !high_speed => self.max_packet_size.min(1023),
+            _ => self.max_packet_size,
+        }
+    }
+
+    /// Returns the ENDPTCTRL endpoint type
+    fn type_bits(&self) -> u32 {
+        (self.kind.to_bm_attributes() & 0b11) as u32
+    }
+
+    /// Returns the queue head's high-bandwidth multiplier
+    fn mult(&self) -> u32 {
+        match self.kind {
+            EndpointType::Isochronous { .. } => 1,
+            _ => 0,
+        }
+    }
+}
+
+struct State {
+    endpoints: [Option<Endpoint>; QH_COUNT],
+    /// Number of allocated bytes in the endpoint memory
+    allocated: usize,
+    /// OUT endpoints with a completed transfer that hasn't been read
+    ep_out_ready: u16,
+    /// Set when we've seen a suspend that we haven't reported
+    suspend_pending: bool,
+    /// Set when the bus is suspended
+    suspended: bool,
+    /// Set when we've seen a resume that we haven't reported
+    resume_pending: bool,
+    /// Set when we've seen a start of frame
+    sof: bool,
+    /// Set when the host reset the bus
+    bus_reset: bool,
+}
+
+impl State {
+    /// Schedule a receive on an OUT endpoint
+    fn prime_out(&mut self, idx: usize) {
+        if let Some(ep) = self.endpoints[idx].as_mut() {
+            let len = ep.packet_len(is_high_speed()) as usize;
+            ep.primed = len;
+            // Safety: the controller owns the buffer until the
+            // transfer completes. We only touch it in `read`.
+            let buffer = unsafe { ep.buffer() };
+            schedule(idx, buffer.as_ptr(), len);
+        }
+    }
+}
+
+/// Schedule a transfer on an endpoint, then prime the endpoint
+fn schedule(idx: usize, buffer: *const u8, len: usize) {
+    let td = &mut transfers().0[idx];
+    td.prepare(buffer, len);
+    queue_heads().0[idx].attach(td);
+    cortex_m::asm::dsb();
+    // Safety: writing zero to a bit has no effect.
+    unsafe { reg::write(reg::ENDPTPRIME, endpoint_bit(idx)) };
+}
+
+/// Flush endpoints, and wait for the flush to complete
+fn flush(bits: u32) {
+    // Safety: the controller clears the bits when it's done.
+    unsafe {
+        reg::write(reg::ENDPTFLUSH, bits);
+        while reg::read(reg::ENDPTFLUSH) & bits != 0 {}
+    }
+}
+
+fn update_speed() {
+    // Safety: atomic read of a status register.
+    let portsc = unsafe { reg::read(reg::PORTSC1) };
+    HIGH_SPEED.store(portsc & reg::PORTSC1_HSP != 0, Ordering::Relaxed);
+}
+
+/// Start the USB PLL (PLL3)
+///
+/// # Safety
+///
+/// Modifies the CCM's analog registers. Caller must make sure that
+/// nothing else is using PLL3.
+unsafe fn pll_start() {
+    use reg::*;
+    loop {
+        let pll = read(PLL_USB1);
+        if pll & PLL_USB1_DIV_SELECT != 0 {
+            // We're never supposed to use the 528MHz mode.
+            write(PLL_USB1_CLR, PLL_USB1_BYPASS_CLK_SRC_MASK);
+            write(PLL_USB1_SET, PLL_USB1_BYPASS);
+            write(
+                PLL_USB1_CLR,
+                PLL_USB1_POWER | PLL_USB1_DIV_SELECT | PLL_USB1_ENABLE | PLL_USB1_EN_USB_CLKS,
+            );
+        } else if pll & PLL_USB1_ENABLE == 0 {
+            write(PLL_USB1_SET, PLL_USB1_ENABLE);
+        } else if pll & PLL_USB1_POWER == 0 {
+            write(PLL_USB1_SET, PLL_USB1_POWER);
+        } else if pll & PLL_USB1_LOCK == 0 {
+            // Wait for lock...
+        } else if pll & PLL_USB1_BYPASS != 0 {
+            write(PLL_USB1_CLR, PLL_USB1_BYPASS);
+        } else if pll & PLL_USB1_EN_USB_CLKS == 0 {
+            write(PLL_USB1_SET, PLL_USB1_EN_USB_CLKS);
+        } else {
+            return;
+        }
+    }
+}
+
+/// The USB1 bus
+///
+/// There should only be one of these in the program.
+pub struct Bus {
+    state: Mutex<RefCell<State>>,
+}
+
+impl Bus {
+    /// Create the USB bus
+    ///
+    /// # Safety
+    ///
+    /// Caller must make sure that there's only one `Bus`.
+    pub unsafe fn new() -> Self {
+        Bus {
+            state: Mutex::new(RefCell::new(State {
+                endpoints: [None; QH_COUNT],
+                allocated: 0,
+                ep_out_ready: 0,
+                suspend_pending: false,
+                suspended: false,
+                resume_pending: false,
+                sof: false,
+                bus_reset: false,
+            })),
+        }
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        interrupt::free(|cs| f(&mut self.state.borrow(cs).borrow_mut()))
+    }
+
+    /// Enable all allocated endpoints
+    ///
+    /// Call this once the host configures the device. This primes
+    /// all OUT endpoints to receive data.
+    pub fn configure(&self) {
+        self.with(|state| {
+            let high_speed = is_high_speed();
+            for number in 1..MAX_ENDPOINTS {
+                let (out_idx, in_idx) = (number * 2, number * 2 + 1);
+                let out = state.endpoints[out_idx];
+                let inn = state.endpoints[in_idx];
+                if out.is_none() && inn.is_none() {
+                    continue;
+                }
+
+                // An unused direction must not be a control endpoint.
+                const BULK: u32 = 0b10;
+                let mut ctrl = 0;
+                if let Some(ep) = out {
+                    queue_heads().0[out_idx].configure(ep.packet_len(high_speed), false, ep.mult());
+                    transfers().0[out_idx].clear();
+                    ctrl |= reg::ENDPTCTRL_RXE | reg::ENDPTCTRL_RXR;
+                }
+                ctrl |= out.map_or(BULK, |ep| ep.type_bits()) << reg::ENDPTCTRL_RXT_SHIFT;
+                if let Some(ep) = inn {
+                    queue_heads().0[in_idx].configure(ep.packet_len(high_speed), false, ep.mult());
+                    transfers().0[in_idx].clear();
+                    ctrl |= reg::ENDPTCTRL_TXE | reg::ENDPTCTRL_TXR;
+                }
+                ctrl |= inn.map_or(BULK, |ep| ep.type_bits()) << reg::ENDPTCTRL_TXT_SHIFT;
+
+                // Safety: endpoint number is valid.
+                unsafe { reg::write(reg::endptctrl(number), ctrl) };
+                if out.is_some() {
+                    state.prime_out(out_idx);
+                }
+            }
+        })
+    }
+
+    /// Cancel the transfers on `endpoints`, and reset their data toggles
+    ///
+    /// Call this when the host selects an interface's alternate setting, which
+    /// resets the interface's endpoints. The OUT endpoints are primed again.
+    pub fn reset_endpoints(&self, endpoints: &[EndpointAddress]) {
+        self.with(|state| {
+            let bits = endpoints
+                .iter()
+                .fold(0, |bits, &addr| bits | endpoint_bit(index(addr)));
+            flush(bits);
+            // Safety: writing one only clears these endpoints' completions.
+            unsafe { reg::write(reg::ENDPTCOMPLETE, bits) };
+            for &addr in endpoints {
+                let idx = index(addr);
+                if addr.index() >= MAX_ENDPOINTS || state.endpoints[idx].is_none() {
+                    continue;
+                }
+                transfers().0[idx].clear();
+                let toggle_reset = if addr.is_in() {
+                    reg::ENDPTCTRL_TXR
+                } else {
+                    reg::ENDPTCTRL_RXR
+                };
+                // Safety: endpoint number is valid. The critical section
+                // prevents a concurrent modification.
+                unsafe { reg::modify(reg::endptctrl(addr.index()), |ctrl| ctrl | toggle_reset) };
+                if addr.is_out() {
+                    state.ep_out_ready &= !(1 << addr.index());
+                    state.prime_out(idx);
+                }
+            }
+        })
+    }
+
+    /// Enable or disable the start-of-frame (SOF) interrupt
+    ///
+    /// When enabled, a SOF wakes the USB ISR every (micro)frame.
+    pub fn set_sof_interrupt(&self, enable: bool) {
+        // Safety: critical section prevents a concurrent modification.
+        interrupt::free(|_| unsafe {
+            reg::modify(reg::USBINTR, |intr| {
+                if enable {
+                    intr | reg::USBINTR_SRE
+                } else {
+                    intr & !reg::USBINTR_SRE
+                }
+            })
+        });
+    }
+
+    /// Returns `true` if there was a start-of-frame since the last call
+    pub fn take_sof(&self) -> bool {
+        self.with(|state| core::mem::replace(&mut state.sof, false))
+    }
+
+    /// Returns `true` if the host reset the bus since the last call
+    pub fn take_reset(&self) -> bool {
+        self.with(|state| core::mem::replace(&mut state.bus_reset, false))
+    }
+}
+
+impl UsbBus for Bus {
+    fn alloc_ep(
+        &mut self,
+        ep_dir: UsbDirection,
+        ep_addr: Option<EndpointAddress>,
+        ep_type: EndpointType,
+        max_packet_size: u16,
+        _interval: u8,
+    ) -> Result<EndpointAddress> {
+        self.with(|state| {
+            let addr = match ep_addr {
+                Some(addr) => {
+                    if addr.index() >= MAX_ENDPOINTS || addr.direction() != ep_dir {
+                        return Err(UsbError::InvalidEndpoint);
+                    }
+                    match state.endpoints[index(addr)] {
+                        // An alias that describes the endpoint with a smaller
+                        // packet size. See `BulkEndpoint`.
+                        Some(ep) if ep.kind == ep_type && max_packet_size <= ep.max_packet_size => {
+                            return Ok(addr);
+                        }
+                        Some(_) => return Err(UsbError::InvalidEndpoint),
+                        None => addr,
+                    }
+                }
+                None => (1..MAX_ENDPOINTS)
+                    .map(|number| EndpointAddress::from_parts(number, ep_dir))
+                    .find(|addr| state.endpoints[index(*addr)].is_none())
+                    .ok_or(UsbError::EndpointOverflow)?,
+            };
+
+            // Only endpoint 0 is a control endpoint, and it's always a control endpoint.
+            if (addr.index() == 0) != (ep_type == EndpointType::Control) {
+                return Err(UsbError::Unsupported);
+            }
+            if max_packet_size > 1024 {
+                return Err(UsbError::Unsupported);
+            }
+
+            let len = (max_packet_size as usize + 3) & !3;
+            if state.allocated + len > EP_MEMORY_LEN {
+                return Err(UsbError::EndpointMemoryOverflow);
+            }
+            state.endpoints[index(addr)] = Some(Endpoint {
+                kind: ep_type,
+                max_packet_size,
+                offset: state.allocated,
+                primed: 0,
+            });
+            state.allocated += len;
+            Ok(addr)
+        })
+    }
+
+    fn enable(&mut self) {
+        let ep0_max_packet_size = self.with(|state| {
+            state.endpoints[0]
+                .map(|ep| ep.max_packet_size)
+                .unwrap_or(64)
+        });
+
+        // Safety: we're the only user of USB1, and the USB PLL.
+        unsafe {
+            use reg::*;
+            pll_start();
+            write(PMU_REG_3P0, PMU_REG_3P0_USB);
+            modify(CCM_CCGR6, |ccgr| ccgr | CCGR6_USBOH3_ON);
+            write(BURSTSIZE, 0x0404);
+
+            if read(USBPHY1_PWD) & USBPHY_PWD_MASK != 0 || read(USBMODE) & USBMODE_CM_MASK != 0 {
+                // The controller is on from a previous use. Reset the
+                // controller and PHY to start from a clean slate.
+                write(USBPHY1_CTRL_SET, USBPHY_CTRL_SFTRST);
+                modify(USBCMD, |cmd| cmd | USBCMD_RST);
+                while read(USBCMD) & USBCMD_RST != 0 {}
+                cortex_m::peripheral::NVIC::unpend(crate::interrupt::USB_OTG1);
+                write(USBPHY1_CTRL_CLR, USBPHY_CTRL_SFTRST);
+            }
+
+            write(USBPHY1_CTRL_CLR, USBPHY_CTRL_CLKGATE);
+            write(USBPHY1_PWD, 0);
+
+            write(USBMODE, USBMODE_CM_DEVICE | USBMODE_SLOM);
+            let qhs = queue_heads();
+            qhs.0[0].configure(ep0_max_packet_size, true, 0);
+            qhs.0[1].configure(ep0_max_packet_size, false, 0);
+            write(ENDPTLISTADDR, qhs as *const QueueHeads as usize as u32);
+
+            write(
+                USBINTR,
+                USBINTR_UE | USBINTR_UEE | USBINTR_PCE | USBINTR_URE | USBINTR_SLE,
+            );
+            write(USBCMD, USBCMD_RS);
+        }
+    }
+
+    fn reset(&self) {
+        self.with(|state| {
+            // Safety: clearing status and endpoints while we own the bus.
+            unsafe {
+                reg::write(reg::ENDPTSETUPSTAT, reg::read(reg::ENDPTSETUPSTAT));
+                reg::write(reg::ENDPTCOMPLETE, reg::read(reg::ENDPTCOMPLETE));
+                while reg::read(reg::ENDPTPRIME) != 0 {}
+                flush(u32::MAX);
+                for number in 1..MAX_ENDPOINTS {
+                    reg::write(reg::endptctrl(number), 0);
+                }
+            }
+            update_speed();
+
+            for td in transfers().0.iter_mut() {
+                td.clear();
+            }
+            if let Some(ep0) = state.endpoints[0] {
+                let qhs = queue_heads();
+                qhs.0[0].configure(ep0.max_packet_size, true, 0);
+                qhs.0[1].configure(ep0.max_packet_size, false, 0);
+            }
+
+            state.ep_out_ready = 0;
+            state.suspend_pending = false;
+            state.suspended = false;
+            state.resume_pending = false;
+            state.bus_reset = true;
+        })
+    }
+
+    fn set_device_address(&self, addr: u8) {
+        // The controller applies the address after the status stage.
+        // Safety: atomic write to a register we own.
+        unsafe {
+            reg::write(
+                reg::DEVICEADDR,
+                ((addr as u32) << reg::DEVICEADDR_USBADR_SHIFT) | reg::DEVICEADDR_USBADRA,
+            )
+        };
+    }
+
+    fn write(&self, ep_addr: EndpointAddress, buf: &[u8]) -> Result<usize> {
+        self.with(|state| {
+            let idx = index(ep_addr);
+            let ep = match state.endpoints.get(idx) {
+                Some(Some(ep)) if ep_addr.is_in() => ep,
+                _ => return Err(UsbError::InvalidEndpoint),
+            };
+            if buf.len() > ep.max_packet_size as usize {
+                return Err(UsbError::BufferOverflow);
+            }
+            if transfers().0[idx].is_active() {
+                return Err(UsbError::WouldBlock);
+            }
+
+            // Safety: the previous transfer is complete, so we own the buffer.
+            let buffer = unsafe { ep.buffer() };
+            buffer[..buf.len()].copy_from_slice(buf);
+            schedule(idx, buffer.as_ptr(), buf.len());
+            Ok(buf.len())
+        })
+    }
+
+    fn read(&self, ep_addr: EndpointAddress, buf: &mut [u8]) -> Result<usize> {
+        self.with(|state| {
+            let idx = index(ep_addr);
+            let ep = match state.endpoints.get(idx) {
+                Some(Some(ep)) if ep_addr.is_out() => *ep,
+                _ => return Err(UsbError::InvalidEndpoint),
+            };
+
+            // Safety: register access while we own the bus.
+            if idx == 0 && unsafe { reg::read(reg::ENDPTSETUPSTAT) } & 1 != 0 {
+                if buf.len() < 8 {
+                    return Err(UsbError::BufferOverflow);
+                }
+                let setup = unsafe {
+                    reg::write(reg::ENDPTSETUPSTAT, 1);
+                    loop {
+                        reg::modify(reg::USBCMD, |cmd| cmd | reg::USBCMD_SUTW);
+                        let setup = queue_heads().0[0].setup();
+                        if reg::read(reg::USBCMD) & reg::USBCMD_SUTW != 0 {
+                            break setup;
+                        }
+                    }
+                };
+                // Safety: register access while we own the bus.
+                unsafe { reg::modify(reg::USBCMD, |cmd| cmd & !reg::USBCMD_SUTW) };
+
+                // Cancel anything from the previous control transfer, then prepare
+                // for the data or status stage.
+                flush(endpoint_bit(0) | endpoint_bit(1));
+                transfers().0[0].clear();
+                transfers().0[1].clear();
+                state.ep_out_ready &= !1;
+                state.prime_out(0);
+
+                buf[..4].copy_from_slice(&setup[0].to_le_bytes());
+                buf[4..8].copy_from_slice(&setup[1].to_le_bytes());
+                return Ok(8);
+            }
+
+            let mask = 1 << ep_addr.index();
+            let td = &transfers().0[idx];
+            if state.ep_out_ready & mask == 0 || td.is_active() {
+                return Err(UsbError::WouldBlock);
+            }
+            let len = ep.primed.saturating_sub(td.remaining());
+            if len > buf.len() {
+                return Err(UsbError::BufferOverflow);
+            }
+
+            // Safety: the transfer is complete, so we own the buffer.
+            let buffer = unsafe { ep.buffer() };
+            buf[..len].copy_from_slice(&buffer[..len]);
+            state.ep_out_ready &= !mask;
+            state.prime_out(idx);
+            Ok(len)
+        })
+    }
+
+    fn set_stalled(&self, ep_addr: EndpointAddress, stalled: bool) {
+        let number = ep_addr.index();
+        if number >= MAX_ENDPOINTS {
+            return;
+        }
+        let (stall, toggle_reset) = if ep_addr.is_in() {
+            (reg::ENDPTCTRL_TXS, reg::ENDPTCTRL_TXR)
+        } else {
+            (reg::ENDPTCTRL_RXS, reg::ENDPTCTRL_RXR)
+        };
+        // Endpoint 0 doesn't have a data toggle reset.
+        let toggle_reset = if number == 0 { 0 } else { toggle_reset };
+        // Safety: critical section prevents a concurrent modification.
+        interrupt::free(|_| unsafe {
+            reg::modify(reg::endptctrl(number), |ctrl| {
+                if stalled {
+                    ctrl | stall
+                } else {
+                    (ctrl & !stall) | toggle_reset
+                }
+            })
+        });
+    }
+
+    fn is_stalled(&self, ep_addr: EndpointAddress) -> bool {
+        let number = ep_addr.index();
+        if number >= MAX_ENDPOINTS {
+            return false;
+        }
+        let stall = if ep_addr.is_in() {
+            reg::ENDPTCTRL_TXS
+        } else {
+            reg::ENDPTCTRL_RXS
+        };
+        // Safety: atomic read of a register.
+        unsafe { reg::read(reg::endptctrl(number)) & stall != 0 }
+    }
+
+    fn suspend(&self) {}
+
+    fn resume(&self) {}
+
+    fn poll(&self) -> PollResult {
+        self.with(|state| {
+            // Safety: register access while we own the bus.
+            let status = unsafe { reg::read(reg::USBSTS) };
+            unsafe { reg::write(reg::USBSTS, status) };
+
+            if status & reg::USBSTS_SRI != 0 {
+                state.sof = true;
+            }
+            if status & reg::USBSTS_SLI != 0 {
+                state.suspend_pending = true;
+            }
+            if status & reg::USBSTS_PCI != 0 {
+                update_speed();
+                let portsc = unsafe { reg::read(reg::PORTSC1) };
+                if state.suspended && portsc & reg::PORTSC1_SUSP == 0 {
+                    state.suspended = false;
+                    state.resume_pending = true;
+                }
+            }
+            if status & reg::USBSTS_URI != 0 {
+                return PollResult::Reset;
+            }
+            if core::mem::replace(&mut state.resume_pending, false) {
+                return PollResult::Resume;
+            }
+
+            // Only endpoint 0 receives setup packets.
+            let ep_setup = unsafe { reg::read(reg::ENDPTSETUPSTAT) } as u16 & 1;
+            let complete = unsafe { reg::read(reg::ENDPTCOMPLETE) };
+            unsafe { reg::write(reg::ENDPTCOMPLETE, complete) };
+            state.ep_out_ready |= complete as u16 & 0xFF;
+            let ep_in_complete = (complete >> 16) as u16 & 0xFF;
+
+            if ep_setup | state.ep_out_ready | ep_in_complete != 0 {
+                PollResult::Data {
+                    ep_out: state.ep_out_ready,
+                    ep_in_complete,
+                    ep_setup,
+                }
+            } else if core::mem::replace(&mut state.suspend_pending, false) {
+                state.suspended = true;
+                PollResult::Suspend
+            } else {
+                PollResult::None
+            }
+        })
+    }
+
+    const QUIRK_SET_ADDRESS_BEFORE_STATUS: bool = true;
+}
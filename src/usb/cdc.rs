@@ -0,0 +1,433 @@
+//! A CDC-ACM (USB serial) class
+//!
+//! The class buffers received and transmitted data, so that users can read
+//! and write while the USB driver runs in an interrupt. The class's bulk
+//! endpoints have a 512 byte max packet size at high speed, and a 64 byte
+//! max packet size at full speed.
+
+use super::bus::{self, BulkIn, BulkOut, Bus};
+use super::ring::Ring;
+use usb_device::{
+    bus::{InterfaceNumber, UsbBusAllocator},
+    class::{ControlIn, ControlOut, UsbClass},
+    control::{Recipient, RequestType},
+    descriptor::DescriptorWriter,
+    endpoint::{EndpointAddress, EndpointIn},
+};
+
+const USB_CLASS_CDC: u8 = 0x02;
+const USB_CLASS_CDC_DATA: u8 = 0x0A;
+const CDC_SUBCLASS_ACM: u8 = 0x02;
+const CDC_PROTOCOL_AT: u8 = 0x01;
+
+const CS_INTERFACE: u8 = 0x24;
+const CDC_TYPE_HEADER: u8 = 0x00;
+const CDC_TYPE_CALL_MANAGEMENT: u8 = 0x01;
+const CDC_TYPE_ACM: u8 = 0x02;
+const CDC_TYPE_UNION: u8 = 0x06;
+
+const SET_LINE_CODING: u8 = 0x20;
+const GET_LINE_CODING: u8 = 0x21;
+const SET_CONTROL_LINE_STATE: u8 = 0x22;
+const SEND_BREAK: u8 = 0x23;
+
+/// Max packet size for the notification endpoint
+const ACM_PACKET_SIZE: u16 = 16;
+
+/// Number of bytes buffered from the host
+const RX_LEN: usize = 1024;
+/// Number of bytes buffered for the host
+pub const TX_LEN: usize = 8 * 1024;
+
+/// The baud rate that requests a reboot into the bootloader
+const BOOTLOADER_BAUD: u32 = 134;
+
+/// Set when the class received data from the host
+pub const RX_COMPLETE: u32 = 1;
+/// Set when the class finished sending data to the host
+pub const TX_COMPLETE: u32 = 2;
+/// Set when the host changed DTR or RTS
+pub const CONTROL_LINES_CHANGED: u32 = 4;
+/// Set when the host changed the line coding
+pub const LINE_CODING_CHANGED: u32 = 8;
+
+/// 115200 baud, 1 stop bit, no parity, 8 data bits
+const DEFAULT_LINE_CODING: [u8; 7] = [0x00, 0xC2, 0x01, 0x00, 0, 0, 8];
+
+const CONTROL_LINE_DTR: u8 = 1 << 0;
+const CONTROL_LINE_RTS: u8 = 1 << 1;
+
+/// The number of stop bits requested by the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopBits {
+    /// 1 stop bit
+    One,
+    /// 1.5 stop bits
+    OnePointFive,
+    /// 2 stop bits
+    Two,
+}
+
+/// The parity requested by the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+    Mark,
+    Space,
+}
+
+/// The serial port settings requested by the host
+///
+/// A USB serial port doesn't need these settings to transfer data. But a host
+/// uses them to describe the serial port that it thinks it's talking to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCoding {
+    /// The baud rate, in bits per second
+    pub baud: u32,
+    pub stop_bits: StopBits,
+    pub parity: Parity,
+    /// The number of data bits; one of 5, 6, 7, 8, or 16
+    pub data_bits: u8,
+}
+
+impl LineCoding {
+    /// Decode the line coding from a SET_LINE_CODING request
+    ///
+    /// Unknown stop bits and parity values decode as one stop bit,
+    /// and no parity.
+    fn from_bytes(bytes: &[u8; 7]) -> Self {
+        LineCoding {
+            baud: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            stop_bits: match bytes[4] {
+                1 => StopBits::OnePointFive,
+                2 => StopBits::Two,
+                _ => StopBits::One,
+            },
+            parity: match bytes[5] {
+                1 => Parity::Odd,
+                2 => Parity::Even,
+                3 => Parity::Mark,
+                4 => Parity::Space,
+                _ => Parity::None,
+            },
+            data_bits: bytes[6],
+        }
+    }
+}
+
+impl Default for LineCoding {
+    /// 115200 baud, 8 data bits, no parity, and 1 stop bit
+    fn default() -> Self {
+        LineCoding::from_bytes(&DEFAULT_LINE_CODING)
+    }
+}
+
+/// A USB serial port
+pub struct Cdc<'a> {
+    /// Set if the port is one of many functions, and needs an
+    /// interface association descriptor
+    iad: bool,
+    comm_if: InterfaceNumber,
+    comm_ep: EndpointIn<'a, Bus>,
+    data_if: InterfaceNumber,
+    read_ep: BulkOut<'a>,
+    write_ep: BulkIn<'a>,
+    line_coding: [u8; 7],
+    control_lines: u8,
+    rx: Ring<RX_LEN>,
+    tx: Ring<TX_LEN>,
+    /// Set while there's a transfer on the write endpoint
+    tx_busy: bool,
+    /// Length of the most recent transfer on the write endpoint
+    tx_last: usize,
+    events: u32,
+    bootloader_request: bool,
+}
+
+impl<'a> Cdc<'a> {
+    pub fn new(alloc: &'a UsbBusAllocator<Bus>, iad: bool) -> Self {
+        Cdc {
+            iad,
+            comm_if: alloc.interface(),
+            comm_ep: alloc.interrupt(ACM_PACKET_SIZE, 8),
+            data_if: alloc.interface(),
+            read_ep: BulkOut::new(alloc),
+            write_ep: BulkIn::new(alloc),
+            line_coding: DEFAULT_LINE_CODING,
+            control_lines: 0,
+            rx: Ring::new(),
+            tx: Ring::new(),
+            tx_busy: false,
+            tx_last: 0,
+            events: 0,
+            bootloader_request: false,
+        }
+    }
+
+    /// Returns, then clears, the event flags
+    pub fn take_events(&mut self) -> u32 {
+        core::mem::replace(&mut self.events, 0)
+    }
+
+    /// Returns `true` if the host asked us to reboot into the bootloader
+    pub fn take_bootloader_request(&mut self) -> bool {
+        core::mem::replace(&mut self.bootloader_request, false)
+    }
+
+    /// Returns the state of the data terminal ready (DTR) control line
+    ///
+    /// Most terminals set DTR when they open the serial port.
+    pub fn dtr(&self) -> bool {
+        self.control_lines & CONTROL_LINE_DTR != 0
+    }
+
+    /// Returns the state of the request to send (RTS) control line
+    pub fn rts(&self) -> bool {
+        self.control_lines & CONTROL_LINE_RTS != 0
+    }
+
+    /// Returns the line coding requested by the host
+    pub fn line_coding(&self) -> LineCoding {
+        LineCoding::from_bytes(&self.line_coding)
+    }
+
+    /// Read buffered data from the host
+    pub fn read(&mut self, buffer: &mut [u8]) -> usize {
+        let count = self.rx.pop(buffer);
+        self.pull_rx();
+        count
+    }
+
+    /// Returns the number of bytes that `read` can return
+    pub fn read_available(&self) -> usize {
+        self.rx.len()
+    }
+
+    /// Buffer data for the host
+    ///
+    /// Returns the number of bytes buffered. This may be less than `buffer`
+    /// if the buffer is full.
+    pub fn write(&mut self, buffer: &[u8]) -> usize {
+        let count = self.tx.push(buffer);
+        if !self.tx_busy {
+            self.push_tx();
+        }
+        count
+    }
+
+    /// Returns the number of bytes that `write` can buffer
+    pub fn write_available(&self) -> usize {
+        self.tx.free()
+    }
+
+    /// Send any buffered data
+    pub fn flush(&mut self) {
+        if !self.tx_busy {
+            self.push_tx();
+        }
+    }
+
+    /// Returns `true` if there's data that the host hasn't received
+    pub fn write_pending(&self) -> bool {
+        self.tx_busy || !self.tx.is_empty()
+    }
+
+    /// Move data from the read endpoint into the receive buffer
+    ///
+    /// If there isn't room for a full packet, the data stays in the endpoint.
+    /// The next `read` pulls the data.
+    fn pull_rx(&mut self) {
+        if self.rx.free() < bus::bulk_packet_len() {
+            return;
+        }
+        let mut packet = [0; bus::MAX_BULK_PACKET_LEN];
+        if let Ok(count) = self.read_ep.read(&mut packet) {
+            self.rx.push(&packet[..count]);
+            self.events |= RX_COMPLETE;
+        }
+    }
+
+    /// Schedule a transfer from the transmit buffer
+    fn push_tx(&mut self) {
+        let packet_len = bus::bulk_packet_len();
+        let mut packet = [0; bus::MAX_BULK_PACKET_LEN];
+        let count = self.tx.pop(&mut packet);
+        // Terminate a transfer that ended on a packet boundary with a
+        // zero length packet, so the host knows that the transfer is done.
+        if count == 0 && (self.tx_last == 0 || self.tx_last % packet_len != 0) {
+            self.tx_last = 0;
+            return;
+        }
+        if self.write_ep.write(&packet[..count]).is_ok() {
+            self.tx_busy = true;
+            self.tx_last = count;
+        }
+    }
+}
+
+impl UsbClass<Bus> for Cdc<'_> {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        if self.iad {
+            writer.iad(
+                self.comm_if,
+                2,
+                USB_CLASS_CDC,
+                CDC_SUBCLASS_ACM,
+                CDC_PROTOCOL_AT,
+                None,
+            )?;
+        }
+        writer.interface(
+            self.comm_if,
+            USB_CLASS_CDC,
+            CDC_SUBCLASS_ACM,
+            CDC_PROTOCOL_AT,
+        )?;
+        writer.write(CS_INTERFACE, &[CDC_TYPE_HEADER, 0x10, 0x01])?;
+        writer.write(
+            CS_INTERFACE,
+            &[CDC_TYPE_CALL_MANAGEMENT, 0x01, self.data_if.into()],
+        )?;
+        writer.write(CS_INTERFACE, &[CDC_TYPE_ACM, 0x06])?;
+        writer.write(
+            CS_INTERFACE,
+            &[CDC_TYPE_UNION, self.comm_if.into(), self.data_if.into()],
+        )?;
+        writer.endpoint(&self.comm_ep)?;
+
+        writer.interface(self.data_if, USB_CLASS_CDC_DATA, 0x00, 0x00)?;
+        self.write_ep.describe(writer)?;
+        self.read_ep.describe(writer)?;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        // A reset disconnects the terminal.
+        if self.control_lines != 0 {
+            self.events |= CONTROL_LINES_CHANGED;
+        }
+        if self.line_coding != DEFAULT_LINE_CODING {
+            self.events |= LINE_CODING_CHANGED;
+        }
+        self.control_lines = 0;
+        self.line_coding = DEFAULT_LINE_CODING;
+        self.rx.clear();
+        self.tx.clear();
+        self.tx_busy = false;
+        self.tx_last = 0;
+        self.bootloader_request = false;
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<Bus>) {
+        let req = xfer.request();
+        if req.request_type != RequestType::Class
+            || req.recipient != Recipient::Interface
+            || req.index != u8::from(self.comm_if) as u16
+        {
+            return;
+        }
+
+        match req.request {
+            SET_LINE_CODING if xfer.data().len() >= 7 => {
+                let mut line_coding = [0; 7];
+                line_coding.copy_from_slice(&xfer.data()[..7]);
+                if line_coding != self.line_coding {
+                    self.line_coding = line_coding;
+                    self.events |= LINE_CODING_CHANGED;
+                }
+                if self.line_coding().baud == BOOTLOADER_BAUD {
+                    self.bootloader_request = true;
+                }
+                xfer.accept().ok();
+            }
+            SET_CONTROL_LINE_STATE => {
+                let control_lines = req.value as u8 & (CONTROL_LINE_DTR | CONTROL_LINE_RTS);
+                if control_lines != self.control_lines {
+                    self.control_lines = control_lines;
+                    self.events |= CONTROL_LINES_CHANGED;
+                }
+                xfer.accept().ok();
+            }
+            SEND_BREAK => {
+                xfer.accept().ok();
+            }
+            _ => {
+                xfer.reject().ok();
+            }
+        }
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<Bus>) {
+        let req = xfer.request();
+        if req.request_type != RequestType::Class
+            || req.recipient != Recipient::Interface
+            || req.index != u8::from(self.comm_if) as u16
+        {
+            return;
+        }
+
+        match req.request {
+            GET_LINE_CODING => {
+                let line_coding = self.line_coding;
+                xfer.accept_with(&line_coding).ok();
+            }
+            _ => {
+                xfer.reject().ok();
+            }
+        }
+    }
+
+    fn endpoint_out(&mut self, addr: EndpointAddress) {
+        if addr == self.read_ep.address() {
+            self.pull_rx();
+        }
+    }
+
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        if addr == self.write_ep.address() {
+            self.tx_busy = false;
+            self.events |= TX_COMPLETE;
+            self.push_tx();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineCoding, Parity, StopBits};
+
+    #[test]
+    fn default_line_coding() {
+        let line_coding = LineCoding::default();
+        assert_eq!(line_coding.baud, 115_200);
+        assert_eq!(line_coding.stop_bits, StopBits::One);
+        assert_eq!(line_coding.parity, Parity::None);
+        assert_eq!(line_coding.data_bits, 8);
+    }
+
+    #[test]
+    fn decode_line_coding() {
+        let line_coding = LineCoding::from_bytes(&[0x86, 0x00, 0x00, 0x00, 2, 2, 7]);
+        assert_eq!(line_coding.baud, 134);
+        assert_eq!(line_coding.stop_bits, StopBits::Two);
+        assert_eq!(line_coding.parity, Parity::Even);
+        assert_eq!(line_coding.data_bits, 7);
+
+        let line_coding = LineCoding::from_bytes(&[0x80, 0x25, 0x00, 0x00, 1, 3, 8]);
+        assert_eq!(line_coding.baud, 9600);
+        assert_eq!(line_coding.stop_bits, StopBits::OnePointFive);
+        assert_eq!(line_coding.parity, Parity::Mark);
+    }
+
+    #[test]
+    fn decode_unknown_line_coding() {
+        let line_coding = LineCoding::from_bytes(&[0x00, 0xC2, 0x01, 0x00, 7, 9, 8]);
+        assert_eq!(line_coding.stop_bits, StopBits::One);
+        assert_eq!(line_coding.parity, Parity::None);
+    }
+}
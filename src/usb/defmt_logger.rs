@@ -0,0 +1,80 @@
+//! A `defmt` global logger that writes to the USB serial port
+//!
+//! Frames go to the logging port, the same port used by the `log`
+//! implementation. The host can't decode text that's mixed in with
+//! frames, so don't use both loggers on the same port. Use the `tools`
+//! package's `defmt-monitor` to decode the frames on the host.
+//!
+//! Each frame is written in a critical section, so frames never mix
+//! with each other. Frames written while the host isn't ready, or while
+//! the serial port is full, are lost. The decoder resynchronizes at the
+//! next frame.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::{with_port, Port};
+
+#[defmt::global_logger]
+struct Logger;
+
+/// Set while a frame is being written
+static TAKEN: AtomicBool = AtomicBool::new(false);
+/// Set if interrupts were enabled before `acquire`
+static mut RESTORE: bool = false;
+/// Set if some of the current frame didn't fit in the serial port
+static mut DROPPING: bool = false;
+static mut ENCODER: defmt::Encoder = defmt::Encoder::new();
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        let primask = cortex_m::register::primask::read();
+        cortex_m::interrupt::disable();
+        if TAKEN.load(Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly");
+        }
+        TAKEN.store(true, Ordering::Relaxed);
+        // Safety: interrupts are disabled, and we own the logger.
+        unsafe {
+            RESTORE = primask.is_active();
+            DROPPING = false;
+            (*core::ptr::addr_of_mut!(ENCODER)).start_frame(write_serial);
+        }
+    }
+
+    unsafe fn flush() {
+        with_port(Port::Primary, |serial| serial.flush()).ok();
+    }
+
+    unsafe fn release() {
+        (*core::ptr::addr_of_mut!(ENCODER)).end_frame(write_serial);
+        // Always try to send the frame's terminator, so the decoder
+        // can resynchronize after a dropped frame.
+        if DROPPING {
+            DROPPING = false;
+            write_serial(&[0]);
+        }
+        TAKEN.store(false, Ordering::Relaxed);
+        if RESTORE {
+            cortex_m::interrupt::enable();
+        }
+        cortex_m::peripheral::NVIC::pend(crate::interrupt::USB_OTG1);
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        (*core::ptr::addr_of_mut!(ENCODER)).write(bytes, write_serial);
+    }
+}
+
+/// Write encoded bytes to the logging port
+///
+/// Once some of a frame doesn't fit, drops the rest of the frame.
+fn write_serial(bytes: &[u8]) {
+    // Safety: only called while we own the logger, with interrupts disabled.
+    unsafe {
+        if DROPPING {
+            return;
+        }
+        let written = with_port(Port::Primary, |serial| serial.write(bytes)).unwrap_or(0);
+        DROPPING = written < bytes.len();
+    }
+}
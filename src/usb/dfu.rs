@@ -0,0 +1,711 @@
+//! USB Device Firmware Upgrade (DFU)
+//!
+//! DFU lets standard tools, like `dfu-util`, update your firmware over USB.
+//! An update has two steps:
+//!
+//! 1. Your application uses [`split_with_dfu`](super::split_with_dfu) to
+//!    present a DFU runtime interface next to its serial port. When the host
+//!    asks the device to detach, the USB stack reboots the Teensy, and
+//!    remembers the request.
+//! 2. After the reboot, [`take_detach_request`] returns `true`. Your application
+//!    uses [`split_dfu`](super::split_dfu) to enter DFU mode. The host downloads
+//!    the firmware into your [`Staging`] region. Once the download is complete,
+//!    the device [checks](Staging::check) the image, then [commits](Staging::commit)
+//!    it. When the host resets the device, the USB stack reboots the Teensy.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::hal::ral::usb::USB1;
+//! use bsp::usb::dfu;
+//!
+//! # struct Region;
+//! # impl dfu::Staging for Region {
+//! #     fn capacity(&self) -> usize { 0 }
+//! #     fn write(&mut self, _: usize, _: &[u8]) -> Result<(), dfu::DfuError> { Ok(()) }
+//! #     fn read(&mut self, _: usize, _: &mut [u8]) -> Result<(), dfu::DfuError> { Ok(()) }
+//! #     fn commit(&mut self, _: usize) -> Result<(), dfu::DfuError> { Ok(()) }
+//! # }
+//! # fn staging_region() -> &'static mut Region { unimplemented!() }
+//! let usb = USB1::take().unwrap();
+//! if dfu::take_detach_request() {
+//!     let staging: &'static mut Region = staging_region();
+//!     let mut poller = bsp::usb::split_dfu(usb, Default::default(), staging).unwrap();
+//!     loop {
+//!         poller.poll();
+//!     }
+//! }
+//!
+//! let (mut poller, reader, writer) = bsp::usb::split_with_dfu(usb, Default::default()).unwrap();
+//! // Run your application...
+//! ```
+//!
+//! Downloads arrive in order, in pieces of up to [`TRANSFER_SIZE`] bytes. `poll`
+//! calls the staging region while it handles the host's requests, so erasing
+//! and programming flash happens in `poll`.
+//!
+//! In DFU mode, there's no serial port; see [device classes](super#device-classes).
+
+use core::mem::MaybeUninit;
+
+use super::bus::Bus;
+use usb_device::{
+    bus::{InterfaceNumber, UsbBusAllocator},
+    class::{ControlIn, ControlOut, UsbClass},
+    control::{Recipient, Request, RequestType},
+    descriptor::DescriptorWriter,
+};
+
+const USB_CLASS_APPLICATION: u8 = 0xFE;
+const DFU_SUBCLASS: u8 = 0x01;
+const DFU_PROTOCOL_RUNTIME: u8 = 0x01;
+const DFU_PROTOCOL_DFU_MODE: u8 = 0x02;
+
+const DFU_FUNCTIONAL: u8 = 0x21;
+
+const DFU_DETACH: u8 = 0x00;
+const DFU_DNLOAD: u8 = 0x01;
+const DFU_UPLOAD: u8 = 0x02;
+const DFU_GETSTATUS: u8 = 0x03;
+const DFU_CLRSTATUS: u8 = 0x04;
+const DFU_GETSTATE: u8 = 0x05;
+const DFU_ABORT: u8 = 0x06;
+
+/// The device can download, can upload, and detaches itself. It's not
+/// manifestation tolerant; the host resets the device after manifestation.
+const ATTRIBUTES: u8 = 0x01 | 0x02 | 0x08;
+/// The longest time between a detach request, and the reboot
+const DETACH_TIMEOUT_MS: u16 = 1000;
+/// DFU 1.1
+const DFU_VERSION: u16 = 0x0110;
+
+/// The largest block of a download or upload, in bytes
+///
+/// This is limited by the USB stack's control buffer.
+pub const TRANSFER_SIZE: usize = 128;
+
+/// A DFU error status, reported to the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DfuError {
+    /// The file isn't for this device
+    Target = 0x01,
+    /// The file fails a vendor-specific check
+    File = 0x02,
+    /// The device can't write memory
+    Write = 0x03,
+    /// Memory erase failed
+    Erase = 0x04,
+    /// Memory erase check failed
+    CheckErased = 0x05,
+    /// Program memory failed
+    Prog = 0x06,
+    /// Programmed memory failed verification
+    Verify = 0x07,
+    /// The address is out of range
+    Address = 0x08,
+    /// The download ended before the device expected
+    NotDone = 0x09,
+    /// The firmware is corrupt
+    Firmware = 0x0A,
+    /// Something else went wrong
+    Unknown = 0x0E,
+}
+
+/// The status for a stalled request
+const STATUS_STALLED_PACKET: u8 = 0x0F;
+const STATUS_OK: u8 = 0x00;
+
+/// Where the device stores a downloaded image
+///
+/// The staging region is usually a region of flash, separate from the running
+/// program. Your implementation erases memory as needed, and decides how to
+/// install a checked image.
+pub trait Staging {
+    /// Returns the size of the staging region, in bytes
+    fn capacity(&self) -> usize;
+
+    /// Write `data` at `offset`
+    ///
+    /// Downloads start at offset zero, and continue in order.
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), DfuError>;
+
+    /// Read into `data` from `offset`
+    fn read(&mut self, offset: usize, data: &mut [u8]) -> Result<(), DfuError>;
+
+    /// Check the downloaded image of `len` bytes
+    ///
+    /// The default implementation uses [`check_teensy_image`].
+    fn check(&mut self, len: usize) -> Result<(), DfuError> {
+        check_teensy_image(self, len)
+    }
+
+    /// Install the checked image of `len` bytes
+    ///
+    /// For example, mark the image as ready for your bootloader. The USB stack
+    /// reboots the Teensy once the host resets the device.
+    fn commit(&mut self, len: usize) -> Result<(), DfuError>;
+}
+
+/// The FlexSPI configuration block tag, "FCFB"
+const FCB_TAG: u32 = 0x4246_4346;
+/// The image vector table's offset from the start of the image
+const IVT_OFFSET: usize = 0x1000;
+/// The image vector table, then the boot data
+const IVT_LEN: usize = 32 + 12;
+
+/// Check that a staged image looks like a Teensy 4 program
+///
+/// The image needs a FlexSPI configuration block, and an image vector table.
+/// The boot data's image length can't exceed `len`, which catches
+/// truncated images.
+pub fn check_teensy_image<S: Staging + ?Sized>(
+    staging: &mut S,
+    len: usize,
+) -> Result<(), DfuError> {
+    if len < IVT_OFFSET + IVT_LEN {
+        return Err(DfuError::Firmware);
+    }
+    let mut tag = [0; 4];
+    staging.read(0, &mut tag)?;
+    if u32::from_le_bytes(tag) != FCB_TAG {
+        return Err(DfuError::Firmware);
+    }
+    let mut ivt = [0; IVT_LEN];
+    staging.read(IVT_OFFSET, &mut ivt)?;
+    if ivt[..3] != [0xD1, 0x00, 0x20] || !(0x40..=0x45).contains(&ivt[3]) {
+        return Err(DfuError::Firmware);
+    }
+    let image_len = u32::from_le_bytes([ivt[36], ivt[37], ivt[38], ivt[39]]);
+    if image_len as usize > len {
+        return Err(DfuError::Firmware);
+    }
+    Ok(())
+}
+
+/// Marks a detach request in retained memory. Spells "DFU!"
+const DETACH_MAGIC: u32 = 0x4446_5521;
+
+/// Placed in the `.retained` section, which survives a software reset.
+/// See `t4link.x`.
+#[cfg_attr(target_arch = "arm", link_section = ".retained")]
+static mut DETACH_REQUEST: MaybeUninit<[u32; 2]> = MaybeUninit::uninit();
+
+/// Remember the detach request across the reboot
+pub(super) fn set_detach_request() {
+    // Safety: only called from poll, in a critical section.
+    unsafe {
+        let request = core::ptr::addr_of_mut!(DETACH_REQUEST).cast::<[u32; 2]>();
+        core::ptr::write_volatile(request, [DETACH_MAGIC, !DETACH_MAGIC]);
+        // The request must reach memory before the reset.
+        cortex_m::Peripherals::steal()
+            .SCB
+            .clean_dcache_by_address(request as usize, core::mem::size_of::<[u32; 2]>());
+    }
+}
+
+/// Returns `true` if the host asked the DFU runtime interface to detach
+/// before the last reset
+///
+/// The request is cleared, so this only returns `true` once. Use this
+/// early in your program to decide if you should enter DFU mode.
+pub fn take_detach_request() -> bool {
+    cortex_m::interrupt::free(|_| unsafe {
+        let request = core::ptr::addr_of_mut!(DETACH_REQUEST).cast::<[u32; 2]>();
+        let candidate = core::ptr::read_volatile(request);
+        core::ptr::write_volatile(request, [0; 2]);
+        candidate == [DETACH_MAGIC, !DETACH_MAGIC]
+    })
+}
+
+fn functional_descriptor() -> [u8; 7] {
+    let timeout = DETACH_TIMEOUT_MS.to_le_bytes();
+    let size = (TRANSFER_SIZE as u16).to_le_bytes();
+    let version = DFU_VERSION.to_le_bytes();
+    [
+        ATTRIBUTES, timeout[0], timeout[1], size[0], size[1], version[0], version[1],
+    ]
+}
+
+/// Returns `true` if `req` is a DFU request for `interface`
+fn is_recipient(interface: InterfaceNumber, req: &Request) -> bool {
+    req.request_type == RequestType::Class
+        && req.recipient == Recipient::Interface
+        && req.index == u8::from(interface) as u16
+}
+
+/// The DFU states
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum State {
+    AppIdle = 0,
+    DfuIdle = 2,
+    DownloadSync = 3,
+    DownloadIdle = 5,
+    ManifestSync = 6,
+    Manifest = 7,
+    ManifestWaitReset = 8,
+    UploadIdle = 9,
+    Error = 10,
+}
+
+/// The DFU runtime interface, next to the application's interfaces
+pub(super) struct Runtime {
+    interface: InterfaceNumber,
+    /// Set when the host asks the device to detach
+    detach: bool,
+}
+
+impl Runtime {
+    pub fn new(alloc: &UsbBusAllocator<Bus>) -> Self {
+        Runtime {
+            interface: alloc.interface(),
+            detach: false,
+        }
+    }
+
+    /// Returns `true` if the host asked the device to detach
+    pub fn take_detach(&mut self) -> bool {
+        core::mem::replace(&mut self.detach, false)
+    }
+}
+
+impl UsbClass<Bus> for Runtime {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        writer.interface(
+            self.interface,
+            USB_CLASS_APPLICATION,
+            DFU_SUBCLASS,
+            DFU_PROTOCOL_RUNTIME,
+        )?;
+        writer.write(DFU_FUNCTIONAL, &functional_descriptor())
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<Bus>) {
+        let req = *xfer.request();
+        if !is_recipient(self.interface, &req) {
+            return;
+        }
+        match req.request {
+            DFU_GETSTATUS => xfer
+                .accept_with(&[STATUS_OK, 0, 0, 0, State::AppIdle as u8, 0])
+                .ok(),
+            DFU_GETSTATE => xfer.accept_with(&[State::AppIdle as u8]).ok(),
+            _ => xfer.reject().ok(),
+        };
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<Bus>) {
+        let req = *xfer.request();
+        if !is_recipient(self.interface, &req) {
+            return;
+        }
+        match req.request {
+            DFU_DETACH => {
+                self.detach = true;
+                xfer.accept().ok()
+            }
+            _ => xfer.reject().ok(),
+        };
+    }
+}
+
+/// The DFU mode state machine
+///
+/// Independent of USB, so that we can test it on the host. Methods that
+/// return `false`, or `None`, stall the request.
+struct Machine<'s> {
+    staging: &'s mut dyn Staging,
+    state: State,
+    status: u8,
+    /// The download or upload position
+    offset: usize,
+    /// Set once the device is ready to reboot into the new image
+    reboot: bool,
+}
+
+impl<'s> Machine<'s> {
+    fn new(staging: &'s mut dyn Staging) -> Self {
+        Machine {
+            staging,
+            state: State::DfuIdle,
+            status: STATUS_OK,
+            offset: 0,
+            reboot: false,
+        }
+    }
+
+    fn fail(&mut self, err: DfuError) {
+        self.status = err as u8;
+        self.state = State::Error;
+    }
+
+    /// Stall the request, and enter the error state
+    fn stall(&mut self) {
+        self.status = STATUS_STALLED_PACKET;
+        self.state = State::Error;
+    }
+
+    /// Handle a download request, with the host's `block`
+    ///
+    /// An empty block ends the download.
+    fn download(&mut self, block: &[u8]) -> bool {
+        match self.state {
+            State::DfuIdle if !block.is_empty() => self.offset = 0,
+            State::DownloadIdle if block.is_empty() => {
+                self.state = State::ManifestSync;
+                return true;
+            }
+            State::DownloadIdle => {}
+            _ => {
+                self.stall();
+                return false;
+            }
+        }
+        if self.offset + block.len() > self.staging.capacity() {
+            self.fail(DfuError::Address);
+            return true;
+        }
+        match self.staging.write(self.offset, block) {
+            Ok(()) => {
+                self.offset += block.len();
+                self.state = State::DownloadSync;
+            }
+            Err(err) => self.fail(err),
+        }
+        true
+    }
+
+    /// Handle an upload request, returning the size of the block
+    ///
+    /// A short block ends the upload.
+    fn upload(&mut self, block: &mut [u8]) -> Option<usize> {
+        match self.state {
+            State::DfuIdle => {
+                self.offset = 0;
+                self.state = State::UploadIdle;
+            }
+            State::UploadIdle => {}
+            _ => {
+                self.stall();
+                return None;
+            }
+        }
+        let len = block
+            .len()
+            .min(self.staging.capacity().saturating_sub(self.offset));
+        if let Err(err) = self.staging.read(self.offset, &mut block[..len]) {
+            self.fail(err);
+            return None;
+        }
+        self.offset += len;
+        if len < block.len() {
+            self.state = State::DfuIdle;
+        }
+        Some(len)
+    }
+
+    /// Handle a get status request
+    ///
+    /// This advances the download, and runs manifestation.
+    fn get_status(&mut self) -> [u8; 6] {
+        let state = match self.state {
+            State::DownloadSync => {
+                self.state = State::DownloadIdle;
+                self.state
+            }
+            State::ManifestSync => {
+                let len = self.offset;
+                match self
+                    .staging
+                    .check(len)
+                    .and_then(|_| self.staging.commit(len))
+                {
+                    Ok(()) => {
+                        self.state = State::ManifestWaitReset;
+                        State::Manifest
+                    }
+                    Err(err) => {
+                        self.fail(err);
+                        self.state
+                    }
+                }
+            }
+            state => state,
+        };
+        // The device finishes its work during the request, so the host
+        // doesn't need to wait before the next request.
+        [self.status, 0, 0, 0, state as u8, 0]
+    }
+
+    fn get_state(&self) -> u8 {
+        self.state as u8
+    }
+
+    /// Handle a clear status request
+    fn clear_status(&mut self) -> bool {
+        if self.state == State::Error {
+            self.status = STATUS_OK;
+            self.state = State::DfuIdle;
+            true
+        } else {
+            self.stall();
+            false
+        }
+    }
+
+    /// Handle an abort request
+    fn abort(&mut self) -> bool {
+        match self.state {
+            State::DfuIdle
+            | State::DownloadSync
+            | State::DownloadIdle
+            | State::ManifestSync
+            | State::UploadIdle => {
+                self.state = State::DfuIdle;
+                true
+            }
+            _ => {
+                self.stall();
+                false
+            }
+        }
+    }
+
+    /// Handle a USB reset
+    fn usb_reset(&mut self) {
+        if self.state == State::ManifestWaitReset {
+            self.reboot = true;
+        } else {
+            self.state = State::DfuIdle;
+            self.status = STATUS_OK;
+        }
+    }
+}
+
+/// The DFU mode interface
+pub(super) struct Dfu<'a> {
+    interface: InterfaceNumber,
+    machine: Machine<'a>,
+}
+
+impl<'a> Dfu<'a> {
+    pub fn new(alloc: &UsbBusAllocator<Bus>, staging: &'a mut dyn Staging) -> Self {
+        Dfu {
+            interface: alloc.interface(),
+            machine: Machine::new(staging),
+        }
+    }
+
+    /// Returns `true` if the device should reboot into its new image
+    pub fn take_reboot(&mut self) -> bool {
+        core::mem::replace(&mut self.machine.reboot, false)
+    }
+}
+
+impl UsbClass<Bus> for Dfu<'_> {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        writer.interface(
+            self.interface,
+            USB_CLASS_APPLICATION,
+            DFU_SUBCLASS,
+            DFU_PROTOCOL_DFU_MODE,
+        )?;
+        writer.write(DFU_FUNCTIONAL, &functional_descriptor())
+    }
+
+    fn reset(&mut self) {
+        self.machine.usb_reset();
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<Bus>) {
+        let req = *xfer.request();
+        if !is_recipient(self.interface, &req) {
+            return;
+        }
+        match req.request {
+            DFU_UPLOAD => {
+                let mut block = [0; TRANSFER_SIZE];
+                let len = (req.length as usize).min(TRANSFER_SIZE);
+                match self.machine.upload(&mut block[..len]) {
+                    Some(len) => xfer.accept_with(&block[..len]).ok(),
+                    None => xfer.reject().ok(),
+                }
+            }
+            DFU_GETSTATUS => xfer.accept_with(&self.machine.get_status()).ok(),
+            DFU_GETSTATE => xfer.accept_with(&[self.machine.get_state()]).ok(),
+            _ => xfer.reject().ok(),
+        };
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<Bus>) {
+        let req = *xfer.request();
+        if !is_recipient(self.interface, &req) {
+            return;
+        }
+        let accepted = match req.request {
+            DFU_DNLOAD => self.machine.download(xfer.data()),
+            DFU_CLRSTATUS => self.machine.clear_status(),
+            DFU_ABORT => self.machine.abort(),
+            _ => false,
+        };
+        if accepted {
+            xfer.accept().ok();
+        } else {
+            xfer.reject().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DfuError, Machine, Staging, State, FCB_TAG, IVT_OFFSET, STATUS_OK};
+
+    /// A staging region in memory
+    struct Region {
+        memory: [u8; 8192],
+        committed: Option<usize>,
+    }
+
+    impl Region {
+        fn new() -> Self {
+            Region {
+                memory: [0xFF; 8192],
+                committed: None,
+            }
+        }
+    }
+
+    impl Staging for Region {
+        fn capacity(&self) -> usize {
+            self.memory.len()
+        }
+        fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), DfuError> {
+            self.memory[offset..offset + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+        fn read(&mut self, offset: usize, data: &mut [u8]) -> Result<(), DfuError> {
+            data.copy_from_slice(&self.memory[offset..offset + data.len()]);
+            Ok(())
+        }
+        fn commit(&mut self, len: usize) -> Result<(), DfuError> {
+            self.committed = Some(len);
+            Ok(())
+        }
+    }
+
+    /// Returns a minimal Teensy 4 image of `len` bytes
+    fn image(len: usize) -> [u8; 6000] {
+        let mut image = [0; 6000];
+        image[..4].copy_from_slice(&FCB_TAG.to_le_bytes());
+        image[IVT_OFFSET..IVT_OFFSET + 4].copy_from_slice(&[0xD1, 0x00, 0x20, 0x40]);
+        image[IVT_OFFSET + 36..IVT_OFFSET + 40].copy_from_slice(&(len as u32).to_le_bytes());
+        image
+    }
+
+    /// Download `image`, and return the status after the last block
+    fn download(machine: &mut Machine, image: &[u8]) -> [u8; 6] {
+        for block in image.chunks(128) {
+            assert!(machine.download(block));
+            let status = machine.get_status();
+            if status[0] != STATUS_OK {
+                return status;
+            }
+            assert_eq!(status[4], State::DownloadIdle as u8);
+        }
+        assert!(machine.download(&[]));
+        assert_eq!(machine.get_state(), State::ManifestSync as u8);
+        machine.get_status()
+    }
+
+    #[test]
+    fn download_and_manifest() {
+        let image = image(6000);
+        let mut region = Region::new();
+        {
+            let mut machine = Machine::new(&mut region);
+            let status = download(&mut machine, &image);
+            assert_eq!(status, [STATUS_OK, 0, 0, 0, State::Manifest as u8, 0]);
+            assert_eq!(machine.get_state(), State::ManifestWaitReset as u8);
+            assert!(!machine.reboot);
+            machine.usb_reset();
+            assert!(machine.reboot);
+        }
+        assert_eq!(region.committed, Some(6000));
+        assert_eq!(&region.memory[..6000], &image[..]);
+    }
+
+    #[test]
+    fn reject_bad_images() {
+        let mut region = Region::new();
+        let mut machine = Machine::new(&mut region);
+
+        // Not a Teensy image
+        let status = download(&mut machine, &[0x42; 5000]);
+        assert_eq!(status[0], DfuError::Firmware as u8);
+        assert_eq!(status[4], State::Error as u8);
+        assert!(machine.clear_status());
+        assert_eq!(machine.get_state(), State::DfuIdle as u8);
+
+        // Truncated
+        let image = image(6000);
+        let status = download(&mut machine, &image[..5000]);
+        assert_eq!(status[0], DfuError::Firmware as u8);
+        assert!(machine.clear_status());
+
+        // Too large for the staging region
+        let status = download(&mut machine, &[0; 8192 + 128]);
+        assert_eq!(status[0], DfuError::Address as u8);
+        assert!(machine.clear_status());
+
+        assert!(!machine.reboot);
+        assert_eq!(region.committed, None);
+    }
+
+    #[test]
+    fn invalid_requests_stall() {
+        let mut region = Region::new();
+        let mut machine = Machine::new(&mut region);
+
+        // A download can't start with an empty block.
+        assert!(!machine.download(&[]));
+        assert_eq!(machine.get_status()[..1], [0x0F]);
+        assert!(machine.clear_status());
+        // There's nothing to clear.
+        assert!(!machine.clear_status());
+        assert!(machine.clear_status());
+
+        // Abort a download, then upload.
+        assert!(machine.download(&[1, 2, 3]));
+        machine.get_status();
+        assert!(machine.abort());
+        let mut block = [0; 128];
+        assert_eq!(machine.upload(&mut block), Some(128));
+        assert_eq!(&block[..4], &[1, 2, 3, 0xFF]);
+        // Uploads can't be interrupted by downloads.
+        assert!(!machine.download(&[1]));
+        assert_eq!(machine.get_state(), State::Error as u8);
+    }
+
+    #[test]
+    fn upload_ends_with_short_block() {
+        let mut region = Region::new();
+        let mut machine = Machine::new(&mut region);
+        let mut block = [0; 100];
+        let mut total = 0;
+        loop {
+            let len = machine.upload(&mut block).unwrap();
+            total += len;
+            if len < block.len() {
+                break;
+            }
+        }
+        assert_eq!(total, 8192);
+        assert_eq!(machine.get_state(), State::DfuIdle as u8);
+    }
+}
@@ -1,10 +1,21 @@
 //! Logging filters
 
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{self, AtomicUsize, Ordering},
+};
+
 /// Filter log messages by module name (`&'static str`) to a log level
 ///
 /// - if the level is `None`, log at all levels from the module (subject to the max log level)
 /// - if the level is not `None`, that will be the base log level for the module
 ///
+/// A filter matches its module, and the module's submodules; a filter for `"motor"`
+/// matches the targets `"motor"` and `"motor::pid"`, but not `"motorcycle"`. A `*`
+/// matches any characters, so `"*::motor"` matches `"my_crate::motor"`. When
+/// multiple filters match a target, the most specific filter wins. The most
+/// specific filter has the most characters that aren't `*`.
+///
 /// # Example
 ///
 /// ```
@@ -17,24 +28,256 @@
 ///     ("i2c", None),
 ///     // Writes only Error- and Warn-level messages from the 'spi' module
 ///     ("spi", Some(LevelFilter::Warn)),
+///     // Writes Info-level messages from any 'motor' module...
+///     ("*::motor", Some(LevelFilter::Info)),
+///     // ...except for the 'my_crate::motor::pid' module, which is more specific.
+///     ("my_crate::motor::pid", Some(LevelFilter::Off)),
 /// ];
 /// ```
 pub type Filter = (&'static str, Option<::log::LevelFilter>);
 
+/// The maximum number of filters
+pub const MAX_FILTERS: usize = 16;
+
+/// The maximum length of a filter's target, in bytes
+pub const MAX_TARGET_LEN: usize = 32;
+
+/// An error when changing the log filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterError {
+    /// There's already [`MAX_FILTERS`] filters
+    Full,
+    /// The target is longer than [`MAX_TARGET_LEN`]
+    TargetTooLong,
+    /// The text command isn't understood
+    InvalidCommand,
+}
+
+/// Encodes an `Option<LevelFilter>` as a byte
+///
+/// Every byte is a valid level, which simplifies reading a level
+/// that might be changing.
+const ALL_LEVELS: u8 = u8::MAX;
+
+fn encode_level(level: Option<::log::LevelFilter>) -> u8 {
+    level.map(|level| level as u8).unwrap_or(ALL_LEVELS)
+}
+
+/// Returns `true` if `pattern` matches `target`, or one of its parent modules
+///
+/// When the pattern doesn't match, only the last `*` matches one more byte;
+/// earlier `*`s never need to. This bounds the time to the product of the
+/// lengths, since the logger calls this for every record.
+fn matches(pattern: &[u8], target: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // The last `*` in the pattern, and where its match ends in the target
+    let mut star = None;
+    loop {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some(byte) if target.get(t) == Some(byte) => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            None if t == target.len() || target[t..].starts_with(b"::") => return true,
+            _ => {}
+        }
+        match star {
+            Some((star_p, star_t)) if star_t < target.len() => {
+                star = Some((star_p, star_t + 1));
+                p = star_p + 1;
+                t = star_t + 1;
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Returns the number of literal characters in the pattern
+fn specificity(pattern: &[u8]) -> usize {
+    pattern.iter().filter(|&&byte| byte != b'*').count()
+}
+
+fn decode_level(level: u8) -> Option<::log::LevelFilter> {
+    use ::log::LevelFilter;
+    match level {
+        0 => Some(LevelFilter::Off),
+        1 => Some(LevelFilter::Error),
+        2 => Some(LevelFilter::Warn),
+        3 => Some(LevelFilter::Info),
+        4 => Some(LevelFilter::Debug),
+        5 => Some(LevelFilter::Trace),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    target: [u8; MAX_TARGET_LEN],
+    len: u8,
+    level: u8,
+}
+
+impl Entry {
+    const EMPTY: Self = Entry {
+        target: [0; MAX_TARGET_LEN],
+        len: 0,
+        level: ALL_LEVELS,
+    };
+
+    fn target(&self) -> &[u8] {
+        &self.target[..(self.len as usize).min(MAX_TARGET_LEN)]
+    }
+}
+
 /// Filters for log channels
-pub struct Filters(&'static [Filter]);
+///
+/// The collection has a fixed capacity of [`MAX_FILTERS`] targets.
+#[derive(Clone, Copy)]
+pub struct Filters {
+    entries: [Entry; MAX_FILTERS],
+    len: usize,
+    /// The level for targets that don't match a filter
+    default_level: u8,
+}
 
 impl Filters {
     /// Returns an empty filters collection
     ///
     /// This `Filters` lets all log messages pass through.
     pub const fn empty() -> Self {
-        Filters::new(&[])
+        Filters {
+            entries: [Entry::EMPTY; MAX_FILTERS],
+            len: 0,
+            default_level: ::log::LevelFilter::Off as u8,
+        }
     }
 
     /// Create a `Filters` collection
-    pub const fn new(filters: &'static [Filter]) -> Self {
-        Filters(filters)
+    pub fn new(filters: &[Filter]) -> Result<Self, FilterError> {
+        let mut this = Filters::empty();
+        for &(target, level) in filters {
+            this.set(target, level)?;
+        }
+        Ok(this)
+    }
+
+    fn entries(&self) -> &[Entry] {
+        &self.entries[..self.len.min(MAX_FILTERS)]
+    }
+
+    fn position(&self, target: &str) -> Option<usize> {
+        self.entries()
+            .iter()
+            .position(|entry| entry.target() == target.as_bytes())
+    }
+
+    /// Add a filter for `target`, or change the level of an existing filter
+    pub fn set(
+        &mut self,
+        target: &str,
+        level: Option<::log::LevelFilter>,
+    ) -> Result<(), FilterError> {
+        let idx = match self.position(target) {
+            Some(idx) => idx,
+            None if target.len() > MAX_TARGET_LEN => return Err(FilterError::TargetTooLong),
+            None if self.len == MAX_FILTERS => return Err(FilterError::Full),
+            None => {
+                let entry = &mut self.entries[self.len];
+                entry.target[..target.len()].copy_from_slice(target.as_bytes());
+                entry.len = target.len() as u8;
+                self.len += 1;
+                self.len - 1
+            }
+        };
+        self.entries[idx].level = encode_level(level);
+        Ok(())
+    }
+
+    /// Remove the filter for `target`
+    ///
+    /// Returns `false` if there was no filter for `target`.
+    pub fn remove(&mut self, target: &str) -> bool {
+        match self.position(target) {
+            Some(idx) => {
+                self.entries.copy_within(idx + 1..self.len, idx);
+                self.len -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove all filters
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Set the level for targets that don't match any filter
+    ///
+    /// The default level only applies when there's at least one filter.
+    /// It defaults to `Off`.
+    pub fn set_default_level(&mut self, level: ::log::LevelFilter) {
+        self.default_level = level as u8;
+    }
+
+    /// Returns the level for targets that don't match any filter
+    pub fn default_level(&self) -> ::log::LevelFilter {
+        decode_level(self.default_level).unwrap_or(::log::LevelFilter::Trace)
+    }
+
+    /// Returns the level of the filter for `target`
+    ///
+    /// The outer `Option` is `None` if there's no filter for `target`.
+    pub fn level(&self, target: &str) -> Option<Option<::log::LevelFilter>> {
+        self.position(target)
+            .map(|idx| decode_level(self.entries[idx].level))
+    }
+
+    /// Change the filters with a text command
+    ///
+    /// | Command                 | Effect                                    |
+    /// | ----------------------- | ----------------------------------------- |
+    /// | `log <target> <level>`  | Set the target's level (`off` to `trace`) |
+    /// | `log <target> all`      | Log all levels from the target            |
+    /// | `log <target> remove`   | Remove the target's filter                |
+    /// | `log default <level>`   | Set the level for unmatched targets       |
+    /// | `log clear`             | Remove all filters                        |
+    ///
+    /// Levels are case insensitive.
+    pub fn command(&mut self, command: &str) -> Result<(), FilterError> {
+        let mut words = command.split_whitespace();
+        if words.next() != Some("log") {
+            return Err(FilterError::InvalidCommand);
+        }
+        match (words.next(), words.next(), words.next()) {
+            (Some("clear"), None, None) => {
+                self.clear();
+                Ok(())
+            }
+            (Some("default"), Some(level), None) => {
+                let level = level.parse().map_err(|_| FilterError::InvalidCommand)?;
+                self.set_default_level(level);
+                Ok(())
+            }
+            (Some(target), Some("remove"), None) => {
+                self.remove(target);
+                Ok(())
+            }
+            (Some(target), Some(level), None) if level.eq_ignore_ascii_case("all") => {
+                self.set(target, None)
+            }
+            (Some(target), Some(level), None) => {
+                let level = level.parse().map_err(|_| FilterError::InvalidCommand)?;
+                self.set(target, Some(level))
+            }
+            _ => Err(FilterError::InvalidCommand),
+        }
     }
 }
 
@@ -42,25 +285,92 @@ impl Filters {
     /// Returns `true` if, based on this metadata, logging should be enabled
     ///
     /// `is_enabled()` considers the permitted modules and log levels for those modules.
+    /// If no filter matches the target, `is_enabled()` uses the default level.
     pub fn is_enabled(&self, metadata: &::log::Metadata) -> bool {
-        if self.0.is_empty() {
-            true
-        } else if let Some(idx) = self
-            .0
-            .iter()
-            .position(|&(target, _)| target == metadata.target())
-        {
-            let (_, lvl) = self.0[idx];
-            lvl.is_none() || lvl.filter(|lvl| metadata.level() <= *lvl).is_some()
-        } else {
-            false
+        if self.entries().is_empty() {
+            return true;
         }
+        let target = metadata.target().as_bytes();
+        let mut best: Option<&Entry> = None;
+        for entry in self.entries() {
+            if matches(entry.target(), target) {
+                best = match best {
+                    Some(best) if specificity(best.target()) >= specificity(entry.target()) => {
+                        Some(best)
+                    }
+                    _ => Some(entry),
+                };
+            }
+        }
+        let lvl = match best {
+            Some(entry) => decode_level(entry.level),
+            None => Some(self.default_level()),
+        };
+        lvl.is_none() || lvl.filter(|lvl| metadata.level() <= *lvl).is_some()
+    }
+}
+
+/// Filters that are read by the logger while they change
+///
+/// This is a sequence lock. Readers use the filters in place, then check that
+/// the sequence number didn't change. Writers increment the sequence number
+/// before and after changing the filters. A writer can't be preempted
+/// by a reader, so readers never wait on writers.
+pub struct SharedFilters {
+    sequence: AtomicUsize,
+    filters: UnsafeCell<Filters>,
+}
+
+// Safety: readers discard results that they computed while a writer
+// changed the filters. Writers are serialized with critical sections.
+unsafe impl Sync for SharedFilters {}
+
+impl SharedFilters {
+    pub const fn new() -> Self {
+        SharedFilters {
+            sequence: AtomicUsize::new(0),
+            filters: UnsafeCell::new(Filters::empty()),
+        }
+    }
+
+    /// Returns the result of `f` for a consistent view of the filters
+    ///
+    /// `f` runs on the filters in place, without copying them. If a writer
+    /// changed the filters while `f` ran, `f` runs again.
+    pub fn read<R>(&self, f: impl Fn(&Filters) -> R) -> R {
+        loop {
+            let sequence = self.sequence.load(Ordering::Acquire);
+            if sequence % 2 == 0 {
+                // Safety: every bit pattern is a valid `Filters`, and every
+                // `Filters` method bounds its indices, so `f` is safe on torn
+                // filters. We discard its result below.
+                let result = f(unsafe { &*self.filters.get() });
+                atomic::fence(Ordering::Acquire);
+                if self.sequence.load(Ordering::Relaxed) == sequence {
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Change the filters
+    ///
+    /// Runs `f` in a critical section.
+    pub fn modify<R>(&self, f: impl FnOnce(&mut Filters) -> R) -> R {
+        cortex_m::interrupt::free(|_| {
+            self.sequence.fetch_add(1, Ordering::Relaxed);
+            atomic::fence(Ordering::Release);
+            // Safety: the critical section excludes other writers.
+            let result = f(unsafe { &mut *self.filters.get() });
+            self.sequence.fetch_add(1, Ordering::Release);
+            result
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Filters;
+    use super::{matches, FilterError, Filters, MAX_FILTERS};
     use log::{Level, LevelFilter};
 
     fn metadata(level: Level, target: &'static str) -> ::log::Metadata<'static> {
@@ -80,7 +390,7 @@ mod tests {
 
     #[test]
     fn empty_always_enabled() {
-        let filters = Filters(&[]);
+        let filters = Filters::empty();
         ALL_LEVELS.iter().for_each(|level| {
             assert!(filters.is_enabled(&metadata(*level, "foobar")));
         });
@@ -88,7 +398,7 @@ mod tests {
 
     #[test]
     fn no_level_always_true() {
-        let filters = Filters(&[("barbaz", None), ("foobar", None)]);
+        let filters = Filters::new(&[("barbaz", None), ("foobar", None)]).unwrap();
         ALL_LEVELS
             .iter()
             .for_each(|level| assert!(filters.is_enabled(&metadata(*level, "foobar"))));
@@ -96,10 +406,11 @@ mod tests {
 
     #[test]
     fn module_level() {
-        let filters = Filters(&[
+        let filters = Filters::new(&[
             ("barbaz", Some(LevelFilter::Error)),
             ("foobar", Some(LevelFilter::Info)),
-        ]);
+        ])
+        .unwrap();
         ALL_LEVELS
             .iter()
             .filter(|level| **level <= LevelFilter::Info)
@@ -128,4 +439,141 @@ mod tests {
             });
         assert!(filters.is_enabled(&metadata(Level::Error, "barbaz")));
     }
+
+    #[test]
+    fn set_remove() {
+        let mut filters = Filters::empty();
+        filters.set("foobar", Some(LevelFilter::Warn)).unwrap();
+        filters.set("barbaz", None).unwrap();
+        assert_eq!(filters.level("foobar"), Some(Some(LevelFilter::Warn)));
+        assert!(!filters.is_enabled(&metadata(Level::Info, "foobar")));
+
+        filters.set("foobar", Some(LevelFilter::Info)).unwrap();
+        assert!(filters.is_enabled(&metadata(Level::Info, "foobar")));
+
+        assert!(filters.remove("foobar"));
+        assert!(!filters.remove("foobar"));
+        assert_eq!(filters.level("foobar"), None);
+        assert_eq!(filters.level("barbaz"), Some(None));
+        assert!(!filters.is_enabled(&metadata(Level::Info, "foobar")));
+    }
+
+    #[test]
+    fn capacity() {
+        let mut filters = Filters::empty();
+        let targets = [
+            "t0", "t1", "t2", "t3", "t4", "t5", "t6", "t7", "t8", "t9", "t10", "t11", "t12", "t13",
+            "t14", "t15",
+        ];
+        assert_eq!(targets.len(), MAX_FILTERS);
+        for target in targets.iter() {
+            filters.set(target, None).unwrap();
+        }
+        assert_eq!(filters.set("t16", None), Err(FilterError::Full));
+        // Changing an existing filter is OK.
+        filters.set("t0", Some(LevelFilter::Off)).unwrap();
+        assert_eq!(
+            filters.set("a_target_that_is_longer_than_32_bytes", None),
+            Err(FilterError::TargetTooLong)
+        );
+    }
+
+    #[test]
+    fn commands() {
+        let mut filters = Filters::empty();
+        filters.command("log motor debug").unwrap();
+        filters.command("log spi WARN").unwrap();
+        filters.command("  log i2c all ").unwrap();
+        assert_eq!(filters.level("motor"), Some(Some(LevelFilter::Debug)));
+        assert_eq!(filters.level("spi"), Some(Some(LevelFilter::Warn)));
+        assert_eq!(filters.level("i2c"), Some(None));
+
+        filters.command("log motor remove").unwrap();
+        assert_eq!(filters.level("motor"), None);
+        filters.command("log default info").unwrap();
+        assert_eq!(filters.default_level(), LevelFilter::Info);
+        filters.command("log clear").unwrap();
+        assert_eq!(filters.level("spi"), None);
+
+        for command in ["", "log", "log motor", "log motor loud", "set motor info"].iter() {
+            assert_eq!(
+                filters.command(command),
+                Err(FilterError::InvalidCommand),
+                "{}",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn hierarchy() {
+        assert!(matches(b"motor", b"motor"));
+        assert!(matches(b"motor", b"motor::pid"));
+        assert!(!matches(b"motor", b"motorcycle"));
+        assert!(!matches(b"motor", b"my_crate::motor"));
+        assert!(!matches(b"motor::pid", b"motor"));
+
+        let filters = Filters::new(&[("motor", Some(LevelFilter::Info))]).unwrap();
+        assert!(filters.is_enabled(&metadata(Level::Info, "motor::pid")));
+        assert!(!filters.is_enabled(&metadata(Level::Debug, "motor::pid")));
+        assert!(!filters.is_enabled(&metadata(Level::Error, "motorcycle")));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(matches(b"*", b"anything::at::all"));
+        assert!(matches(b"*::motor", b"my_crate::motor"));
+        assert!(matches(b"*::motor", b"my_crate::motor::pid"));
+        assert!(!matches(b"*::motor", b"motor"));
+        assert!(matches(b"my_crate::*::pid", b"my_crate::motor::pid"));
+        assert!(matches(b"motor*", b"motorcycle"));
+        assert!(!matches(b"my_crate::*::pid", b"my_crate::pid"));
+        assert!(matches(b"*a*b", b"xaxb::c"));
+        assert!(!matches(b"*a*b", b"xbxa"));
+    }
+
+    #[test]
+    fn many_wildcards() {
+        let target = [b'a'; 120];
+        assert!(!matches(b"*a*a*a*a*a*a*a*a*b", &target));
+        assert!(matches(b"*a*a*a*a*a*a*a*a*a", &target));
+    }
+
+    #[test]
+    fn precedence() {
+        // Order doesn't matter; the most specific filter wins.
+        let filters = Filters::new(&[
+            ("my_app::motor::pid", Some(LevelFilter::Off)),
+            ("*", Some(LevelFilter::Error)),
+            ("*::motor", Some(LevelFilter::Debug)),
+            ("my_app", Some(LevelFilter::Warn)),
+        ])
+        .unwrap();
+        // Only '*' matches.
+        assert!(filters.is_enabled(&metadata(Level::Error, "other")));
+        assert!(!filters.is_enabled(&metadata(Level::Warn, "other")));
+        // 'my_app' is more specific than '*'.
+        assert!(filters.is_enabled(&metadata(Level::Warn, "my_app::spi")));
+        assert!(!filters.is_enabled(&metadata(Level::Info, "my_app::spi")));
+        // '*::motor' is more specific than 'my_app'.
+        assert!(filters.is_enabled(&metadata(Level::Debug, "my_app::motor")));
+        assert!(filters.is_enabled(&metadata(Level::Debug, "other::motor")));
+        // 'my_app::motor::pid' is the most specific.
+        assert!(!filters.is_enabled(&metadata(Level::Error, "my_app::motor::pid")));
+        assert!(!filters.is_enabled(&metadata(Level::Error, "my_app::motor::pid::gains")));
+    }
+
+    #[test]
+    fn default_level() {
+        let mut filters = Filters::new(&[("motor", Some(LevelFilter::Trace))]).unwrap();
+        assert_eq!(filters.default_level(), LevelFilter::Off);
+        ALL_LEVELS
+            .iter()
+            .for_each(|level| assert!(!filters.is_enabled(&metadata(*level, "spi"))));
+
+        filters.set_default_level(LevelFilter::Warn);
+        assert!(filters.is_enabled(&metadata(Level::Warn, "spi")));
+        assert!(!filters.is_enabled(&metadata(Level::Info, "spi")));
+        assert!(filters.is_enabled(&metadata(Level::Trace, "motor")));
+    }
 }
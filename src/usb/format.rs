@@ -0,0 +1,276 @@
+//! Log record formatting
+//!
+//! A [`Formatter`] writes a log record's text. Select a formatter with
+//! [`LoggingConfig::formatter`](super::LoggingConfig::formatter), or write
+//! your own. The logger ends every record with a line ending, so formatters
+//! shouldn't.
+//!
+//! | Formatter       | Example                                           |
+//! | --------------- | ------------------------------------------------- |
+//! | [`default`]     | `[INFO motor]: speed 42`                          |
+//! | [`timestamped`] | `[12.000125 INFO motor]: speed 42`                |
+//! | [`verbose`]     | `[12.000125 INFO my_crate::motor:17]: speed 42`   |
+//! | [`compact`]     | `I speed 42`                                      |
+//!
+//! Timestamps come from the [`Clock`] in
+//! [`LoggingConfig::clock`](super::LoggingConfig::clock). Without a clock,
+//! formatters omit the timestamp.
+//!
+//! This example shows a custom formatter that colors the level with ANSI
+//! escape codes.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::usb::format::Timestamp;
+//! use core::fmt;
+//!
+//! fn colored(
+//!     out: &mut dyn fmt::Write,
+//!     record: &log::Record,
+//!     _: Option<Timestamp>,
+//! ) -> fmt::Result {
+//!     let color = match record.level() {
+//!         log::Level::Error => 31,
+//!         log::Level::Warn => 33,
+//!         _ => 0,
+//!     };
+//!     write!(out, "\x1b[{}m{}\x1b[0m {}", color, record.level(), record.args())
+//! }
+//!
+//! let config = bsp::usb::LoggingConfig {
+//!     formatter: colored,
+//!     ..Default::default()
+//! };
+//! ```
+
+use core::fmt;
+
+/// Writes a log record
+///
+/// `timestamp` is `None` if there's no [`Clock`].
+pub type Formatter = fn(
+    out: &mut dyn fmt::Write,
+    record: &::log::Record,
+    timestamp: Option<Timestamp>,
+) -> fmt::Result;
+
+/// A source of log timestamps
+///
+/// `now` returns a tick count that never wraps. You might extend the DWT cycle
+/// counter, or a GPT or SRTC counter, to 64 bits. `now` is called from every
+/// context that logs, including interrupts, so it should be quick.
+///
+/// ```no_run
+/// use teensy4_bsp as bsp;
+///
+/// fn now() -> u64 {
+///     // Read your 64-bit, 1MHz timer...
+///     # 0
+/// }
+///
+/// let config = bsp::usb::LoggingConfig {
+///     formatter: bsp::usb::format::timestamped,
+///     clock: Some(bsp::usb::format::Clock {
+///         now,
+///         ticks_per_second: 1_000_000,
+///     }),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    /// Returns the current tick count
+    pub now: fn() -> u64,
+    /// The clock's frequency, in Hz
+    pub ticks_per_second: u32,
+}
+
+impl Clock {
+    /// Returns the current time
+    pub fn timestamp(&self) -> Timestamp {
+        Timestamp {
+            ticks: (self.now)(),
+            ticks_per_second: self.ticks_per_second,
+        }
+    }
+}
+
+/// The time when a record was logged
+///
+/// The `Display` implementation shows seconds, with microsecond resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamp {
+    /// The clock's tick count
+    pub ticks: u64,
+    /// The clock's frequency, in Hz
+    pub ticks_per_second: u32,
+}
+
+impl Timestamp {
+    /// Returns the whole seconds, and the remaining microseconds
+    pub fn seconds_micros(&self) -> (u64, u32) {
+        let ticks_per_second = u64::from(self.ticks_per_second.max(1));
+        let seconds = self.ticks / ticks_per_second;
+        let micros = (self.ticks % ticks_per_second) * 1_000_000 / ticks_per_second;
+        (seconds, micros as u32)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (seconds, micros) = self.seconds_micros();
+        write!(f, "{}.{:06}", seconds, micros)
+    }
+}
+
+/// `[{level} {target}]: {message}`
+///
+/// This is the default formatter. It ignores the timestamp.
+pub fn default(
+    out: &mut dyn fmt::Write,
+    record: &::log::Record,
+    _: Option<Timestamp>,
+) -> fmt::Result {
+    write!(
+        out,
+        "[{} {}]: {}",
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+/// `[{timestamp} {level} {target}]: {message}`
+pub fn timestamped(
+    out: &mut dyn fmt::Write,
+    record: &::log::Record,
+    timestamp: Option<Timestamp>,
+) -> fmt::Result {
+    out.write_char('[')?;
+    if let Some(timestamp) = timestamp {
+        write!(out, "{} ", timestamp)?;
+    }
+    write!(
+        out,
+        "{} {}]: {}",
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+/// `[{timestamp} {level} {module_path}:{line}]: {message}`
+///
+/// If the record doesn't have a module path, this shows the target.
+pub fn verbose(
+    out: &mut dyn fmt::Write,
+    record: &::log::Record,
+    timestamp: Option<Timestamp>,
+) -> fmt::Result {
+    out.write_char('[')?;
+    if let Some(timestamp) = timestamp {
+        write!(out, "{} ", timestamp)?;
+    }
+    let path = record.module_path().unwrap_or_else(|| record.target());
+    write!(out, "{} {}", record.level(), path)?;
+    if let Some(line) = record.line() {
+        write!(out, ":{}", line)?;
+    }
+    write!(out, "]: {}", record.args())
+}
+
+/// `{L} {message}`, where `L` is the first letter of the level
+///
+/// It ignores the timestamp.
+pub fn compact(
+    out: &mut dyn fmt::Write,
+    record: &::log::Record,
+    _: Option<Timestamp>,
+) -> fmt::Result {
+    let letter = match record.level() {
+        ::log::Level::Error => 'E',
+        ::log::Level::Warn => 'W',
+        ::log::Level::Info => 'I',
+        ::log::Level::Debug => 'D',
+        ::log::Level::Trace => 'T',
+    };
+    write!(out, "{} {}", letter, record.args())
+}
+
+/// Displays a record with a formatter
+pub(super) struct Formatted<'a> {
+    pub formatter: Formatter,
+    pub record: &'a ::log::Record<'a>,
+    pub timestamp: Option<Timestamp>,
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.formatter)(f, self.record, self.timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::{Formatted, Formatter, Timestamp};
+    use std::string::String;
+
+    const TIMESTAMP: Timestamp = Timestamp {
+        ticks: 12_000_125,
+        ticks_per_second: 1_000_000,
+    };
+
+    fn format(formatter: Formatter, timestamp: Option<Timestamp>) -> String {
+        // The record borrows temporaries, so it only lives in this statement.
+        std::format!(
+            "{}",
+            Formatted {
+                formatter,
+                record: &::log::Record::builder()
+                    .level(::log::Level::Info)
+                    .target("motor")
+                    .module_path(Some("my_crate::motor"))
+                    .line(Some(17))
+                    .args(format_args!("speed {}", 42))
+                    .build(),
+                timestamp,
+            }
+        )
+    }
+
+    #[test]
+    fn presets() {
+        assert_eq!(format(super::default, None), "[INFO motor]: speed 42");
+        assert_eq!(
+            format(super::default, Some(TIMESTAMP)),
+            "[INFO motor]: speed 42"
+        );
+        assert_eq!(
+            format(super::timestamped, Some(TIMESTAMP)),
+            "[12.000125 INFO motor]: speed 42"
+        );
+        assert_eq!(format(super::timestamped, None), "[INFO motor]: speed 42");
+        assert_eq!(
+            format(super::verbose, Some(TIMESTAMP)),
+            "[12.000125 INFO my_crate::motor:17]: speed 42"
+        );
+        assert_eq!(format(super::compact, Some(TIMESTAMP)), "I speed 42");
+    }
+
+    #[test]
+    fn timestamp() {
+        let timestamp = Timestamp {
+            ticks: 1_200_000_300,
+            ticks_per_second: 600_000_000,
+        };
+        assert_eq!(timestamp.seconds_micros(), (2, 0));
+        assert_eq!(std::format!("{}", timestamp), "2.000000");
+
+        let timestamp = Timestamp {
+            ticks: 98_304 + 16_384,
+            ticks_per_second: 32_768,
+        };
+        assert_eq!(std::format!("{}", timestamp), "3.500000");
+    }
+}
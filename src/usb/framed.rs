@@ -0,0 +1,344 @@
+//! COBS-framed packets over USB serial
+//!
+//! A [`FramedWriter`] sends each payload as a single frame, and a
+//! [`FramedReader`] collects received bytes until it has a complete
+//! frame. Frames are encoded with [consistent overhead byte stuffing](cobs)
+//! (COBS), carry an optional CRC, and end with a zero, so the reader
+//! resynchronizes at the next frame after it loses data.
+//!
+//! The codec is provided by the `teensy4-framed` package. Use that same
+//! package on the host; its `FramedPort` talks to a `FramedWriter` and a
+//! `FramedReader`. Make sure that both ends use the same [`Checksum`].
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::hal::ral::usb::USB1;
+//! use bsp::usb::framed::{Checksum, FramedReader, FramedWriter};
+//!
+//! let (mut poller, reader, writer) =
+//!     bsp::usb::split(USB1::take().unwrap(), Default::default()).unwrap();
+//! let mut reader: FramedReader<256> = FramedReader::new(reader, Checksum::Crc16);
+//! let mut writer = FramedWriter::new(writer, Checksum::Crc16);
+//!
+//! let mut payload = [0; 256];
+//! loop {
+//!     poller.poll();
+//!     if let Ok(Some(len)) = reader.poll_frame(&mut payload) {
+//!         // Echo the payload.
+//!         writer.send(&payload[..len]).ok();
+//!     }
+//! }
+//! ```
+
+pub use teensy4_framed::{
+    cobs, crc, decode_frame, encode_frame, max_frame_len, write_frame, Checksum, Error, DELIMITER,
+};
+
+use super::{cdc::TX_LEN, with_port, Reader, Writer};
+
+/// An error when sending or receiving a frame
+#[derive(Debug)]
+pub enum FrameError {
+    /// The received frame is malformed, its checksum doesn't match, or
+    /// the payload doesn't fit in your buffer
+    ///
+    /// The reader drops the frame. Call `poll_frame` again to receive the
+    /// next frame.
+    Decode(Error),
+    /// The frame is too large for the reader's buffer, or the writer's
+    /// serial buffer
+    ///
+    /// A reader drops everything up to the next frame.
+    TooLarge,
+    /// There isn't enough space for the frame in the serial buffer
+    ///
+    /// Nothing was written. Try again after `poll` sends more data.
+    WouldBlock,
+    /// A USB error
+    Usb(super::Error),
+}
+
+impl From<Error> for FrameError {
+    fn from(err: Error) -> Self {
+        FrameError::Decode(err)
+    }
+}
+
+impl From<super::Error> for FrameError {
+    fn from(err: super::Error) -> Self {
+        FrameError::Usb(err)
+    }
+}
+
+/// Sends frames to the USB serial host
+pub struct FramedWriter {
+    writer: Writer,
+    checksum: Checksum,
+}
+
+impl FramedWriter {
+    pub fn new(writer: Writer, checksum: Checksum) -> Self {
+        FramedWriter { writer, checksum }
+    }
+
+    /// Release the writer
+    pub fn into_inner(self) -> Writer {
+        self.writer
+    }
+
+    /// Send `payload` as a single frame
+    ///
+    /// The whole frame is buffered, or nothing is. Returns
+    /// [`FrameError::WouldBlock`] if the serial buffer doesn't have space for
+    /// the frame, and [`FrameError::TooLarge`] if it never will.
+    pub fn send(&mut self, payload: &[u8]) -> Result<(), FrameError> {
+        let len = max_frame_len(payload.len(), self.checksum);
+        if len > TX_LEN {
+            return Err(FrameError::TooLarge);
+        }
+        let checksum = self.checksum;
+        with_port(self.writer.port, |serial| {
+            if serial.write_available() < len {
+                return Err(FrameError::WouldBlock);
+            }
+            write_frame(payload, checksum, |bytes| {
+                serial.write(bytes);
+            });
+            Ok(())
+        })?
+    }
+}
+
+/// Receives frames from the USB serial host
+///
+/// `N` must be at least `max_frame_len(payload, checksum)`, which includes the
+/// delimiter, for your largest payload. See [`max_frame_len`].
+pub struct FramedReader<const N: usize> {
+    reader: Reader,
+    checksum: Checksum,
+    deframer: Deframer<N>,
+}
+
+impl<const N: usize> FramedReader<N> {
+    pub fn new(reader: Reader, checksum: Checksum) -> Self {
+        FramedReader {
+            reader,
+            checksum,
+            deframer: Deframer::new(),
+        }
+    }
+
+    /// Release the reader
+    ///
+    /// Any partially-received frame is lost.
+    pub fn into_inner(self) -> Reader {
+        self.reader
+    }
+
+    /// Receive the next frame's payload into `buffer`
+    ///
+    /// Reads all available data. Returns the size of the payload once there's
+    /// a complete frame, or `None` if there isn't a frame yet. `buffer` needs
+    /// room for the payload, and its checksum.
+    pub fn poll_frame(&mut self, buffer: &mut [u8]) -> Result<Option<usize>, FrameError> {
+        let reader = &mut self.reader;
+        poll_frame(&mut self.deframer, self.checksum, buffer, |bytes| {
+            reader.read(bytes)
+        })
+    }
+}
+
+/// Fill the deframer with `read` until there's a frame, or until there's no
+/// more data
+fn poll_frame<const N: usize>(
+    deframer: &mut Deframer<N>,
+    checksum: Checksum,
+    buffer: &mut [u8],
+    mut read: impl FnMut(&mut [u8]) -> Result<usize, super::Error>,
+) -> Result<Option<usize>, FrameError> {
+    loop {
+        if let Some(encoded) = deframer.next_frame()? {
+            return Ok(Some(decode_frame(encoded, checksum, buffer)?));
+        }
+        let count = read(deframer.space())?;
+        if count == 0 {
+            return Ok(None);
+        }
+        deframer.filled(count);
+    }
+}
+
+/// Buffers received bytes until there's a complete frame
+struct Deframer<const N: usize> {
+    buffer: [u8; N],
+    /// The number of received bytes in the buffer
+    len: usize,
+    /// The size of the last frame, including its delimiter
+    ///
+    /// It's removed from the buffer on the next call to `next_frame`.
+    consumed: usize,
+    /// Set while dropping a frame that's too large
+    discarding: bool,
+}
+
+impl<const N: usize> Deframer<N> {
+    const fn new() -> Self {
+        Deframer {
+            buffer: [0; N],
+            len: 0,
+            consumed: 0,
+            discarding: false,
+        }
+    }
+
+    /// Returns the next encoded frame, without its delimiter
+    ///
+    /// Skips empty frames. Returns [`FrameError::TooLarge`] once when a frame
+    /// fills the buffer, then drops the rest of that frame.
+    fn next_frame(&mut self) -> Result<Option<&[u8]>, FrameError> {
+        loop {
+            self.buffer.copy_within(self.consumed..self.len, 0);
+            self.len -= self.consumed;
+            self.consumed = 0;
+
+            match self.buffer[..self.len]
+                .iter()
+                .position(|&byte| byte == DELIMITER)
+            {
+                Some(end) => {
+                    self.consumed = end + 1;
+                    let discarded = core::mem::replace(&mut self.discarding, false);
+                    if !discarded && end > 0 {
+                        return Ok(Some(&self.buffer[..end]));
+                    }
+                }
+                None if self.len == N => {
+                    self.len = 0;
+                    if !core::mem::replace(&mut self.discarding, true) {
+                        return Err(FrameError::TooLarge);
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Returns the free space for received bytes
+    ///
+    /// Only call after `next_frame` returns `None`, or an error.
+    fn space(&mut self) -> &mut [u8] {
+        &mut self.buffer[self.len..]
+    }
+
+    /// Indicate that `count` bytes were written into `space`
+    fn filled(&mut self, count: usize) {
+        self.len += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_frame, max_frame_len, poll_frame, Checksum, Deframer, Error, FrameError};
+
+    /// Returns a reader that provides `data` in `chunk` sized pieces
+    fn chunks<'a>(
+        mut data: &'a [u8],
+        chunk: usize,
+    ) -> impl FnMut(&mut [u8]) -> Result<usize, crate::usb::Error> + 'a {
+        move |buffer| {
+            let count = chunk.min(buffer.len()).min(data.len());
+            buffer[..count].copy_from_slice(&data[..count]);
+            data = &data[count..];
+            Ok(count)
+        }
+    }
+
+    fn frames(payloads: &[&[u8]], checksum: Checksum) -> ([u8; 256], usize) {
+        let mut stream = [0; 256];
+        let mut len = 0;
+        for payload in payloads {
+            len += encode_frame(payload, checksum, &mut stream[len..]).unwrap();
+        }
+        (stream, len)
+    }
+
+    #[test]
+    fn receive_in_pieces() {
+        let payloads: [&[u8]; 3] = [b"hello", b"", b"\0world\0"];
+        let (stream, len) = frames(&payloads, Checksum::Crc32);
+        for &chunk in [1, 3, 7, 256].iter() {
+            let mut deframer = Deframer::<32>::new();
+            let mut read = chunks(&stream[..len], chunk);
+            let mut received = 0;
+            let mut payload = [0; 32];
+            while let Some(size) =
+                poll_frame(&mut deframer, Checksum::Crc32, &mut payload, &mut read).unwrap()
+            {
+                assert_eq!(&payload[..size], payloads[received]);
+                received += 1;
+            }
+            assert_eq!(received, payloads.len());
+        }
+    }
+
+    #[test]
+    fn receive_largest_frame() {
+        const N: usize = max_frame_len(10, Checksum::Crc16);
+        let (stream, len) = frames(&[&[0x42; 10]], Checksum::Crc16);
+        assert_eq!(len, N);
+        let mut deframer = Deframer::<N>::new();
+        let mut read = chunks(&stream[..len], 3);
+        let mut payload = [0; N];
+        assert_eq!(
+            poll_frame(&mut deframer, Checksum::Crc16, &mut payload, &mut read).unwrap(),
+            Some(10)
+        );
+        assert_eq!(&payload[..10], &[0x42; 10]);
+    }
+
+    #[test]
+    fn skip_noise_and_bad_frames() {
+        let (frames, len) = frames(&[b"good"], Checksum::Crc16);
+        let mut stream = [0; 64];
+        stream[..4].copy_from_slice(&[0, 0, 0x42, 0]);
+        stream[4..4 + len].copy_from_slice(&frames[..len]);
+
+        let mut deframer = Deframer::<32>::new();
+        let mut read = chunks(&stream[..4 + len], 64);
+        let mut payload = [0; 32];
+        assert!(matches!(
+            poll_frame(&mut deframer, Checksum::Crc16, &mut payload, &mut read),
+            Err(FrameError::Decode(Error::Malformed))
+        ));
+        assert_eq!(
+            poll_frame(&mut deframer, Checksum::Crc16, &mut payload, &mut read).unwrap(),
+            Some(4)
+        );
+        assert_eq!(&payload[..4], b"good");
+    }
+
+    #[test]
+    fn drop_large_frames() {
+        let (stream, len) = frames(&[&[0x42; 40], b"small", &[0x42; 100]], Checksum::None);
+        let mut deframer = Deframer::<16>::new();
+        let mut read = chunks(&stream[..len], 8);
+        let mut payload = [0; 16];
+        assert!(matches!(
+            poll_frame(&mut deframer, Checksum::None, &mut payload, &mut read),
+            Err(FrameError::TooLarge)
+        ));
+        assert_eq!(
+            poll_frame(&mut deframer, Checksum::None, &mut payload, &mut read).unwrap(),
+            Some(5)
+        );
+        assert_eq!(&payload[..5], b"small");
+        assert!(matches!(
+            poll_frame(&mut deframer, Checksum::None, &mut payload, &mut read),
+            Err(FrameError::TooLarge)
+        ));
+        assert_eq!(
+            poll_frame(&mut deframer, Checksum::None, &mut payload, &mut read).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,837 @@
+//! USB HID devices: keyboard, mouse, joystick, and raw HID
+//!
+//! Use [`split_hid`](super::split_hid) to present HID interfaces instead of
+//! a USB serial port. Select the interfaces with a [`Config`]. Each selected
+//! interface has a handle in the returned [`Devices`]:
+//!
+//! - a [`Keyboard`] sends [`KeyboardReport`]s, and receives the host's LEDs.
+//! - a [`Mouse`] sends [`MouseReport`]s.
+//! - a [`Joystick`] sends [`JoystickReport`]s.
+//! - a [`RawHid`] sends and receives 64 byte packets. It uses Teensyduino's
+//!   raw HID usage page (`0xFFAB`) and usage (`0x0200`), so host software
+//!   written for Teensyduino's raw HID should also work.
+//!
+//! There's no serial port; see [device classes](super#device-classes).
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::hal::ral::usb::USB1;
+//! use bsp::usb::hid;
+//!
+//! let identity = bsp::usb::UsbIdentity {
+//!     // Teensyduino's product ID for keyboard, mouse, and joystick
+//!     product_id: 0x0482,
+//!     product: "Keyboard/Mouse/Joystick",
+//!     ..Default::default()
+//! };
+//! let (mut poller, devices) = bsp::usb::split_hid(
+//!     USB1::take().unwrap(),
+//!     identity,
+//!     hid::Config {
+//!         keyboard: true,
+//!         mouse: true,
+//!         ..Default::default()
+//!     },
+//! )
+//! .unwrap();
+//! let keyboard = devices.keyboard.unwrap();
+//! let mouse = devices.mouse.unwrap();
+//!
+//! loop {
+//!     poller.poll();
+//!     mouse.send(&hid::MouseReport { x: 5, ..Default::default() }).ok();
+//!     if let Some(report) = hid::KeyboardReport::ascii('a') {
+//!         keyboard.send(&report).ok();
+//!     }
+//! }
+//! ```
+
+use super::bus::Bus;
+use super::{with_stack, Error};
+use usb_device::{
+    bus::{InterfaceNumber, UsbBusAllocator},
+    class::{ControlIn, ControlOut, UsbClass},
+    control::{Recipient, Request, RequestType},
+    descriptor::DescriptorWriter,
+    endpoint::{EndpointAddress, EndpointIn, EndpointOut},
+};
+
+const USB_CLASS_HID: u8 = 0x03;
+const HID_SUBCLASS_NONE: u8 = 0x00;
+const HID_SUBCLASS_BOOT: u8 = 0x01;
+const HID_PROTOCOL_NONE: u8 = 0x00;
+const HID_PROTOCOL_KEYBOARD: u8 = 0x01;
+const HID_PROTOCOL_MOUSE: u8 = 0x02;
+
+const HID_DESCRIPTOR: u8 = 0x21;
+const REPORT_DESCRIPTOR: u8 = 0x22;
+
+const GET_REPORT: u8 = 0x01;
+const GET_IDLE: u8 = 0x02;
+const GET_PROTOCOL: u8 = 0x03;
+const SET_REPORT: u8 = 0x09;
+const SET_IDLE: u8 = 0x0A;
+const SET_PROTOCOL: u8 = 0x0B;
+
+/// The size of a raw HID packet
+pub const RAW_PACKET_SIZE: usize = 64;
+
+/// The largest report, in bytes
+const MAX_REPORT_LEN: usize = RAW_PACKET_SIZE;
+
+#[rustfmt::skip]
+const KEYBOARD_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,         // Usage Page (Generic Desktop)
+    0x09, 0x06,         // Usage (Keyboard)
+    0xA1, 0x01,         // Collection (Application)
+    0x05, 0x07,         //   Usage Page (Keyboard)
+    0x19, 0xE0,         //   Usage Minimum (Left Control)
+    0x29, 0xE7,         //   Usage Maximum (Right GUI)
+    0x15, 0x00,         //   Logical Minimum (0)
+    0x25, 0x01,         //   Logical Maximum (1)
+    0x75, 0x01,         //   Report Size (1)
+    0x95, 0x08,         //   Report Count (8)
+    0x81, 0x02,         //   Input (Data, Variable, Absolute)
+    0x75, 0x08,         //   Report Size (8)
+    0x95, 0x01,         //   Report Count (1)
+    0x81, 0x01,         //   Input (Constant)
+    0x05, 0x08,         //   Usage Page (LEDs)
+    0x19, 0x01,         //   Usage Minimum (Num Lock)
+    0x29, 0x05,         //   Usage Maximum (Kana)
+    0x75, 0x01,         //   Report Size (1)
+    0x95, 0x05,         //   Report Count (5)
+    0x91, 0x02,         //   Output (Data, Variable, Absolute)
+    0x75, 0x03,         //   Report Size (3)
+    0x95, 0x01,         //   Report Count (1)
+    0x91, 0x01,         //   Output (Constant)
+    0x05, 0x07,         //   Usage Page (Keyboard)
+    0x19, 0x00,         //   Usage Minimum (0)
+    0x29, 0xFF,         //   Usage Maximum (255)
+    0x15, 0x00,         //   Logical Minimum (0)
+    0x26, 0xFF, 0x00,   //   Logical Maximum (255)
+    0x75, 0x08,         //   Report Size (8)
+    0x95, 0x06,         //   Report Count (6)
+    0x81, 0x00,         //   Input (Data, Array)
+    0xC0,               // End Collection
+];
+
+#[rustfmt::skip]
+const MOUSE_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,         // Usage Page (Generic Desktop)
+    0x09, 0x02,         // Usage (Mouse)
+    0xA1, 0x01,         // Collection (Application)
+    0x09, 0x01,         //   Usage (Pointer)
+    0xA1, 0x00,         //   Collection (Physical)
+    0x05, 0x09,         //     Usage Page (Buttons)
+    0x19, 0x01,         //     Usage Minimum (1)
+    0x29, 0x05,         //     Usage Maximum (5)
+    0x15, 0x00,         //     Logical Minimum (0)
+    0x25, 0x01,         //     Logical Maximum (1)
+    0x75, 0x01,         //     Report Size (1)
+    0x95, 0x05,         //     Report Count (5)
+    0x81, 0x02,         //     Input (Data, Variable, Absolute)
+    0x75, 0x03,         //     Report Size (3)
+    0x95, 0x01,         //     Report Count (1)
+    0x81, 0x01,         //     Input (Constant)
+    0x05, 0x01,         //     Usage Page (Generic Desktop)
+    0x09, 0x30,         //     Usage (X)
+    0x09, 0x31,         //     Usage (Y)
+    0x09, 0x38,         //     Usage (Wheel)
+    0x15, 0x81,         //     Logical Minimum (-127)
+    0x25, 0x7F,         //     Logical Maximum (127)
+    0x75, 0x08,         //     Report Size (8)
+    0x95, 0x03,         //     Report Count (3)
+    0x81, 0x06,         //     Input (Data, Variable, Relative)
+    0xC0,               //   End Collection
+    0xC0,               // End Collection
+];
+
+#[rustfmt::skip]
+const JOYSTICK_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,         // Usage Page (Generic Desktop)
+    0x09, 0x04,         // Usage (Joystick)
+    0xA1, 0x01,         // Collection (Application)
+    0x05, 0x09,         //   Usage Page (Buttons)
+    0x19, 0x01,         //   Usage Minimum (1)
+    0x29, 0x20,         //   Usage Maximum (32)
+    0x15, 0x00,         //   Logical Minimum (0)
+    0x25, 0x01,         //   Logical Maximum (1)
+    0x75, 0x01,         //   Report Size (1)
+    0x95, 0x20,         //   Report Count (32)
+    0x81, 0x02,         //   Input (Data, Variable, Absolute)
+    0x05, 0x01,         //   Usage Page (Generic Desktop)
+    0x09, 0x39,         //   Usage (Hat Switch)
+    0x15, 0x00,         //   Logical Minimum (0)
+    0x25, 0x07,         //   Logical Maximum (7)
+    0x35, 0x00,         //   Physical Minimum (0)
+    0x46, 0x3B, 0x01,   //   Physical Maximum (315)
+    0x65, 0x14,         //   Unit (Degrees)
+    0x75, 0x04,         //   Report Size (4)
+    0x95, 0x01,         //   Report Count (1)
+    0x81, 0x42,         //   Input (Data, Variable, Absolute, Null State)
+    0x65, 0x00,         //   Unit (None)
+    0x45, 0x00,         //   Physical Maximum (0)
+    0x75, 0x04,         //   Report Size (4)
+    0x95, 0x01,         //   Report Count (1)
+    0x81, 0x01,         //   Input (Constant)
+    0x09, 0x30,         //   Usage (X)
+    0x09, 0x31,         //   Usage (Y)
+    0x09, 0x32,         //   Usage (Z)
+    0x09, 0x33,         //   Usage (Rx)
+    0x09, 0x34,         //   Usage (Ry)
+    0x09, 0x35,         //   Usage (Rz)
+    0x16, 0x01, 0x80,   //   Logical Minimum (-32767)
+    0x26, 0xFF, 0x7F,   //   Logical Maximum (32767)
+    0x75, 0x10,         //   Report Size (16)
+    0x95, 0x06,         //   Report Count (6)
+    0x81, 0x02,         //   Input (Data, Variable, Absolute)
+    0xC0,               // End Collection
+];
+
+#[rustfmt::skip]
+const RAW_HID_REPORT_DESCRIPTOR: &[u8] = &[
+    0x06, 0xAB, 0xFF,   // Usage Page (0xFFAB, vendor defined)
+    0x0A, 0x00, 0x02,   // Usage (0x0200)
+    0xA1, 0x01,         // Collection (Application)
+    0x75, 0x08,         //   Report Size (8)
+    0x15, 0x00,         //   Logical Minimum (0)
+    0x26, 0xFF, 0x00,   //   Logical Maximum (255)
+    0x95, 0x40,         //   Report Count (64)
+    0x09, 0x01,         //   Usage (1)
+    0x81, 0x02,         //   Input (Data, Variable, Absolute)
+    0x95, 0x40,         //   Report Count (64)
+    0x09, 0x02,         //   Usage (2)
+    0x91, 0x02,         //   Output (Data, Variable, Absolute)
+    0xC0,               // End Collection
+];
+
+/// Selects the HID interfaces
+///
+/// The default configuration has no interfaces; enable the interfaces
+/// that you need.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub keyboard: bool,
+    pub mouse: bool,
+    pub joystick: bool,
+    pub raw_hid: bool,
+}
+
+/// Handles for the selected HID interfaces
+///
+/// An interface's handle is `None` if it isn't selected in the [`Config`].
+pub struct Devices {
+    pub keyboard: Option<Keyboard>,
+    pub mouse: Option<Mouse>,
+    pub joystick: Option<Joystick>,
+    pub raw_hid: Option<RawHid>,
+}
+
+impl Devices {
+    pub(super) fn new(config: Config) -> Self {
+        Devices {
+            keyboard: if config.keyboard {
+                Some(Keyboard(core::marker::PhantomData))
+            } else {
+                None
+            },
+            mouse: if config.mouse {
+                Some(Mouse(core::marker::PhantomData))
+            } else {
+                None
+            },
+            joystick: if config.joystick {
+                Some(Joystick(core::marker::PhantomData))
+            } else {
+                None
+            },
+            raw_hid: if config.raw_hid {
+                Some(RawHid(core::marker::PhantomData))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Keyboard modifier bits for [`KeyboardReport::modifiers`]
+pub mod modifier {
+    pub const LEFT_CTRL: u8 = 1 << 0;
+    pub const LEFT_SHIFT: u8 = 1 << 1;
+    pub const LEFT_ALT: u8 = 1 << 2;
+    pub const LEFT_GUI: u8 = 1 << 3;
+    pub const RIGHT_CTRL: u8 = 1 << 4;
+    pub const RIGHT_SHIFT: u8 = 1 << 5;
+    pub const RIGHT_ALT: u8 = 1 << 6;
+    pub const RIGHT_GUI: u8 = 1 << 7;
+}
+
+/// The keys that are pressed
+///
+/// `keys` are the usage IDs of up to six pressed keys, from the HID usage
+/// tables' keyboard page. Unused entries are zero. To release all keys,
+/// send the default report.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeyboardReport {
+    /// A combination of [`modifier`] bits
+    pub modifiers: u8,
+    pub keys: [u8; 6],
+}
+
+impl KeyboardReport {
+    /// Returns the report that types `ch` on a US keyboard
+    ///
+    /// Returns `None` if `ch` isn't on a US keyboard. Send the default
+    /// report after this report to release the key.
+    pub fn ascii(ch: char) -> Option<Self> {
+        const SHIFT: u8 = modifier::LEFT_SHIFT;
+        let (modifiers, key) = match ch {
+            'a'..='z' => (0, 0x04 + (ch as u8 - b'a')),
+            'A'..='Z' => (SHIFT, 0x04 + (ch as u8 - b'A')),
+            '1'..='9' => (0, 0x1E + (ch as u8 - b'1')),
+            '0' => (0, 0x27),
+            '!' => (SHIFT, 0x1E),
+            '@' => (SHIFT, 0x1F),
+            '#' => (SHIFT, 0x20),
+            '$' => (SHIFT, 0x21),
+            '%' => (SHIFT, 0x22),
+            '^' => (SHIFT, 0x23),
+            '&' => (SHIFT, 0x24),
+            '*' => (SHIFT, 0x25),
+            '(' => (SHIFT, 0x26),
+            ')' => (SHIFT, 0x27),
+            '\n' => (0, 0x28),
+            '\x08' => (0, 0x2A),
+            '\t' => (0, 0x2B),
+            ' ' => (0, 0x2C),
+            '-' => (0, 0x2D),
+            '_' => (SHIFT, 0x2D),
+            '=' => (0, 0x2E),
+            '+' => (SHIFT, 0x2E),
+            '[' => (0, 0x2F),
+            '{' => (SHIFT, 0x2F),
+            ']' => (0, 0x30),
+            '}' => (SHIFT, 0x30),
+            '\\' => (0, 0x31),
+            '|' => (SHIFT, 0x31),
+            ';' => (0, 0x33),
+            ':' => (SHIFT, 0x33),
+            '\'' => (0, 0x34),
+            '"' => (SHIFT, 0x34),
+            '`' => (0, 0x35),
+            '~' => (SHIFT, 0x35),
+            ',' => (0, 0x36),
+            '<' => (SHIFT, 0x36),
+            '.' => (0, 0x37),
+            '>' => (SHIFT, 0x37),
+            '/' => (0, 0x38),
+            '?' => (SHIFT, 0x38),
+            _ => return None,
+        };
+        Some(KeyboardReport {
+            modifiers,
+            keys: [key, 0, 0, 0, 0, 0],
+        })
+    }
+
+    fn to_bytes(self) -> [u8; 8] {
+        let k = self.keys;
+        [self.modifiers, 0, k[0], k[1], k[2], k[3], k[4], k[5]]
+    }
+}
+
+/// The keyboard LEDs, as set by the host
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Leds(u8);
+
+impl Leds {
+    pub fn num_lock(self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+    pub fn caps_lock(self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+    pub fn scroll_lock(self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+}
+
+/// Mouse buttons, and movement since the last report
+///
+/// `i8::MIN` is treated as `-i8::MAX` in all movements.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MouseReport {
+    /// Buttons 1 through 5 are bits 0 through 4; button 1 is the left button
+    pub buttons: u8,
+    /// Movement to the right
+    pub x: i8,
+    /// Movement down
+    pub y: i8,
+    /// Scroll up
+    pub wheel: i8,
+}
+
+impl MouseReport {
+    fn to_bytes(self) -> [u8; 4] {
+        [
+            self.buttons & 0x1F,
+            self.x.max(-i8::MAX) as u8,
+            self.y.max(-i8::MAX) as u8,
+            self.wheel.max(-i8::MAX) as u8,
+        ]
+    }
+}
+
+/// The state of a joystick
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct JoystickReport {
+    /// Buttons 1 through 32 are bits 0 through 31
+    pub buttons: u32,
+    /// The hat switch direction, or `None` if it's centered
+    ///
+    /// Zero is up, and each step turns 45 degrees clockwise, up to 7.
+    pub hat: Option<u8>,
+    /// The X, Y, Z, Rx, Ry, and Rz axes
+    ///
+    /// `i16::MIN` is treated as `-i16::MAX`.
+    pub axes: [i16; 6],
+}
+
+impl JoystickReport {
+    fn to_bytes(self) -> [u8; 17] {
+        let mut bytes = [0; 17];
+        bytes[..4].copy_from_slice(&self.buttons.to_le_bytes());
+        bytes[4] = match self.hat {
+            Some(hat) if hat < 8 => hat,
+            _ => 0x0F,
+        };
+        for (axis, bytes) in self.axes.iter().zip(bytes[5..].chunks_exact_mut(2)) {
+            bytes.copy_from_slice(&axis.max(&-i16::MAX).to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Sends keyboard reports
+pub struct Keyboard(core::marker::PhantomData<*const ()>);
+
+// Safety: OK to move across execution contexts; never
+// safe to share across those contexts.
+unsafe impl Send for Keyboard {}
+
+impl Keyboard {
+    /// Send the keys that are pressed
+    ///
+    /// Returns `false` if the host hasn't received the previous report.
+    /// Try again later.
+    pub fn send(&self, report: &KeyboardReport) -> Result<bool, Error> {
+        with_stack(|stack| {
+            stack
+                .classes
+                .hid()
+                .map(|hid| hid.send(Kind::Keyboard, &report.to_bytes()))
+        })
+    }
+
+    /// Returns the LEDs set by the host
+    pub fn leds(&self) -> Leds {
+        with_stack(|stack| stack.classes.hid().map(|hid| Leds(hid.leds))).unwrap_or_default()
+    }
+}
+
+/// Sends mouse reports
+pub struct Mouse(core::marker::PhantomData<*const ()>);
+
+// Safety: see Keyboard.
+unsafe impl Send for Mouse {}
+
+impl Mouse {
+    /// Send button states, and movement
+    ///
+    /// Returns `false` if the host hasn't received the previous report.
+    /// Try again later.
+    pub fn send(&self, report: &MouseReport) -> Result<bool, Error> {
+        with_stack(|stack| {
+            stack
+                .classes
+                .hid()
+                .map(|hid| hid.send(Kind::Mouse, &report.to_bytes()))
+        })
+    }
+}
+
+/// Sends joystick reports
+pub struct Joystick(core::marker::PhantomData<*const ()>);
+
+// Safety: see Keyboard.
+unsafe impl Send for Joystick {}
+
+impl Joystick {
+    /// Send the joystick state
+    ///
+    /// Returns `false` if the host hasn't received the previous report.
+    /// Try again later.
+    pub fn send(&self, report: &JoystickReport) -> Result<bool, Error> {
+        with_stack(|stack| {
+            stack
+                .classes
+                .hid()
+                .map(|hid| hid.send(Kind::Joystick, &report.to_bytes()))
+        })
+    }
+}
+
+/// Sends and receives raw HID packets
+pub struct RawHid(core::marker::PhantomData<*const ()>);
+
+// Safety: see Keyboard.
+unsafe impl Send for RawHid {}
+
+impl RawHid {
+    /// Send a packet to the host
+    ///
+    /// Returns `false` if the host hasn't received the previous packet.
+    /// Try again later.
+    pub fn write(&self, packet: &[u8; RAW_PACKET_SIZE]) -> Result<bool, Error> {
+        with_stack(|stack| {
+            stack
+                .classes
+                .hid()
+                .map(|hid| hid.send(Kind::RawHid, packet))
+        })
+    }
+
+    /// Receive a packet from the host
+    ///
+    /// Returns `false` if there's no packet.
+    pub fn read(&self, packet: &mut [u8; RAW_PACKET_SIZE]) -> Result<bool, Error> {
+        with_stack(|stack| stack.classes.hid().map(|hid| hid.read_raw(packet)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Keyboard,
+    Mouse,
+    Joystick,
+    RawHid,
+}
+
+impl Kind {
+    fn report_descriptor(self) -> &'static [u8] {
+        match self {
+            Kind::Keyboard => KEYBOARD_REPORT_DESCRIPTOR,
+            Kind::Mouse => MOUSE_REPORT_DESCRIPTOR,
+            Kind::Joystick => JOYSTICK_REPORT_DESCRIPTOR,
+            Kind::RawHid => RAW_HID_REPORT_DESCRIPTOR,
+        }
+    }
+
+    /// Returns the interface subclass and protocol
+    fn subclass_protocol(self) -> (u8, u8) {
+        match self {
+            Kind::Keyboard => (HID_SUBCLASS_BOOT, HID_PROTOCOL_KEYBOARD),
+            Kind::Mouse => (HID_SUBCLASS_BOOT, HID_PROTOCOL_MOUSE),
+            Kind::Joystick | Kind::RawHid => (HID_SUBCLASS_NONE, HID_PROTOCOL_NONE),
+        }
+    }
+
+    fn max_packet_size(self) -> u16 {
+        match self {
+            Kind::Keyboard | Kind::Mouse => 8,
+            Kind::Joystick => 32,
+            Kind::RawHid => RAW_PACKET_SIZE as u16,
+        }
+    }
+}
+
+/// One HID interface
+struct Interface<'a> {
+    kind: Kind,
+    number: InterfaceNumber,
+    write_ep: EndpointIn<'a, Bus>,
+    /// Only for raw HID
+    read_ep: Option<EndpointOut<'a, Bus>>,
+    /// The most recent report, for GET_REPORT
+    report: [u8; MAX_REPORT_LEN],
+    report_len: usize,
+    idle: u8,
+    protocol: u8,
+}
+
+impl<'a> Interface<'a> {
+    fn new(alloc: &'a UsbBusAllocator<Bus>, kind: Kind) -> Self {
+        Interface {
+            kind,
+            number: alloc.interface(),
+            write_ep: alloc.interrupt(kind.max_packet_size(), 1),
+            read_ep: if kind == Kind::RawHid {
+                Some(alloc.interrupt(kind.max_packet_size(), 1))
+            } else {
+                None
+            },
+            report: [0; MAX_REPORT_LEN],
+            report_len: 0,
+            idle: 0,
+            protocol: 1,
+        }
+    }
+
+    /// The HID descriptor, which describes the report descriptor
+    fn hid_descriptor(&self) -> [u8; 7] {
+        let len = (self.kind.report_descriptor().len() as u16).to_le_bytes();
+        // HID 1.11, not localized, one report descriptor.
+        [0x11, 0x01, 0x00, 0x01, REPORT_DESCRIPTOR, len[0], len[1]]
+    }
+}
+
+/// The HID class, with all selected interfaces
+pub(super) struct Hid<'a> {
+    interfaces: [Option<Interface<'a>>; 4],
+    /// The keyboard LEDs
+    leds: u8,
+    /// A packet from the raw HID OUT endpoint
+    received: Option<[u8; RAW_PACKET_SIZE]>,
+}
+
+impl<'a> Hid<'a> {
+    pub fn new(alloc: &'a UsbBusAllocator<Bus>, config: Config) -> Self {
+        let interface = |selected: bool, kind| {
+            if selected {
+                Some(Interface::new(alloc, kind))
+            } else {
+                None
+            }
+        };
+        Hid {
+            interfaces: [
+                interface(config.keyboard, Kind::Keyboard),
+                interface(config.mouse, Kind::Mouse),
+                interface(config.joystick, Kind::Joystick),
+                interface(config.raw_hid, Kind::RawHid),
+            ],
+            leds: 0,
+            received: None,
+        }
+    }
+
+    fn interface(&mut self, kind: Kind) -> Option<&mut Interface<'a>> {
+        self.interfaces
+            .iter_mut()
+            .flatten()
+            .find(|interface| interface.kind == kind)
+    }
+
+    /// Returns the interface that handles `req`
+    fn recipient(&mut self, req: &Request) -> Option<&mut Interface<'a>> {
+        if req.recipient != Recipient::Interface {
+            return None;
+        }
+        self.interfaces
+            .iter_mut()
+            .flatten()
+            .find(|interface| u8::from(interface.number) as u16 == req.index)
+    }
+
+    /// Send a report, or return `false` if the previous report is still
+    /// waiting for the host
+    fn send(&mut self, kind: Kind, report: &[u8]) -> bool {
+        let interface = match self.interface(kind) {
+            Some(interface) => interface,
+            None => return false,
+        };
+        if interface.write_ep.write(report).is_err() {
+            return false;
+        }
+        interface.report[..report.len()].copy_from_slice(report);
+        interface.report_len = report.len();
+        true
+    }
+
+    fn read_raw(&mut self, packet: &mut [u8; RAW_PACKET_SIZE]) -> bool {
+        match self.received.take() {
+            Some(received) => {
+                *packet = received;
+                self.pull_raw();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move a packet from the raw HID endpoint, if there's space
+    fn pull_raw(&mut self) {
+        if self.received.is_some() {
+            return;
+        }
+        let read_ep = match self.interface(Kind::RawHid) {
+            Some(Interface {
+                read_ep: Some(read_ep),
+                ..
+            }) => read_ep,
+            _ => return,
+        };
+        let mut packet = [0; RAW_PACKET_SIZE];
+        if read_ep.read(&mut packet).is_ok() {
+            self.received = Some(packet);
+        }
+    }
+}
+
+impl UsbClass<Bus> for Hid<'_> {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        for interface in self.interfaces.iter().flatten() {
+            let (subclass, protocol) = interface.kind.subclass_protocol();
+            writer.interface(interface.number, USB_CLASS_HID, subclass, protocol)?;
+            writer.write(HID_DESCRIPTOR, &interface.hid_descriptor())?;
+            writer.endpoint(&interface.write_ep)?;
+            if let Some(read_ep) = &interface.read_ep {
+                writer.endpoint(read_ep)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        for interface in self.interfaces.iter_mut().flatten() {
+            interface.report_len = 0;
+            interface.idle = 0;
+            interface.protocol = 1;
+        }
+        self.leds = 0;
+        self.received = None;
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<Bus>) {
+        let req = *xfer.request();
+        if req.request_type != RequestType::Class {
+            return;
+        }
+        let interface = match self.recipient(&req) {
+            Some(interface) => interface,
+            None => return,
+        };
+
+        match req.request {
+            SET_IDLE => {
+                interface.idle = (req.value >> 8) as u8;
+                xfer.accept().ok();
+            }
+            SET_PROTOCOL => {
+                interface.protocol = req.value as u8;
+                xfer.accept().ok();
+            }
+            SET_REPORT if interface.kind == Kind::Keyboard && !xfer.data().is_empty() => {
+                self.leds = xfer.data()[0];
+                xfer.accept().ok();
+            }
+            _ => {
+                xfer.reject().ok();
+            }
+        }
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<Bus>) {
+        let req = *xfer.request();
+        let interface = match self.recipient(&req) {
+            Some(interface) => interface,
+            None => return,
+        };
+
+        match (req.request_type, req.request) {
+            (RequestType::Standard, Request::GET_DESCRIPTOR) => match (req.value >> 8) as u8 {
+                REPORT_DESCRIPTOR => {
+                    xfer.accept_with_static(interface.kind.report_descriptor())
+                        .ok();
+                }
+                HID_DESCRIPTOR => {
+                    xfer.accept_with(&interface.hid_descriptor()).ok();
+                }
+                _ => {
+                    xfer.reject().ok();
+                }
+            },
+            (RequestType::Class, GET_REPORT) => {
+                let len = interface.report_len;
+                xfer.accept_with(&interface.report[..len]).ok();
+            }
+            (RequestType::Class, GET_IDLE) => {
+                xfer.accept_with(&[interface.idle]).ok();
+            }
+            (RequestType::Class, GET_PROTOCOL) => {
+                xfer.accept_with(&[interface.protocol]).ok();
+            }
+            (RequestType::Class, _) => {
+                xfer.reject().ok();
+            }
+            _ => {}
+        }
+    }
+
+    fn endpoint_out(&mut self, addr: EndpointAddress) {
+        let raw = self
+            .interface(Kind::RawHid)
+            .and_then(|interface| interface.read_ep.as_ref())
+            .map(|read_ep| read_ep.address());
+        if raw == Some(addr) {
+            self.pull_raw();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{modifier, JoystickReport, KeyboardReport, MouseReport};
+
+    #[test]
+    fn ascii_keys() {
+        let report = KeyboardReport::ascii('a').unwrap();
+        assert_eq!(report.to_bytes(), [0, 0, 0x04, 0, 0, 0, 0, 0]);
+        let report = KeyboardReport::ascii('Z').unwrap();
+        assert_eq!(report.modifiers, modifier::LEFT_SHIFT);
+        assert_eq!(report.keys[0], 0x1D);
+        assert_eq!(KeyboardReport::ascii('0').unwrap().keys[0], 0x27);
+        assert_eq!(KeyboardReport::ascii('1').unwrap().keys[0], 0x1E);
+        let report = KeyboardReport::ascii('?').unwrap();
+        assert_eq!(
+            (report.modifiers, report.keys[0]),
+            (modifier::LEFT_SHIFT, 0x38)
+        );
+        assert_eq!(KeyboardReport::ascii('é'), None);
+    }
+
+    #[test]
+    fn mouse_report() {
+        let report = MouseReport {
+            buttons: 0xFF,
+            x: -1,
+            y: 2,
+            wheel: -128,
+        };
+        assert_eq!(report.to_bytes(), [0x1F, 0xFF, 0x02, 0x81]);
+    }
+
+    #[test]
+    fn joystick_report() {
+        let report = JoystickReport {
+            buttons: 0x8000_0001,
+            hat: None,
+            axes: [0, 1, -1, i16::MAX, i16::MIN, 0x1234],
+        };
+        assert_eq!(
+            report.to_bytes(),
+            [
+                0x01, 0x00, 0x00, 0x80, 0x0F, 0x00, 0x00, 0x01, 0x00, 0xFF, 0xFF, 0xFF, 0x7F, 0x01,
+                0x80, 0x34, 0x12
+            ]
+        );
+        let report = JoystickReport {
+            hat: Some(6),
+            ..Default::default()
+        };
+        assert_eq!(report.to_bytes()[4], 6);
+    }
+}
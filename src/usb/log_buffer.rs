@@ -0,0 +1,225 @@
+//! A ring buffer of log records
+//!
+//! The logger saves records in this buffer while the host isn't listening.
+//! Each record is a two byte, little-endian length, followed by the record's
+//! text. Records are never split; when a record doesn't fit, the buffer drops
+//! a whole record, as selected by the [`Overflow`] policy.
+
+use core::fmt;
+
+/// Size of the length that precedes each record
+const HEADER_LEN: usize = 2;
+
+/// Selects which records to drop when the log buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Drop the oldest records to make room for the new record
+    DropOldest,
+    /// Drop the new record, keeping the oldest records
+    DropNewest,
+}
+
+pub struct LogBuffer {
+    storage: &'static mut [u8],
+    /// Index of the oldest record's header
+    head: usize,
+    /// Number of bytes used by complete records
+    len: usize,
+    /// Number of bytes in the record that's being written
+    pending: usize,
+    /// Set if the record that's being written doesn't fit
+    truncated: bool,
+    overflow: Overflow,
+    dropped: usize,
+}
+
+impl LogBuffer {
+    pub fn new(storage: &'static mut [u8], overflow: Overflow) -> Self {
+        LogBuffer {
+            storage,
+            head: 0,
+            len: 0,
+            pending: 0,
+            truncated: false,
+            overflow,
+            dropped: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of records that were dropped
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Format, then save, a record
+    pub fn push(&mut self, args: fmt::Arguments) {
+        self.pending = 0;
+        self.truncated = false;
+        let result = fmt::write(self, format_args!("\0\0{}", args));
+        if result.is_err() || self.truncated || self.pending - HEADER_LEN > u16::MAX as usize {
+            self.pending = 0;
+            self.dropped += 1;
+            return;
+        }
+
+        let record_len = self.pending - HEADER_LEN;
+        let header = (record_len as u16).to_le_bytes();
+        let start = self.head + self.len;
+        for (offset, byte) in header.iter().enumerate() {
+            let idx = self.index(start + offset);
+            self.storage[idx] = *byte;
+        }
+        self.len += self.pending;
+        self.pending = 0;
+    }
+
+    /// Returns the oldest record
+    ///
+    /// If the record wraps around the end of the buffer, the record
+    /// is split across the two slices. Otherwise, the second slice
+    /// is empty.
+    pub fn front(&self) -> Option<(&[u8], &[u8])> {
+        if self.is_empty() {
+            return None;
+        }
+        let record_len = self.record_len();
+        let start = self.index(self.head + HEADER_LEN);
+        let end = start + record_len;
+        if end <= self.storage.len() {
+            Some((&self.storage[start..end], &[]))
+        } else {
+            let (wrapped, first) = self.storage.split_at(start);
+            Some((first, &wrapped[..end - self.storage.len()]))
+        }
+    }
+
+    /// Remove the oldest record
+    pub fn pop_front(&mut self) {
+        if !self.is_empty() {
+            let size = HEADER_LEN + self.record_len();
+            self.head = self.index(self.head + size);
+            self.len -= size;
+        }
+    }
+
+    /// Remove the oldest record, and count it as dropped
+    pub fn drop_front(&mut self) {
+        self.pop_front();
+        self.dropped += 1;
+    }
+
+    fn index(&self, idx: usize) -> usize {
+        idx % self.storage.len()
+    }
+
+    fn record_len(&self) -> usize {
+        let lo = self.storage[self.head];
+        let hi = self.storage[self.index(self.head + 1)];
+        u16::from_le_bytes([lo, hi]) as usize
+    }
+}
+
+impl fmt::Write for LogBuffer {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        if self.truncated {
+            return Ok(());
+        }
+        for byte in string.bytes() {
+            while self.len + self.pending >= self.storage.len() {
+                if self.overflow == Overflow::DropOldest && !self.is_empty() {
+                    self.drop_front();
+                } else {
+                    self.truncated = true;
+                    return Ok(());
+                }
+            }
+            let idx = self.index(self.head + self.len + self.pending);
+            self.storage[idx] = byte;
+            self.pending += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::{LogBuffer, Overflow};
+    use std::{boxed::Box, vec::Vec};
+
+    fn buffer(size: usize, overflow: Overflow) -> LogBuffer {
+        LogBuffer::new(Box::leak(std::vec![0; size].into_boxed_slice()), overflow)
+    }
+
+    fn pop(buffer: &mut LogBuffer) -> Option<Vec<u8>> {
+        let (first, second) = buffer.front()?;
+        let record = [first, second].concat();
+        buffer.pop_front();
+        Some(record)
+    }
+
+    #[test]
+    fn in_order() {
+        let mut buffer = buffer(64, Overflow::DropNewest);
+        assert!(buffer.is_empty());
+        buffer.push(format_args!("hello {}", 1));
+        buffer.push(format_args!("world {}", 2));
+        assert_eq!(pop(&mut buffer).unwrap(), b"hello 1");
+        assert_eq!(pop(&mut buffer).unwrap(), b"world 2");
+        assert!(pop(&mut buffer).is_none());
+        assert_eq!(buffer.dropped(), 0);
+    }
+
+    #[test]
+    fn drop_newest() {
+        // Room for two records of 8 bytes, each with a 2 byte header.
+        let mut buffer = buffer(20, Overflow::DropNewest);
+        buffer.push(format_args!("record 1"));
+        buffer.push(format_args!("record 2"));
+        buffer.push(format_args!("record 3"));
+        assert_eq!(buffer.dropped(), 1);
+        assert_eq!(pop(&mut buffer).unwrap(), b"record 1");
+        assert_eq!(pop(&mut buffer).unwrap(), b"record 2");
+        assert!(pop(&mut buffer).is_none());
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let mut buffer = buffer(20, Overflow::DropOldest);
+        buffer.push(format_args!("record 1"));
+        buffer.push(format_args!("record 2"));
+        buffer.push(format_args!("record 3"));
+        assert_eq!(buffer.dropped(), 1);
+        assert_eq!(pop(&mut buffer).unwrap(), b"record 2");
+        assert_eq!(pop(&mut buffer).unwrap(), b"record 3");
+        assert!(pop(&mut buffer).is_none());
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut buffer = buffer(24, Overflow::DropOldest);
+        for idx in 0..10 {
+            buffer.push(format_args!("record {}", idx));
+        }
+        assert_eq!(buffer.dropped(), 8);
+        assert_eq!(pop(&mut buffer).unwrap(), b"record 8");
+        assert_eq!(pop(&mut buffer).unwrap(), b"record 9");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn too_large() {
+        let mut buffer = buffer(8, Overflow::DropOldest);
+        buffer.push(format_args!("abc"));
+        buffer.push(format_args!("this record never fits"));
+        assert_eq!(buffer.dropped(), 2);
+        assert!(buffer.is_empty());
+
+        buffer.push(format_args!("abc"));
+        assert_eq!(pop(&mut buffer).unwrap(), b"abc");
+    }
+}
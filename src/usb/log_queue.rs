@@ -0,0 +1,271 @@
+//! A lock-free queue of log records
+//!
+//! Any number of producers, in any execution context, format records into the
+//! queue without disabling interrupts. A producer claims a slot with a
+//! compare-and-swap, formats the record into the slot, then publishes the slot.
+//! Records longer than the slot are truncated. When the queue is full, the
+//! producer drops its record.
+//!
+//! The USB stack consumes the records. Records leave the queue in the order
+//! that producers claimed their slots. A slot that's claimed, but not yet
+//! published, blocks the consumer until its producer finishes.
+//!
+//! This is Dmitry Vyukov's bounded queue. Each slot has a sequence number that
+//! says whose turn it is to use the slot. Since we can't assign a different
+//! sequence number to each slot in a `const fn`, slots store their sequence
+//! number relative to their index.
+
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+struct Slot<const SIZE: usize> {
+    /// The sequence number, minus the slot's index
+    sequence: AtomicUsize,
+    len: UnsafeCell<usize>,
+    data: UnsafeCell<[u8; SIZE]>,
+}
+
+impl<const SIZE: usize> Slot<SIZE> {
+    // Only used to initialize the array of slots.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: Self = Slot {
+        sequence: AtomicUsize::new(0),
+        len: UnsafeCell::new(0),
+        data: UnsafeCell::new([0; SIZE]),
+    };
+}
+
+/// A queue of `N` records, each up to `SIZE` bytes
+///
+/// `N` must be a power of two.
+pub struct LogQueue<const N: usize, const SIZE: usize> {
+    slots: [Slot<SIZE>; N],
+    /// The position of the next slot to claim
+    enqueue: AtomicUsize,
+    /// The position of the next slot to consume
+    dequeue: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+// Safety: a slot's data is only accessed by the producer that claimed
+// the slot, or by the consumer once the slot is published. The sequence
+// number synchronizes the two.
+unsafe impl<const N: usize, const SIZE: usize> Sync for LogQueue<N, SIZE> {}
+
+impl<const N: usize, const SIZE: usize> LogQueue<N, SIZE> {
+    pub const fn new() -> Self {
+        LogQueue {
+            slots: [Slot::EMPTY; N],
+            enqueue: AtomicUsize::new(0),
+            dequeue: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of records that were dropped
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn sequence(&self, index: usize) -> usize {
+        self.slots[index]
+            .sequence
+            .load(Ordering::Acquire)
+            .wrapping_add(index)
+    }
+
+    fn set_sequence(&self, index: usize, sequence: usize) {
+        self.slots[index]
+            .sequence
+            .store(sequence.wrapping_sub(index), Ordering::Release);
+    }
+
+    /// Format, then enqueue, a record
+    ///
+    /// Returns `false` if the queue is full, and the record was dropped.
+    pub fn push(&self, args: fmt::Arguments) -> bool {
+        let mut pos = self.enqueue.load(Ordering::Relaxed);
+        let index = loop {
+            let index = pos % N;
+            let diff = self.sequence(index).wrapping_sub(pos) as isize;
+            if diff == 0 {
+                match self.enqueue.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break index,
+                    Err(actual) => pos = actual,
+                }
+            } else if diff < 0 {
+                // The consumer hasn't released this slot.
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return false;
+            } else {
+                // Another producer claimed this slot.
+                pos = self.enqueue.load(Ordering::Relaxed);
+            }
+        };
+
+        let slot = &self.slots[index];
+        // Safety: we claimed this slot, so we have exclusive access until we
+        // publish it.
+        let mut writer = SlotWriter {
+            data: unsafe { &mut *slot.data.get() },
+            len: 0,
+        };
+        // Truncation isn't an error, and we have nowhere to report any
+        // other formatting error.
+        fmt::write(&mut writer, args).ok();
+        unsafe { *slot.len.get() = writer.len };
+        self.set_sequence(index, pos.wrapping_add(1));
+        true
+    }
+
+    /// Returns the oldest published record
+    ///
+    /// # Safety
+    ///
+    /// The caller must be the only consumer until it calls `pop_front`, or drops
+    /// the returned slice.
+    pub unsafe fn front(&self) -> Option<&[u8]> {
+        let pos = self.dequeue.load(Ordering::Relaxed);
+        let index = pos % N;
+        if self.sequence(index) != pos.wrapping_add(1) {
+            return None;
+        }
+        let slot = &self.slots[index];
+        let len = *slot.len.get();
+        let data: &[u8; SIZE] = &*slot.data.get();
+        Some(&data[..len])
+    }
+
+    /// Release the oldest published record, so that producers can reuse its slot
+    ///
+    /// # Safety
+    ///
+    /// The caller must be the only consumer, and there must be no slices returned
+    /// by `front`.
+    pub unsafe fn pop_front(&self) {
+        let pos = self.dequeue.load(Ordering::Relaxed);
+        let index = pos % N;
+        if self.sequence(index) == pos.wrapping_add(1) {
+            self.dequeue.store(pos.wrapping_add(1), Ordering::Relaxed);
+            self.set_sequence(index, pos.wrapping_add(N));
+        }
+    }
+}
+
+/// Formats a record into a slot, truncating at a character boundary
+struct SlotWriter<'a> {
+    data: &'a mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for SlotWriter<'_> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        let free = self.data.len() - self.len;
+        let mut count = string.len().min(free);
+        while !string.is_char_boundary(count) {
+            count -= 1;
+        }
+        self.data[self.len..self.len + count].copy_from_slice(&string.as_bytes()[..count]);
+        self.len += count;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::LogQueue;
+    use std::{sync::Arc, thread, vec::Vec};
+
+    fn pop<const N: usize, const SIZE: usize>(queue: &LogQueue<N, SIZE>) -> Option<Vec<u8>> {
+        unsafe {
+            let record = queue.front()?.to_vec();
+            queue.pop_front();
+            Some(record)
+        }
+    }
+
+    #[test]
+    fn in_order() {
+        let queue = LogQueue::<4, 16>::new();
+        assert!(queue.push(format_args!("hello {}", 1)));
+        assert!(queue.push(format_args!("world {}", 2)));
+        assert_eq!(pop(&queue).unwrap(), b"hello 1");
+        assert_eq!(pop(&queue).unwrap(), b"world 2");
+        assert!(pop(&queue).is_none());
+    }
+
+    #[test]
+    fn full() {
+        let queue = LogQueue::<2, 16>::new();
+        assert!(queue.push(format_args!("1")));
+        assert!(queue.push(format_args!("2")));
+        assert!(!queue.push(format_args!("3")));
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(pop(&queue).unwrap(), b"1");
+        assert!(queue.push(format_args!("4")));
+        assert_eq!(pop(&queue).unwrap(), b"2");
+        assert_eq!(pop(&queue).unwrap(), b"4");
+    }
+
+    #[test]
+    fn truncate() {
+        let queue = LogQueue::<2, 8>::new();
+        queue.push(format_args!("{}", "truncated"));
+        assert_eq!(pop(&queue).unwrap(), b"truncate");
+        // Don't split a multi-byte character.
+        queue.push(format_args!("abcdefg{}", "é"));
+        assert_eq!(pop(&queue).unwrap(), b"abcdefg");
+    }
+
+    #[test]
+    fn wrap_around() {
+        let queue = LogQueue::<4, 8>::new();
+        for idx in 0..100 {
+            assert!(queue.push(format_args!("{}", idx)));
+            assert_eq!(pop(&queue).unwrap(), std::format!("{}", idx).as_bytes());
+        }
+    }
+
+    #[test]
+    fn producers() {
+        const PRODUCERS: usize = 4;
+        const RECORDS: usize = 1000;
+        let queue = Arc::new(LogQueue::<8, 16>::new());
+        let threads: Vec<_> = (0..PRODUCERS)
+            .map(|producer| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    for record in 0..RECORDS {
+                        while !queue.push(format_args!("{} {}", producer, record)) {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        // Records from each producer arrive in order.
+        let mut next = [0; PRODUCERS];
+        while next.iter().any(|&next| next < RECORDS) {
+            if let Some(record) = pop(&queue) {
+                let record = std::string::String::from_utf8(record).unwrap();
+                let mut fields = record.split(' ').map(|field| field.parse().unwrap());
+                let producer: usize = fields.next().unwrap();
+                assert_eq!(fields.next().unwrap(), next[producer]);
+                next[producer] += 1;
+            }
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+}
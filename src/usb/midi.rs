@@ -0,0 +1,796 @@
+//! USB MIDI devices
+//!
+//! Use [`split_midi`](super::split_midi) to present a USB MIDI interface
+//! instead of a USB serial port. The returned [`Midi`] sends and receives
+//! USB MIDI event [`Packet`]s. Like the serial port, the MIDI interface buffers
+//! packets, and [`poll`](super::poll()) moves them to and from the host.
+//!
+//! [`MidiMessage`] describes channel voice and system real-time messages.
+//! Send a system exclusive (SysEx) message with [`Midi::send_sysex`], which
+//! splits the message into packets. Use a [`SysExReader`] to collect received
+//! SysEx packets into complete messages.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::hal::ral::usb::USB1;
+//! use bsp::usb::midi::{MidiMessage, SysExReader};
+//!
+//! let identity = bsp::usb::UsbIdentity {
+//!     // Teensyduino's product ID for MIDI
+//!     product_id: 0x0485,
+//!     product: "MIDI",
+//!     ..Default::default()
+//! };
+//! let (mut poller, mut midi) = bsp::usb::split_midi(USB1::take().unwrap(), identity).unwrap();
+//! let mut sysex: SysExReader<128> = SysExReader::new();
+//!
+//! loop {
+//!     poller.poll();
+//!     while let Ok(Some(packet)) = midi.read() {
+//!         match packet.message() {
+//!             Some(MidiMessage::NoteOn { channel, note, velocity }) => {
+//!                 // Echo the note on the next channel.
+//!                 let channel = (channel + 1) % 16;
+//!                 midi.send(0, &MidiMessage::NoteOn { channel, note, velocity }).ok();
+//!             }
+//!             Some(_) => {}
+//!             None => {
+//!                 if let Some(data) = sysex.push(packet) {
+//!                     // Handle the SysEx message...
+//!                 }
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! There's no serial port; see [device classes](super#device-classes).
+
+use super::bus::{self, BulkIn, BulkOut, Bus};
+use super::ring::Ring;
+use super::{with_stack, Error};
+use usb_device::{
+    bus::{InterfaceNumber, UsbBusAllocator},
+    class::UsbClass,
+    descriptor::DescriptorWriter,
+    endpoint::EndpointAddress,
+};
+
+const USB_CLASS_AUDIO: u8 = 0x01;
+const AUDIO_SUBCLASS_CONTROL: u8 = 0x01;
+const AUDIO_SUBCLASS_MIDI_STREAMING: u8 = 0x03;
+
+const CS_INTERFACE: u8 = 0x24;
+const CS_ENDPOINT: u8 = 0x25;
+const AC_HEADER: u8 = 0x01;
+const MS_HEADER: u8 = 0x01;
+const MS_GENERAL: u8 = 0x01;
+const MIDI_IN_JACK: u8 = 0x02;
+const MIDI_OUT_JACK: u8 = 0x03;
+const JACK_EMBEDDED: u8 = 0x01;
+const JACK_EXTERNAL: u8 = 0x02;
+
+const EMBEDDED_IN_JACK: u8 = 1;
+const EXTERNAL_IN_JACK: u8 = 2;
+const EMBEDDED_OUT_JACK: u8 = 3;
+const EXTERNAL_OUT_JACK: u8 = 4;
+
+/// The MIDI streaming descriptors' total length: the header, four jacks,
+/// and two audio endpoints with their class-specific descriptors
+const MS_TOTAL_LEN: u16 = 7 + 6 + 6 + 9 + 9 + (9 + 5) * 2;
+
+/// Number of bytes buffered from the host
+///
+/// There's room for two high speed packets, so we can read the next
+/// packet before the user reads every event in the previous packet.
+const RX_LEN: usize = 2 * bus::MAX_BULK_PACKET_LEN;
+/// Number of bytes buffered for the host
+const TX_LEN: usize = 1024;
+
+/// A MIDI channel voice, or system real-time, message
+///
+/// Channels are zero through 15. Notes, velocities, and other data bytes are
+/// seven bits; encoding ignores the high bit. A `NoteOn` with zero velocity
+/// stays a `NoteOn`; many devices treat it as a `NoteOff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+    NoteOff {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    PolyPressure {
+        channel: u8,
+        note: u8,
+        pressure: u8,
+    },
+    ControlChange {
+        channel: u8,
+        control: u8,
+        value: u8,
+    },
+    ProgramChange {
+        channel: u8,
+        program: u8,
+    },
+    ChannelPressure {
+        channel: u8,
+        pressure: u8,
+    },
+    /// `value` is 14 bits; 8192 is centered
+    PitchBend {
+        channel: u8,
+        value: u16,
+    },
+    TimingClock,
+    Start,
+    Continue,
+    Stop,
+    ActiveSensing,
+    SystemReset,
+}
+
+impl MidiMessage {
+    /// Encode the MIDI message, returning the bytes and the number of bytes
+    pub fn to_bytes(&self) -> ([u8; 3], usize) {
+        use MidiMessage::*;
+        let status = |kind: u8, channel: u8| kind | (channel & 0x0F);
+        match *self {
+            NoteOff {
+                channel,
+                note,
+                velocity,
+            } => ([status(0x80, channel), note & 0x7F, velocity & 0x7F], 3),
+            NoteOn {
+                channel,
+                note,
+                velocity,
+            } => ([status(0x90, channel), note & 0x7F, velocity & 0x7F], 3),
+            PolyPressure {
+                channel,
+                note,
+                pressure,
+            } => ([status(0xA0, channel), note & 0x7F, pressure & 0x7F], 3),
+            ControlChange {
+                channel,
+                control,
+                value,
+            } => ([status(0xB0, channel), control & 0x7F, value & 0x7F], 3),
+            ProgramChange { channel, program } => ([status(0xC0, channel), program & 0x7F, 0], 2),
+            ChannelPressure { channel, pressure } => {
+                ([status(0xD0, channel), pressure & 0x7F, 0], 2)
+            }
+            PitchBend { channel, value } => (
+                [
+                    status(0xE0, channel),
+                    value as u8 & 0x7F,
+                    (value >> 7) as u8 & 0x7F,
+                ],
+                3,
+            ),
+            TimingClock => ([0xF8, 0, 0], 1),
+            Start => ([0xFA, 0, 0], 1),
+            Continue => ([0xFB, 0, 0], 1),
+            Stop => ([0xFC, 0, 0], 1),
+            ActiveSensing => ([0xFE, 0, 0], 1),
+            SystemReset => ([0xFF, 0, 0], 1),
+        }
+    }
+
+    /// Decode a MIDI message that starts with its status byte
+    ///
+    /// Returns `None` if `bytes` doesn't start with a supported message, or
+    /// if it's missing data bytes. Ignores any bytes after the message.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        use MidiMessage::*;
+        let (&status, data) = bytes.split_first()?;
+        let channel = status & 0x0F;
+        let data_len = match status {
+            0x80..=0xBF | 0xE0..=0xEF => 2,
+            0xC0..=0xDF => 1,
+            _ => 0,
+        };
+        let data = data.get(..data_len)?;
+        if data.iter().any(|&byte| byte & 0x80 != 0) {
+            return None;
+        }
+        let message = match status & 0xF0 {
+            0x80 => NoteOff {
+                channel,
+                note: data[0],
+                velocity: data[1],
+            },
+            0x90 => NoteOn {
+                channel,
+                note: data[0],
+                velocity: data[1],
+            },
+            0xA0 => PolyPressure {
+                channel,
+                note: data[0],
+                pressure: data[1],
+            },
+            0xB0 => ControlChange {
+                channel,
+                control: data[0],
+                value: data[1],
+            },
+            0xC0 => ProgramChange {
+                channel,
+                program: data[0],
+            },
+            0xD0 => ChannelPressure {
+                channel,
+                pressure: data[0],
+            },
+            0xE0 => PitchBend {
+                channel,
+                value: data[0] as u16 | (data[1] as u16) << 7,
+            },
+            _ => match status {
+                0xF8 => TimingClock,
+                0xFA => Start,
+                0xFB => Continue,
+                0xFC => Stop,
+                0xFE => ActiveSensing,
+                0xFF => SystemReset,
+                _ => return None,
+            },
+        };
+        Some(message)
+    }
+}
+
+/// Code index numbers, which describe a packet's contents
+mod cin {
+    pub const SYSEX: u8 = 0x4;
+    pub const SYSEX_END_1: u8 = 0x5;
+    pub const SYSEX_END_2: u8 = 0x6;
+    pub const SYSEX_END_3: u8 = 0x7;
+    pub const SINGLE_BYTE: u8 = 0xF;
+}
+
+/// A USB MIDI event packet
+///
+/// A packet has a cable number, and up to three bytes of a MIDI message.
+/// Cable numbers are zero through 15; this device only has cable zero, but
+/// the host may use other cables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet(pub [u8; 4]);
+
+impl Packet {
+    /// Create the packet for a message on `cable`
+    pub fn from_message(cable: u8, message: &MidiMessage) -> Self {
+        let (bytes, len) = message.to_bytes();
+        let cin = if len == 1 {
+            cin::SINGLE_BYTE
+        } else {
+            bytes[0] >> 4
+        };
+        Packet([cable << 4 | cin, bytes[0], bytes[1], bytes[2]])
+    }
+
+    pub fn cable(&self) -> u8 {
+        self.0[0] >> 4
+    }
+
+    /// Returns the code index number
+    pub fn cin(&self) -> u8 {
+        self.0[0] & 0x0F
+    }
+
+    /// Decode the packet's message
+    ///
+    /// Returns `None` for SysEx packets, and for messages that [`MidiMessage`]
+    /// doesn't support.
+    pub fn message(&self) -> Option<MidiMessage> {
+        match self.cin() {
+            0x8..=0xE => MidiMessage::from_bytes(&self.0[1..]),
+            cin::SINGLE_BYTE => MidiMessage::from_bytes(&self.0[1..2]),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the packets for a SysEx message
+///
+/// `data` excludes the start (`0xF0`) and end (`0xF7`) bytes; the packets
+/// include them. Data bytes are seven bits; encoding ignores the high bit.
+pub fn sysex_packets(cable: u8, data: &[u8]) -> SysExPackets<'_> {
+    SysExPackets {
+        cable,
+        data,
+        position: 0,
+    }
+}
+
+/// Returns the number of packets that carry `len` bytes of SysEx data
+pub const fn sysex_packet_count(len: usize) -> usize {
+    (len + 1) / 3 + 1
+}
+
+/// An iterator over the packets of a SysEx message
+///
+/// See [`sysex_packets`].
+pub struct SysExPackets<'a> {
+    cable: u8,
+    data: &'a [u8],
+    /// The position in the message, including the start and end bytes
+    position: usize,
+}
+
+impl SysExPackets<'_> {
+    fn byte(&self, position: usize) -> u8 {
+        if position == 0 {
+            0xF0
+        } else if position == self.data.len() + 1 {
+            0xF7
+        } else {
+            self.data[position - 1] & 0x7F
+        }
+    }
+}
+
+impl Iterator for SysExPackets<'_> {
+    type Item = Packet;
+    fn next(&mut self) -> Option<Packet> {
+        let len = self.data.len() + 2;
+        let remaining = len.checked_sub(self.position).filter(|&rem| rem > 0)?;
+        let count = remaining.min(3);
+        let cin = match remaining {
+            1 => cin::SYSEX_END_1,
+            2 => cin::SYSEX_END_2,
+            3 => cin::SYSEX_END_3,
+            _ => cin::SYSEX,
+        };
+        let mut packet = [self.cable << 4 | cin, 0, 0, 0];
+        for (idx, byte) in packet[1..=count].iter_mut().enumerate() {
+            *byte = self.byte(self.position + idx);
+        }
+        self.position += count;
+        Some(Packet(packet))
+    }
+}
+
+/// Collects SysEx packets into complete messages
+///
+/// The reader holds up to `N` bytes of SysEx data, not including the start and
+/// end bytes. It drops longer messages. It ignores non-SysEx packets, so you can
+/// push all received packets. Use a reader for each cable that sends SysEx.
+pub struct SysExReader<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+    /// Set while collecting a message
+    active: bool,
+    /// Set if the message didn't fit
+    overflow: bool,
+}
+
+impl<const N: usize> SysExReader<N> {
+    pub const fn new() -> Self {
+        SysExReader {
+            buffer: [0; N],
+            len: 0,
+            active: false,
+            overflow: false,
+        }
+    }
+
+    /// Add a packet, and return the SysEx data if the message is complete
+    pub fn push(&mut self, packet: Packet) -> Option<&[u8]> {
+        let count = match packet.cin() {
+            cin::SYSEX => 3,
+            cin::SYSEX_END_1 => 1,
+            cin::SYSEX_END_2 => 2,
+            cin::SYSEX_END_3 => 3,
+            _ => return None,
+        };
+        for &byte in &packet.0[1..=count] {
+            match byte {
+                0xF0 => {
+                    self.active = true;
+                    self.overflow = false;
+                    self.len = 0;
+                }
+                0xF7 if self.active => {
+                    self.active = false;
+                    if !self.overflow {
+                        return Some(&self.buffer[..self.len]);
+                    }
+                }
+                _ if self.active => match self.buffer.get_mut(self.len) {
+                    Some(slot) => {
+                        *slot = byte;
+                        self.len += 1;
+                    }
+                    None => self.overflow = true,
+                },
+                // A single byte system common message, or a packet
+                // without a start.
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+impl<const N: usize> Default for SysExReader<N> {
+    fn default() -> Self {
+        SysExReader::new()
+    }
+}
+
+/// Sends and receives USB MIDI packets
+pub struct Midi(core::marker::PhantomData<*const ()>);
+
+// Safety: OK to move across execution contexts; never
+// safe to share across those contexts.
+unsafe impl Send for Midi {}
+
+impl Midi {
+    pub(super) const fn new() -> Self {
+        Midi(core::marker::PhantomData)
+    }
+
+    /// Send a message on `cable`
+    ///
+    /// Returns `false` if there isn't space for the message. Try again after
+    /// `poll` sends more data.
+    pub fn send(&mut self, cable: u8, message: &MidiMessage) -> Result<bool, Error> {
+        self.write(&[Packet::from_message(cable, message)])
+    }
+
+    /// Send a SysEx message on `cable`
+    ///
+    /// `data` excludes the start and end bytes. The whole message is buffered,
+    /// or nothing is. Returns `false` if there isn't space for the message. Try
+    /// again after `poll` sends more data. Messages with more than 766 bytes of
+    /// data never fit.
+    pub fn send_sysex(&mut self, cable: u8, data: &[u8]) -> Result<bool, Error> {
+        with_stack(|stack| {
+            stack.classes.midi().map(|midi| {
+                if midi.tx.free() < sysex_packet_count(data.len()) * 4 {
+                    return false;
+                }
+                for packet in sysex_packets(cable, data) {
+                    midi.tx.push(&packet.0);
+                }
+                midi.flush();
+                true
+            })
+        })
+    }
+
+    /// Send packets
+    ///
+    /// All packets are buffered, or none are. Returns `false` if there isn't
+    /// space for the packets.
+    pub fn write(&mut self, packets: &[Packet]) -> Result<bool, Error> {
+        with_stack(|stack| {
+            stack.classes.midi().map(|midi| {
+                if midi.tx.free() < packets.len() * 4 {
+                    return false;
+                }
+                for packet in packets {
+                    midi.tx.push(&packet.0);
+                }
+                midi.flush();
+                true
+            })
+        })
+    }
+
+    /// Receive the next packet, or `None` if there are no packets
+    pub fn read(&mut self) -> Result<Option<Packet>, Error> {
+        with_stack(|stack| stack.classes.midi().map(|midi| midi.read()))
+    }
+}
+
+/// The USB MIDI class
+pub(super) struct MidiClass<'a> {
+    control_if: InterfaceNumber,
+    streaming_if: InterfaceNumber,
+    read_ep: BulkOut<'a>,
+    write_ep: BulkIn<'a>,
+    rx: Ring<RX_LEN>,
+    tx: Ring<TX_LEN>,
+    /// Set while there's a transfer on the write endpoint
+    tx_busy: bool,
+}
+
+impl<'a> MidiClass<'a> {
+    pub fn new(alloc: &'a UsbBusAllocator<Bus>) -> Self {
+        MidiClass {
+            control_if: alloc.interface(),
+            streaming_if: alloc.interface(),
+            read_ep: BulkOut::new(alloc),
+            write_ep: BulkIn::new(alloc),
+            rx: Ring::new(),
+            tx: Ring::new(),
+            tx_busy: false,
+        }
+    }
+
+    fn read(&mut self) -> Option<Packet> {
+        if self.rx.len() < 4 {
+            return None;
+        }
+        let mut packet = [0; 4];
+        self.rx.pop(&mut packet);
+        self.pull_rx();
+        Some(Packet(packet))
+    }
+
+    fn flush(&mut self) {
+        if !self.tx_busy {
+            self.push_tx();
+        }
+    }
+
+    /// Move data from the read endpoint into the receive buffer
+    fn pull_rx(&mut self) {
+        if self.rx.free() < bus::bulk_packet_len() {
+            return;
+        }
+        let mut packet = [0; bus::MAX_BULK_PACKET_LEN];
+        if let Ok(count) = self.read_ep.read(&mut packet) {
+            // Drop any partial event packet.
+            self.rx.push(&packet[..count - count % 4]);
+        }
+    }
+
+    /// Schedule a transfer from the transmit buffer
+    fn push_tx(&mut self) {
+        let packet_len = bus::bulk_packet_len();
+        let mut packet = [0; bus::MAX_BULK_PACKET_LEN];
+        let count = self.tx.pop(&mut packet[..packet_len]);
+        if count > 0 && self.write_ep.write(&packet[..count]).is_ok() {
+            self.tx_busy = true;
+        }
+    }
+}
+
+impl UsbClass<Bus> for MidiClass<'_> {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        writer.interface(self.control_if, USB_CLASS_AUDIO, AUDIO_SUBCLASS_CONTROL, 0)?;
+        // Audio 1.0, 9 bytes of class-specific descriptors, and one
+        // streaming interface.
+        writer.write(
+            CS_INTERFACE,
+            &[AC_HEADER, 0x00, 0x01, 9, 0, 1, self.streaming_if.into()],
+        )?;
+
+        writer.interface(
+            self.streaming_if,
+            USB_CLASS_AUDIO,
+            AUDIO_SUBCLASS_MIDI_STREAMING,
+            0,
+        )?;
+        let total = MS_TOTAL_LEN.to_le_bytes();
+        writer.write(CS_INTERFACE, &[MS_HEADER, 0x00, 0x01, total[0], total[1]])?;
+        writer.write(
+            CS_INTERFACE,
+            &[MIDI_IN_JACK, JACK_EMBEDDED, EMBEDDED_IN_JACK, 0],
+        )?;
+        writer.write(
+            CS_INTERFACE,
+            &[MIDI_IN_JACK, JACK_EXTERNAL, EXTERNAL_IN_JACK, 0],
+        )?;
+        writer.write(
+            CS_INTERFACE,
+            &[
+                MIDI_OUT_JACK,
+                JACK_EMBEDDED,
+                EMBEDDED_OUT_JACK,
+                1,
+                EXTERNAL_IN_JACK,
+                1,
+                0,
+            ],
+        )?;
+        writer.write(
+            CS_INTERFACE,
+            &[
+                MIDI_OUT_JACK,
+                JACK_EXTERNAL,
+                EXTERNAL_OUT_JACK,
+                1,
+                EMBEDDED_IN_JACK,
+                1,
+                0,
+            ],
+        )?;
+
+        // Audio endpoints have two more bytes, bRefresh and bSynchAddress.
+        let audio_endpoint = |buf: &mut [u8]| {
+            buf.get_mut(..2)
+                .ok_or(usb_device::UsbError::BufferOverflow)?
+                .fill(0);
+            Ok(2)
+        };
+        self.read_ep.describe_ex(writer, audio_endpoint)?;
+        writer.write(CS_ENDPOINT, &[MS_GENERAL, 1, EMBEDDED_IN_JACK])?;
+        self.write_ep.describe_ex(writer, audio_endpoint)?;
+        writer.write(CS_ENDPOINT, &[MS_GENERAL, 1, EMBEDDED_OUT_JACK])?;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.rx.clear();
+        self.tx.clear();
+        self.tx_busy = false;
+    }
+
+    fn endpoint_out(&mut self, addr: EndpointAddress) {
+        if addr == self.read_ep.address() {
+            self.pull_rx();
+        }
+    }
+
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        if addr == self.write_ep.address() {
+            self.tx_busy = false;
+            self.push_tx();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sysex_packet_count, sysex_packets, MidiMessage, Packet, SysExReader};
+
+    #[test]
+    fn roundtrip_messages() {
+        let messages = [
+            MidiMessage::NoteOff {
+                channel: 0,
+                note: 60,
+                velocity: 64,
+            },
+            MidiMessage::NoteOn {
+                channel: 15,
+                note: 127,
+                velocity: 0,
+            },
+            MidiMessage::PolyPressure {
+                channel: 3,
+                note: 1,
+                pressure: 2,
+            },
+            MidiMessage::ControlChange {
+                channel: 9,
+                control: 7,
+                value: 100,
+            },
+            MidiMessage::ProgramChange {
+                channel: 1,
+                program: 42,
+            },
+            MidiMessage::ChannelPressure {
+                channel: 2,
+                pressure: 99,
+            },
+            MidiMessage::PitchBend {
+                channel: 4,
+                value: 0x3FFF,
+            },
+            MidiMessage::TimingClock,
+            MidiMessage::Start,
+            MidiMessage::Continue,
+            MidiMessage::Stop,
+            MidiMessage::ActiveSensing,
+            MidiMessage::SystemReset,
+        ];
+        for message in messages.iter() {
+            let (bytes, len) = message.to_bytes();
+            assert_eq!(MidiMessage::from_bytes(&bytes[..len]), Some(*message));
+            let packet = Packet::from_message(5, message);
+            assert_eq!(packet.cable(), 5);
+            assert_eq!(packet.message(), Some(*message));
+        }
+    }
+
+    #[test]
+    fn encode_messages() {
+        let note_on = MidiMessage::NoteOn {
+            channel: 1,
+            note: 60,
+            velocity: 127,
+        };
+        assert_eq!(note_on.to_bytes(), ([0x91, 60, 127], 3));
+        assert_eq!(
+            Packet::from_message(0, &note_on),
+            Packet([0x09, 0x91, 60, 127])
+        );
+
+        let bend = MidiMessage::PitchBend {
+            channel: 0,
+            value: 8192,
+        };
+        assert_eq!(bend.to_bytes(), ([0xE0, 0x00, 0x40], 3));
+        assert_eq!(
+            Packet::from_message(1, &MidiMessage::TimingClock),
+            Packet([0x1F, 0xF8, 0, 0])
+        );
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(MidiMessage::from_bytes(&[]), None);
+        // No status byte
+        assert_eq!(MidiMessage::from_bytes(&[0x40, 0x40]), None);
+        // Missing data
+        assert_eq!(MidiMessage::from_bytes(&[0x90, 0x40]), None);
+        // Data byte has the high bit
+        assert_eq!(MidiMessage::from_bytes(&[0xB0, 0x80, 0x00]), None);
+        // Unsupported system message
+        assert_eq!(MidiMessage::from_bytes(&[0xF2, 0x00, 0x00]), None);
+        assert_eq!(Packet([0x04, 0xF0, 0x01, 0x02]).message(), None);
+    }
+
+    #[test]
+    fn sysex_chunks() {
+        let packets: [Packet; 3] = {
+            let mut packets = sysex_packets(2, &[1, 2, 3, 4, 5]);
+            let chunks = [
+                packets.next().unwrap(),
+                packets.next().unwrap(),
+                packets.next().unwrap(),
+            ];
+            assert!(packets.next().is_none());
+            chunks
+        };
+        assert_eq!(packets[0], Packet([0x24, 0xF0, 1, 2]));
+        assert_eq!(packets[1], Packet([0x24, 3, 4, 5]));
+        assert_eq!(packets[2], Packet([0x25, 0xF7, 0, 0]));
+
+        for len in 0..10 {
+            let data = [0x11; 10];
+            let mut count = 0;
+            let mut reader: SysExReader<16> = SysExReader::new();
+            let mut received = None;
+            for packet in sysex_packets(0, &data[..len]) {
+                count += 1;
+                assert!(received.is_none());
+                received = reader.push(packet).map(|data| data.len());
+            }
+            assert_eq!(count, sysex_packet_count(len));
+            assert_eq!(received, Some(len));
+        }
+    }
+
+    #[test]
+    fn sysex_reader() {
+        let mut reader: SysExReader<4> = SysExReader::new();
+        // Ignores messages, and packets without a start.
+        let note = MidiMessage::NoteOn {
+            channel: 0,
+            note: 1,
+            velocity: 1,
+        };
+        assert_eq!(reader.push(Packet::from_message(0, &note)), None);
+        assert_eq!(reader.push(Packet([0x07, 1, 2, 0xF7])), None);
+
+        // Drops long messages.
+        for packet in sysex_packets(0, &[1, 2, 3, 4, 5]) {
+            assert_eq!(reader.push(packet), None);
+        }
+
+        // Real-time messages may interrupt SysEx.
+        let mut packets = sysex_packets(0, &[7, 8, 9]);
+        assert_eq!(reader.push(packets.next().unwrap()), None);
+        assert_eq!(
+            reader.push(Packet::from_message(0, &MidiMessage::TimingClock)),
+            None
+        );
+        assert_eq!(reader.push(packets.next().unwrap()), Some(&[7, 8, 9][..]));
+    }
+}
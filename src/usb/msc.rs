@@ -0,0 +1,946 @@
+//! USB mass storage devices
+//!
+//! Use [`split_msc`](super::split_msc) to present a USB drive instead of a
+//! USB serial port. The drive is any [`BlockDevice`], like an SD card, a
+//! flash region, or a [`RamDisk`]. The host formats, reads, and writes the
+//! blocks; the device only needs to store them.
+//!
+//! The drive uses the bulk-only transport, and the SCSI transparent command
+//! set, like most USB drives. [`poll`](super::poll()) handles the SCSI
+//! commands, and it calls the block device. Since `poll` runs the block
+//! device in a critical section, prefer a fast block device.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::hal::ral::usb::USB1;
+//! use bsp::usb::msc::RamDisk;
+//!
+//! static mut BLOCKS: [u8; 128 * 1024] = [0; 128 * 1024];
+//! // Safety: the only reference to BLOCKS.
+//! let blocks = unsafe { &mut *core::ptr::addr_of_mut!(BLOCKS) };
+//! let disk = cortex_m::singleton!(: RamDisk<'static> = RamDisk::new(blocks, 512)).unwrap();
+//!
+//! let identity = bsp::usb::UsbIdentity {
+//!     product_id: 0x0488,
+//!     product: "Mass Storage",
+//!     ..Default::default()
+//! };
+//! let mut poller = bsp::usb::split_msc(USB1::take().unwrap(), identity, disk).unwrap();
+//! loop {
+//!     poller.poll();
+//! }
+//! ```
+//!
+//! While the host has the drive, don't change the blocks from your application;
+//! the host caches the file system. There's no serial port; see
+//! [device classes](super#device-classes).
+
+use super::bus::{self, BulkIn, BulkOut, Bus};
+use usb_device::{
+    bus::{InterfaceNumber, UsbBusAllocator},
+    class::{ControlIn, ControlOut, UsbClass},
+    control::{Recipient, Request, RequestType},
+    descriptor::DescriptorWriter,
+    endpoint::EndpointAddress,
+    UsbDirection,
+};
+
+const USB_CLASS_MSC: u8 = 0x08;
+const MSC_SUBCLASS_SCSI: u8 = 0x06;
+const MSC_PROTOCOL_BULK_ONLY: u8 = 0x50;
+
+const GET_MAX_LUN: u8 = 0xFE;
+const BULK_ONLY_RESET: u8 = 0xFF;
+
+/// The largest supported block size, in bytes
+pub const MAX_BLOCK_SIZE: usize = 4096;
+
+const CBW_SIGNATURE: u32 = 0x4342_5355;
+const CBW_LEN: usize = 31;
+const CSW_SIGNATURE: u32 = 0x5342_5355;
+const CSW_LEN: usize = 13;
+
+const STATUS_PASSED: u8 = 0;
+const STATUS_FAILED: u8 = 1;
+const STATUS_PHASE_ERROR: u8 = 2;
+
+/// SCSI operation codes
+mod op {
+    pub const TEST_UNIT_READY: u8 = 0x00;
+    pub const REQUEST_SENSE: u8 = 0x03;
+    pub const INQUIRY: u8 = 0x12;
+    pub const MODE_SENSE_6: u8 = 0x1A;
+    pub const START_STOP_UNIT: u8 = 0x1B;
+    pub const PREVENT_ALLOW_MEDIUM_REMOVAL: u8 = 0x1E;
+    pub const READ_FORMAT_CAPACITIES: u8 = 0x23;
+    pub const READ_CAPACITY_10: u8 = 0x25;
+    pub const READ_10: u8 = 0x28;
+    pub const WRITE_10: u8 = 0x2A;
+    pub const VERIFY_10: u8 = 0x2F;
+    pub const SYNCHRONIZE_CACHE_10: u8 = 0x35;
+}
+
+/// SCSI sense data: the sense key, additional sense code, and its qualifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sense(u8, u8, u8);
+
+impl Sense {
+    const NONE: Sense = Sense(0x00, 0x00, 0x00);
+    const UNRECOVERED_READ_ERROR: Sense = Sense(0x03, 0x11, 0x00);
+    const WRITE_ERROR: Sense = Sense(0x03, 0x0C, 0x00);
+    const INVALID_COMMAND: Sense = Sense(0x05, 0x20, 0x00);
+    const LBA_OUT_OF_RANGE: Sense = Sense(0x05, 0x21, 0x00);
+    const WRITE_PROTECTED: Sense = Sense(0x07, 0x27, 0x00);
+}
+
+/// An error from a block device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockError {
+    /// The block couldn't be read or written
+    Io,
+    /// The device doesn't allow writes
+    WriteProtected,
+}
+
+/// Storage for a USB drive
+///
+/// A block device is an array of equally-sized blocks. The host reads and
+/// writes whole blocks.
+pub trait BlockDevice {
+    /// Returns the size of each block, in bytes
+    ///
+    /// The size can't change, and it can't be larger than [`MAX_BLOCK_SIZE`].
+    /// Most hosts expect 512 byte blocks.
+    fn block_size(&self) -> usize;
+
+    /// Returns the number of blocks
+    fn block_count(&self) -> u32;
+
+    /// Read block `lba` into `block`
+    ///
+    /// `block` is one block long.
+    fn read(&mut self, lba: u32, block: &mut [u8]) -> Result<(), BlockError>;
+
+    /// Write `block` to block `lba`
+    ///
+    /// `block` is one block long.
+    fn write(&mut self, lba: u32, block: &[u8]) -> Result<(), BlockError>;
+
+    /// Make sure that written blocks are stored
+    ///
+    /// The default implementation does nothing.
+    fn flush(&mut self) -> Result<(), BlockError> {
+        Ok(())
+    }
+}
+
+/// A block device in memory
+///
+/// The contents are lost when the Teensy resets, so the host will
+/// offer to format the drive.
+pub struct RamDisk<'a> {
+    memory: &'a mut [u8],
+    block_size: usize,
+}
+
+impl<'a> RamDisk<'a> {
+    /// Use `memory` as blocks of `block_size` bytes
+    ///
+    /// The disk ignores any bytes after the last whole block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero, or larger than [`MAX_BLOCK_SIZE`].
+    pub fn new(memory: &'a mut [u8], block_size: usize) -> Self {
+        assert!(block_size > 0 && block_size <= MAX_BLOCK_SIZE);
+        RamDisk { memory, block_size }
+    }
+
+    /// Returns the blocks
+    pub fn memory(&self) -> &[u8] {
+        self.memory
+    }
+
+    fn block(&mut self, lba: u32) -> Result<&mut [u8], BlockError> {
+        let start = lba as usize * self.block_size;
+        self.memory
+            .get_mut(start..start + self.block_size)
+            .ok_or(BlockError::Io)
+    }
+}
+
+impl BlockDevice for RamDisk<'_> {
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn block_count(&self) -> u32 {
+        (self.memory.len() / self.block_size) as u32
+    }
+
+    fn read(&mut self, lba: u32, block: &mut [u8]) -> Result<(), BlockError> {
+        block.copy_from_slice(self.block(lba)?);
+        Ok(())
+    }
+
+    fn write(&mut self, lba: u32, block: &[u8]) -> Result<(), BlockError> {
+        self.block(lba)?.copy_from_slice(block);
+        Ok(())
+    }
+}
+
+/// The bulk-only transport's phases
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// Waiting for a command block wrapper
+    Command,
+    /// Sending data to the host
+    DataIn,
+    /// Receiving data from the host
+    DataOut,
+    /// Sending the command status wrapper
+    Status,
+    /// The command block wrapper was invalid; the bulk endpoints stall
+    /// until the host resets the transport
+    Stalled,
+    /// The host and the device disagree about the direction of the data;
+    /// the data endpoint stalls until the host clears the halt
+    Halted(UsbDirection),
+}
+
+/// The data that the device transfers in the data phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Data {
+    /// Nothing; any data is padding, or discarded
+    None,
+    /// The response in the buffer
+    Response,
+    /// Blocks read from the device
+    Read { lba: u32, count: u32 },
+    /// Blocks written to the device
+    Write { lba: u32, count: u32 },
+}
+
+/// The data that a command transfers
+enum Transfer {
+    None,
+    In(u32),
+    Out(u32),
+}
+
+/// The bulk-only transport, and SCSI commands
+///
+/// Independent of USB, so that we can test it on the host.
+struct Transport<'d> {
+    device: &'d mut dyn BlockDevice,
+    phase: Phase,
+    data: Data,
+    /// The current command's tag
+    tag: u32,
+    /// Number of bytes that the host expects to transfer in the data phase
+    remaining: u32,
+    /// Number of bytes that were padding, or that were discarded
+    residue: u32,
+    status: u8,
+    sense: Sense,
+    buffer: [u8; MAX_BLOCK_SIZE],
+    /// The next buffer byte to send, or to receive
+    position: usize,
+    /// Number of valid bytes in the buffer
+    len: usize,
+}
+
+impl<'d> Transport<'d> {
+    fn new(device: &'d mut dyn BlockDevice) -> Self {
+        Transport {
+            device,
+            phase: Phase::Command,
+            data: Data::None,
+            tag: 0,
+            remaining: 0,
+            residue: 0,
+            status: STATUS_PASSED,
+            sense: Sense::NONE,
+            buffer: [0; MAX_BLOCK_SIZE],
+            position: 0,
+            len: 0,
+        }
+    }
+
+    /// Prepare for the next command
+    fn reset(&mut self) {
+        self.phase = Phase::Command;
+        self.data = Data::None;
+        self.remaining = 0;
+        self.position = 0;
+        self.len = 0;
+    }
+
+    /// Returns `true` if the transport expects data from the host
+    fn receiving(&self) -> bool {
+        matches!(self.phase, Phase::Command | Phase::DataOut)
+    }
+
+    /// Returns `true` if the bulk endpoints should stall
+    fn stalled(&self) -> bool {
+        self.phase == Phase::Stalled
+    }
+
+    /// Returns the direction of the data endpoint that should stall until
+    /// the host clears its halt
+    fn halted(&self) -> Option<UsbDirection> {
+        match self.phase {
+            Phase::Halted(direction) => Some(direction),
+            _ => None,
+        }
+    }
+
+    /// The host cleared the halt of the data endpoint in `direction`
+    ///
+    /// If that endpoint stalled for a phase error, the transport sends the
+    /// command status next.
+    fn halt_cleared(&mut self, direction: UsbDirection) {
+        if self.phase == Phase::Halted(direction) {
+            self.phase = Phase::Status;
+        }
+    }
+
+    /// Handle a packet from the host
+    ///
+    /// Only call when `receiving`.
+    fn receive(&mut self, mut packet: &[u8]) {
+        match self.phase {
+            Phase::Command => self.command(packet),
+            Phase::DataOut => {
+                while !packet.is_empty() && self.remaining > 0 {
+                    let count = self.receive_data(packet);
+                    packet = &packet[count..];
+                    self.remaining -= count as u32;
+                }
+                if self.remaining == 0 {
+                    self.phase = Phase::Status;
+                }
+            }
+            Phase::DataIn | Phase::Status | Phase::Stalled | Phase::Halted(_) => {}
+        }
+    }
+
+    /// Returns the number of bytes consumed from `packet`
+    fn receive_data(&mut self, packet: &[u8]) -> usize {
+        let (lba, blocks) = match self.data {
+            Data::Write { lba, count } => (lba, count),
+            _ => {
+                let count = packet.len().min(self.remaining as usize);
+                self.residue += count as u32;
+                return count;
+            }
+        };
+        let block_size = self.device.block_size();
+        let count = packet
+            .len()
+            .min(self.remaining as usize)
+            .min(block_size - self.position);
+        self.buffer[self.position..self.position + count].copy_from_slice(&packet[..count]);
+        self.position += count;
+        if self.position == block_size {
+            self.position = 0;
+            match self.device.write(lba, &self.buffer[..block_size]) {
+                Ok(()) if blocks > 1 => {
+                    self.data = Data::Write {
+                        lba: lba + 1,
+                        count: blocks - 1,
+                    }
+                }
+                Ok(()) => self.data = Data::None,
+                Err(err) => {
+                    self.fail(match err {
+                        BlockError::Io => Sense::WRITE_ERROR,
+                        BlockError::WriteProtected => Sense::WRITE_PROTECTED,
+                    });
+                    self.data = Data::None;
+                }
+            }
+        }
+        count
+    }
+
+    /// Fill `packet` with data for the host, and return the size of
+    /// the packet
+    ///
+    /// Returns zero if there's nothing to send.
+    fn transmit(&mut self, packet: &mut [u8]) -> usize {
+        match self.phase {
+            Phase::DataIn => {
+                let mut len = 0;
+                while len < packet.len() && self.remaining > 0 {
+                    let count = self.transmit_data(&mut packet[len..]);
+                    len += count;
+                    self.remaining -= count as u32;
+                }
+                if self.remaining == 0 {
+                    self.phase = Phase::Status;
+                }
+                len
+            }
+            Phase::Status if packet.len() >= CSW_LEN => {
+                packet[..4].copy_from_slice(&CSW_SIGNATURE.to_le_bytes());
+                packet[4..8].copy_from_slice(&self.tag.to_le_bytes());
+                packet[8..12].copy_from_slice(&self.residue.to_le_bytes());
+                packet[12] = self.status;
+                self.reset();
+                CSW_LEN
+            }
+            _ => 0,
+        }
+    }
+
+    /// Returns the number of bytes written into `packet`
+    fn transmit_data(&mut self, packet: &mut [u8]) -> usize {
+        if self.position == self.len {
+            if let Data::Read { lba, count } = self.data {
+                self.load_block(lba, count);
+            }
+        }
+        let count = packet.len().min(self.remaining as usize);
+        if self.position == self.len {
+            packet[..count].fill(0);
+            self.residue += count as u32;
+            return count;
+        }
+        let count = count.min(self.len - self.position);
+        packet[..count].copy_from_slice(&self.buffer[self.position..self.position + count]);
+        self.position += count;
+        count
+    }
+
+    /// Read the next block into the buffer
+    fn load_block(&mut self, lba: u32, count: u32) {
+        let block_size = self.device.block_size();
+        self.position = 0;
+        self.len = 0;
+        self.data = if count > 1 {
+            Data::Read {
+                lba: lba + 1,
+                count: count - 1,
+            }
+        } else {
+            Data::None
+        };
+        match self.device.read(lba, &mut self.buffer[..block_size]) {
+            Ok(()) => self.len = block_size,
+            Err(_) => {
+                self.fail(Sense::UNRECOVERED_READ_ERROR);
+                self.data = Data::None;
+            }
+        }
+    }
+
+    /// Handle a command block wrapper
+    ///
+    /// An invalid command block wrapper stalls the transport, as required by
+    /// section 6.6.1 of the bulk-only transport specification. If the host
+    /// and the command disagree about the direction of the data, the data
+    /// endpoint stalls before the command status (section 6.7, cases 8 and 10).
+    fn command(&mut self, cbw: &[u8]) {
+        if cbw.len() != CBW_LEN || read_u32_le(&cbw[..4]) != CBW_SIGNATURE {
+            self.phase = Phase::Stalled;
+            return;
+        }
+        self.tag = read_u32_le(&cbw[4..8]);
+        let expected = read_u32_le(&cbw[8..12]);
+        let host_in = cbw[12] & 0x80 != 0;
+        let cb_len = (cbw[14] as usize).clamp(1, 16);
+        let mut cb = [0; 16];
+        cb[..cb_len].copy_from_slice(&cbw[15..15 + cb_len]);
+
+        self.remaining = expected;
+        self.residue = 0;
+        self.status = STATUS_PASSED;
+        self.position = 0;
+        self.len = 0;
+        self.data = Data::None;
+
+        // If the device has less data than the host expects, it pads the
+        // data, or discards the host's data. If the device has more data,
+        // it transfers what the host expects, then reports a phase error.
+        // Responses are truncated to the host's length.
+        self.phase = match self.execute(&cb) {
+            Transfer::In(len) | Transfer::Out(len) if len > 0 && expected == 0 => {
+                return self.phase_error()
+            }
+            _ if expected == 0 => Phase::Status,
+            Transfer::In(len) if host_in => {
+                if len > expected && self.data != Data::Response {
+                    self.status = STATUS_PHASE_ERROR;
+                }
+                self.len = self.len.min(expected as usize);
+                Phase::DataIn
+            }
+            Transfer::Out(len) if !host_in => {
+                if len > expected {
+                    self.status = STATUS_PHASE_ERROR;
+                }
+                Phase::DataOut
+            }
+            Transfer::None if host_in => Phase::DataIn,
+            Transfer::None => Phase::DataOut,
+            Transfer::In(_) | Transfer::Out(_) => {
+                self.phase_error();
+                Phase::Halted(if host_in {
+                    UsbDirection::In
+                } else {
+                    UsbDirection::Out
+                })
+            }
+        };
+    }
+
+    /// The host and the device disagree about the data phase
+    fn phase_error(&mut self) {
+        self.data = Data::None;
+        self.len = 0;
+        self.residue = self.remaining;
+        self.remaining = 0;
+        self.status = STATUS_PHASE_ERROR;
+        self.phase = Phase::Status;
+    }
+
+    /// Fail the command, and report `sense` in the next request sense
+    fn fail(&mut self, sense: Sense) {
+        self.status = STATUS_FAILED;
+        self.sense = sense;
+    }
+
+    /// Put `response` in the buffer, truncated to `allocation` bytes
+    fn respond(&mut self, response: &[u8], allocation: usize) -> Transfer {
+        let len = response.len().min(allocation);
+        self.buffer[..len].copy_from_slice(&response[..len]);
+        self.len = len;
+        self.data = Data::Response;
+        Transfer::In(len as u32)
+    }
+
+    /// Run a SCSI command, and return its data transfer
+    fn execute(&mut self, cb: &[u8; 16]) -> Transfer {
+        let block_size = self.device.block_size();
+        let block_count = self.device.block_count();
+        match cb[0] {
+            op::TEST_UNIT_READY
+            | op::START_STOP_UNIT
+            | op::PREVENT_ALLOW_MEDIUM_REMOVAL
+            | op::VERIFY_10 => {
+                self.sense = Sense::NONE;
+                Transfer::None
+            }
+            op::SYNCHRONIZE_CACHE_10 => {
+                if self.device.flush().is_err() {
+                    self.fail(Sense::WRITE_ERROR);
+                }
+                Transfer::None
+            }
+            op::REQUEST_SENSE => {
+                let Sense(key, asc, ascq) = core::mem::replace(&mut self.sense, Sense::NONE);
+                let mut response = [0; 18];
+                response[0] = 0x70;
+                response[2] = key;
+                response[7] = 10;
+                response[12] = asc;
+                response[13] = ascq;
+                self.respond(&response, cb[4] as usize)
+            }
+            op::INQUIRY => {
+                let mut response = [0; 36];
+                // Direct access, removable, SPC-2.
+                response[..5].copy_from_slice(&[0x00, 0x80, 0x04, 0x02, 31]);
+                response[8..16].copy_from_slice(b"Teensy  ");
+                response[16..32].copy_from_slice(b"Mass Storage    ");
+                response[32..36].copy_from_slice(b"1.0 ");
+                self.respond(&response, read_u16_be(&cb[3..5]) as usize)
+            }
+            op::MODE_SENSE_6 => self.respond(&[3, 0, 0, 0], cb[4] as usize),
+            op::READ_FORMAT_CAPACITIES => {
+                let mut response = [0; 12];
+                response[3] = 8;
+                response[4..8].copy_from_slice(&block_count.to_be_bytes());
+                // Formatted media
+                response[8] = 0x02;
+                response[9..12].copy_from_slice(&(block_size as u32).to_be_bytes()[1..]);
+                self.respond(&response, read_u16_be(&cb[7..9]) as usize)
+            }
+            op::READ_CAPACITY_10 => {
+                let mut response = [0; 8];
+                let last = block_count.saturating_sub(1);
+                response[..4].copy_from_slice(&last.to_be_bytes());
+                response[4..].copy_from_slice(&(block_size as u32).to_be_bytes());
+                self.respond(&response, response.len())
+            }
+            op::READ_10 | op::WRITE_10 => {
+                let lba = read_u32_be(&cb[2..6]);
+                let count = read_u16_be(&cb[7..9]) as u32;
+                let len = count * block_size as u32;
+                let in_range = match lba.checked_add(count) {
+                    Some(end) => end <= block_count,
+                    None => false,
+                };
+                if !in_range {
+                    self.fail(Sense::LBA_OUT_OF_RANGE);
+                    return Transfer::None;
+                }
+                if count > 0 {
+                    self.data = if cb[0] == op::READ_10 {
+                        Data::Read { lba, count }
+                    } else {
+                        Data::Write { lba, count }
+                    };
+                }
+                if cb[0] == op::READ_10 {
+                    Transfer::In(len)
+                } else {
+                    Transfer::Out(len)
+                }
+            }
+            _ => {
+                self.fail(Sense::INVALID_COMMAND);
+                Transfer::None
+            }
+        }
+    }
+}
+
+fn read_u16_be(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+fn read_u32_be(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn read_u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// The USB mass storage class
+pub(super) struct Msc<'a> {
+    interface: InterfaceNumber,
+    read_ep: BulkOut<'a>,
+    write_ep: BulkIn<'a>,
+    transport: Transport<'a>,
+    /// Set while there's a transfer on the write endpoint
+    tx_busy: bool,
+}
+
+impl<'a> Msc<'a> {
+    /// # Panics
+    ///
+    /// Panics if the device's block size is zero, or larger than [`MAX_BLOCK_SIZE`].
+    pub fn new(alloc: &'a UsbBusAllocator<Bus>, device: &'a mut dyn BlockDevice) -> Self {
+        let block_size = device.block_size();
+        assert!(block_size > 0 && block_size <= MAX_BLOCK_SIZE);
+        Msc {
+            interface: alloc.interface(),
+            read_ep: BulkOut::new(alloc),
+            write_ep: BulkIn::new(alloc),
+            transport: Transport::new(device),
+            tx_busy: false,
+        }
+    }
+
+    /// Move a packet from the read endpoint into the transport, then send
+    /// the transport's response
+    fn pull_rx(&mut self) {
+        if self.transport.receiving() {
+            let mut packet = [0; bus::MAX_BULK_PACKET_LEN];
+            if let Ok(count) = self.read_ep.read(&mut packet) {
+                self.transport.receive(&packet[..count]);
+            }
+        }
+        if self.transport.stalled() {
+            self.read_ep.stall();
+            self.write_ep.stall();
+            return;
+        }
+        match self.transport.halted() {
+            Some(UsbDirection::In) => return self.write_ep.stall(),
+            Some(UsbDirection::Out) => return self.read_ep.stall(),
+            None => {}
+        }
+        if !self.tx_busy {
+            self.push_tx();
+        }
+    }
+
+    /// Schedule a transfer of the transport's next packet
+    fn push_tx(&mut self) {
+        let packet_len = bus::bulk_packet_len();
+        let mut packet = [0; bus::MAX_BULK_PACKET_LEN];
+        let count = self.transport.transmit(&mut packet[..packet_len]);
+        if count > 0 && self.write_ep.write(&packet[..count]).is_ok() {
+            self.tx_busy = true;
+        }
+    }
+
+    fn is_recipient(&self, req: &Request) -> bool {
+        req.request_type == RequestType::Class
+            && req.recipient == Recipient::Interface
+            && req.index == u8::from(self.interface) as u16
+    }
+}
+
+impl UsbClass<Bus> for Msc<'_> {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        writer.interface(
+            self.interface,
+            USB_CLASS_MSC,
+            MSC_SUBCLASS_SCSI,
+            MSC_PROTOCOL_BULK_ONLY,
+        )?;
+        self.read_ep.describe(writer)?;
+        self.write_ep.describe(writer)?;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.transport.reset();
+        self.tx_busy = false;
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<Bus>) {
+        let req = *xfer.request();
+        if !self.is_recipient(&req) {
+            return;
+        }
+        match req.request {
+            // There's one logical unit.
+            GET_MAX_LUN => xfer.accept_with(&[0]).ok(),
+            _ => xfer.reject().ok(),
+        };
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<Bus>) {
+        let req = *xfer.request();
+        if req.request_type == RequestType::Standard
+            && req.recipient == Recipient::Endpoint
+            && req.request == Request::CLEAR_FEATURE
+            && req.value == Request::FEATURE_ENDPOINT_HALT
+        {
+            // The device clears the halt; the transport notices, then sends
+            // the command status from poll().
+            let addr = EndpointAddress::from(req.index as u8 & 0x8f);
+            if addr == self.read_ep.address() || addr == self.write_ep.address() {
+                self.transport.halt_cleared(addr.direction());
+            }
+            return;
+        }
+        if !self.is_recipient(&req) {
+            return;
+        }
+        match req.request {
+            BULK_ONLY_RESET => {
+                self.transport.reset();
+                self.read_ep.unstall();
+                self.write_ep.unstall();
+                xfer.accept().ok()
+            }
+            _ => xfer.reject().ok(),
+        };
+    }
+
+    fn poll(&mut self) {
+        if !self.tx_busy {
+            self.push_tx();
+        }
+    }
+
+    fn endpoint_out(&mut self, addr: EndpointAddress) {
+        if addr == self.read_ep.address() {
+            self.pull_rx();
+        }
+    }
+
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        if addr == self.write_ep.address() {
+            self.tx_busy = false;
+            // The transport may be ready for the next command.
+            self.pull_rx();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RamDisk, Transport, CBW_SIGNATURE, CSW_LEN, CSW_SIGNATURE};
+    use usb_device::UsbDirection;
+
+    /// Returns a command block wrapper
+    fn cbw(tag: u32, expected: u32, host_in: bool, cb: &[u8]) -> [u8; 31] {
+        let mut cbw = [0; 31];
+        cbw[..4].copy_from_slice(&CBW_SIGNATURE.to_le_bytes());
+        cbw[4..8].copy_from_slice(&tag.to_le_bytes());
+        cbw[8..12].copy_from_slice(&expected.to_le_bytes());
+        cbw[12] = if host_in { 0x80 } else { 0 };
+        cbw[14] = cb.len() as u8;
+        cbw[15..15 + cb.len()].copy_from_slice(cb);
+        cbw
+    }
+
+    /// Receive all data from the transport, and return the number of bytes
+    fn receive_all(transport: &mut Transport, data: &mut [u8]) -> usize {
+        let mut len = 0;
+        loop {
+            let mut packet = [0; 64];
+            let count = transport.transmit(&mut packet);
+            if count == 0 {
+                return len;
+            }
+            data[len..len + count].copy_from_slice(&packet[..count]);
+            len += count;
+        }
+    }
+
+    /// Check the command status wrapper at the end of `data`, and return
+    /// the residue and status
+    fn csw(data: &[u8], tag: u32) -> (u32, u8) {
+        let csw = &data[data.len() - CSW_LEN..];
+        assert_eq!(&csw[..4], &CSW_SIGNATURE.to_le_bytes());
+        assert_eq!(&csw[4..8], &tag.to_le_bytes());
+        let residue = u32::from_le_bytes([csw[8], csw[9], csw[10], csw[11]]);
+        (residue, csw[12])
+    }
+
+    /// Run a command that sends data to the host, and return the data and
+    /// the command status
+    fn command_in(transport: &mut Transport, cb: &[u8], expected: u32) -> ([u8; 2048], u32, u8) {
+        transport.receive(&cbw(7, expected, true, cb));
+        let mut data = [0; 2048];
+        let len = receive_all(transport, &mut data);
+        assert_eq!(len, expected as usize + CSW_LEN);
+        let (residue, status) = csw(&data[..len], 7);
+        (data, residue, status)
+    }
+
+    #[test]
+    fn inquiry_and_capacity() {
+        let mut memory = [0; 64 * 512];
+        let mut disk = RamDisk::new(&mut memory, 512);
+        let mut transport = Transport::new(&mut disk);
+
+        let (data, residue, status) = command_in(&mut transport, &[0x12, 0, 0, 0, 36, 0], 36);
+        assert_eq!((residue, status), (0, 0));
+        assert_eq!(data[0], 0x00);
+        assert_eq!(&data[8..14], b"Teensy");
+
+        let (data, residue, status) =
+            command_in(&mut transport, &[0x25, 0, 0, 0, 0, 0, 0, 0, 0, 0], 8);
+        assert_eq!((residue, status), (0, 0));
+        assert_eq!(&data[..8], &[0, 0, 0, 63, 0, 0, 2, 0]);
+
+        transport.receive(&cbw(9, 0, false, &[0x00, 0, 0, 0, 0, 0]));
+        let mut data = [0; 64];
+        assert_eq!(receive_all(&mut transport, &mut data), CSW_LEN);
+        assert_eq!(csw(&data[..CSW_LEN], 9), (0, 0));
+    }
+
+    #[test]
+    fn write_then_read() {
+        let mut memory = [0; 16 * 512];
+        let mut disk = RamDisk::new(&mut memory, 512);
+        {
+            let mut transport = Transport::new(&mut disk);
+            // Write blocks 2 and 3.
+            transport.receive(&cbw(1, 1024, false, &[0x2A, 0, 0, 0, 0, 2, 0, 0, 2, 0]));
+            for idx in 0..16 {
+                assert!(transport.receiving());
+                transport.receive(&[idx as u8; 64]);
+            }
+            assert!(!transport.receiving());
+            let mut data = [0; 64];
+            assert_eq!(receive_all(&mut transport, &mut data), CSW_LEN);
+            assert_eq!(csw(&data[..CSW_LEN], 1), (0, 0));
+
+            let (data, residue, status) =
+                command_in(&mut transport, &[0x28, 0, 0, 0, 0, 2, 0, 0, 2, 0], 1024);
+            assert_eq!((residue, status), (0, 0));
+            for (idx, chunk) in data[..1024].chunks(64).enumerate() {
+                assert!(chunk.iter().all(|&byte| byte == idx as u8));
+            }
+        }
+        assert!(disk.memory()[..1024].iter().all(|&byte| byte == 0));
+        assert!(disk.memory()[1024 + 512..1024 + 576]
+            .iter()
+            .all(|&byte| byte == 8));
+        assert!(disk.memory()[2048..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn errors_and_sense() {
+        let mut memory = [0; 8 * 512];
+        let mut disk = RamDisk::new(&mut memory, 512);
+        let mut transport = Transport::new(&mut disk);
+
+        // Out of range reads fail, and pad the data.
+        let (_, residue, status) =
+            command_in(&mut transport, &[0x28, 0, 0, 0, 0, 7, 0, 0, 2, 0], 1024);
+        assert_eq!((residue, status), (1024, 1));
+        let (data, _, status) = command_in(&mut transport, &[0x03, 0, 0, 0, 18, 0], 18);
+        assert_eq!(status, 0);
+        assert_eq!((data[2], data[12]), (0x05, 0x21));
+
+        // Unknown commands fail.
+        transport.receive(&cbw(3, 0, false, &[0xC0, 0, 0, 0, 0, 0]));
+        let mut data = [0; 64];
+        assert_eq!(receive_all(&mut transport, &mut data), CSW_LEN);
+        assert_eq!(csw(&data[..CSW_LEN], 3), (0, 1));
+        let (data, _, _) = command_in(&mut transport, &[0x03, 0, 0, 0, 18, 0], 18);
+        assert_eq!((data[2], data[12]), (0x05, 0x20));
+        // Sense is cleared after it's reported.
+        let (data, _, _) = command_in(&mut transport, &[0x03, 0, 0, 0, 18, 0], 18);
+        assert_eq!((data[2], data[12]), (0, 0));
+
+        // The host expects fewer blocks than the command reads.
+        let (_, residue, status) =
+            command_in(&mut transport, &[0x28, 0, 0, 0, 0, 0, 0, 0, 2, 0], 512);
+        assert_eq!((residue, status), (0, 2));
+
+        // Invalid command block wrappers stall the transport until it's reset.
+        transport.receive(&[0; 31]);
+        assert!(transport.stalled());
+        assert_eq!(transport.transmit(&mut [0; 64]), 0);
+        assert!(!transport.receiving());
+        transport.reset();
+        assert!(!transport.stalled());
+        assert!(transport.receiving());
+    }
+
+    #[test]
+    fn direction_mismatch() {
+        let mut memory = [0; 8 * 512];
+        let mut disk = RamDisk::new(&mut memory, 512);
+        let mut transport = Transport::new(&mut disk);
+
+        // The host expects data, but the command writes; Bulk-In stalls.
+        transport.receive(&cbw(4, 512, true, &[0x2A, 0, 0, 0, 0, 0, 0, 0, 1, 0]));
+        assert_eq!(transport.halted(), Some(UsbDirection::In));
+        assert!(!transport.receiving());
+        assert_eq!(transport.transmit(&mut [0; 64]), 0);
+        transport.halt_cleared(UsbDirection::Out);
+        assert_eq!(transport.halted(), Some(UsbDirection::In));
+        transport.halt_cleared(UsbDirection::In);
+        let mut data = [0; 64];
+        assert_eq!(receive_all(&mut transport, &mut data), CSW_LEN);
+        assert_eq!(csw(&data[..CSW_LEN], 4), (512, 2));
+
+        // The host sends data, but the command reads; Bulk-Out stalls.
+        transport.receive(&cbw(5, 512, false, &[0x28, 0, 0, 0, 0, 0, 0, 0, 1, 0]));
+        assert_eq!(transport.halted(), Some(UsbDirection::Out));
+        assert!(!transport.receiving());
+        assert_eq!(transport.transmit(&mut [0; 64]), 0);
+        transport.halt_cleared(UsbDirection::Out);
+        assert_eq!(transport.halted(), None);
+        assert_eq!(receive_all(&mut transport, &mut data), CSW_LEN);
+        assert_eq!(csw(&data[..CSW_LEN], 5), (512, 2));
+        assert!(transport.receiving());
+    }
+}
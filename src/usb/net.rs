@@ -0,0 +1,927 @@
+//! USB network interfaces
+//!
+//! Use [`split_net`](super::split_net) to present a USB network adapter instead
+//! of a USB serial port. The adapter implements the CDC Network Control Model
+//! (NCM), which Linux, macOS, and Windows 10 and later support without extra
+//! drivers. The host sees an Ethernet link to the Teensy.
+//!
+//! The returned [`Net`] sends and receives Ethernet frames, excluding the frame
+//! check sequence. [`poll`](super::poll()) moves frames to and from the host.
+//! The Teensy's frames use its [MAC address](Net::mac_address); the host's
+//! interface has a [different address](Net::host_mac_address). You're
+//! responsible for ARP, IP, and everything above; enable the `"usb-net"` feature
+//! to use `Net` as a [smoltcp](https://docs.rs/smoltcp) `phy::Device`.
+//!
+//! ```no_run
+//! use teensy4_bsp as bsp;
+//! use bsp::hal::ral::usb::USB1;
+//! use bsp::usb::net::MAX_FRAME_LEN;
+//!
+//! let identity = bsp::usb::UsbIdentity {
+//!     product_id: 0x0490,
+//!     product: "Network",
+//!     ..Default::default()
+//! };
+//! let (mut poller, mut net) = bsp::usb::split_net(USB1::take().unwrap(), identity).unwrap();
+//! let mut frame = [0; MAX_FRAME_LEN];
+//!
+//! loop {
+//!     poller.poll();
+//!     while let Ok(Some(len)) = net.receive(&mut frame) {
+//!         // Handle the Ethernet frame in frame[..len]...
+//!     }
+//! }
+//! ```
+//!
+//! The adapter holds one frame for the host, and one transfer of frames from
+//! the host. There's no serial port; see [device classes](super#device-classes).
+
+#[cfg(feature = "usb-net")]
+mod phy;
+#[cfg(feature = "usb-net")]
+#[cfg_attr(docsrs, doc(cfg(feature = "usb-net")))]
+pub use phy::{RxToken, TxToken};
+
+use super::bus::{self, BulkIn, BulkOut, Bus};
+use super::{with_stack, Error};
+use usb_device::{
+    bus::{InterfaceNumber, StringIndex, UsbBusAllocator},
+    class::{ControlIn, ControlOut, UsbClass},
+    control::{Recipient, RequestType},
+    descriptor::DescriptorWriter,
+    endpoint::{EndpointAddress, EndpointIn},
+    LangID,
+};
+
+const USB_CLASS_CDC: u8 = 0x02;
+const USB_CLASS_CDC_DATA: u8 = 0x0A;
+const CDC_SUBCLASS_NCM: u8 = 0x0D;
+const CDC_PROTOCOL_NTB: u8 = 0x01;
+
+const CS_INTERFACE: u8 = 0x24;
+const CDC_TYPE_HEADER: u8 = 0x00;
+const CDC_TYPE_UNION: u8 = 0x06;
+const CDC_TYPE_ETHERNET: u8 = 0x0F;
+const CDC_TYPE_NCM: u8 = 0x1A;
+
+const SET_ETHERNET_PACKET_FILTER: u8 = 0x43;
+const GET_NTB_PARAMETERS: u8 = 0x80;
+const GET_NTB_INPUT_SIZE: u8 = 0x85;
+const SET_NTB_INPUT_SIZE: u8 = 0x86;
+
+const NETWORK_CONNECTION: u8 = 0x00;
+const CONNECTION_SPEED_CHANGE: u8 = 0x2A;
+
+const NOTIFY_PACKET_SIZE: u16 = 16;
+
+/// The data interface's alternate setting with endpoints
+const DATA_ALT_ENABLED: u8 = 1;
+
+/// The largest Ethernet frame, excluding the frame check sequence
+pub const MAX_FRAME_LEN: usize = 1514;
+
+/// Size of the transfer buffers; the largest NTB that we exchange
+const NTB_MAX_LEN: usize = 2048;
+
+const NTH16_SIGNATURE: u32 = 0x484D_434E; // "NCMH"
+const NDP16_SIGNATURE: u32 = 0x304D_434E; // "NCM0"
+const NDP16_CRC_SIGNATURE: u32 = 0x314D_434E; // "NCM1"
+const NTH16_LEN: usize = 12;
+/// An NDP16 with one datagram, and the terminating entry
+const NDP16_LEN: usize = 16;
+/// Where a transmitted datagram starts in its NTB
+const DATAGRAM_INDEX: usize = NTH16_LEN + NDP16_LEN;
+
+/// The NTB parameters that we return for GET_NTB_PARAMETERS
+///
+/// We support NTB16, and align NDPs and datagrams on four bytes.
+/// There's no limit on the number of datagrams from the host.
+const NTB_PARAMETERS: [u8; 28] = {
+    let max = (NTB_MAX_LEN as u32).to_le_bytes();
+    [
+        28, 0, // wLength
+        0x01, 0x00, // bmNtbFormatsSupported
+        max[0], max[1], max[2], max[3], // dwNtbInMaxSize
+        4, 0, // wNdpInDivisor
+        0, 0, // wNdpInPayloadRemainder
+        4, 0, // wNdpInAlignment
+        0, 0, // reserved
+        max[0], max[1], max[2], max[3], // dwNtbOutMaxSize
+        4, 0, // wNdpOutDivisor
+        0, 0, // wNdpOutPayloadRemainder
+        4, 0, // wNdpOutAlignment
+        0, 0, // wNtbOutMaxDatagrams
+    ]
+};
+
+fn read_u16(buffer: &[u8], index: usize) -> u16 {
+    u16::from_le_bytes([buffer[index], buffer[index + 1]])
+}
+
+fn read_u32(buffer: &[u8], index: usize) -> u32 {
+    u32::from_le_bytes([
+        buffer[index],
+        buffer[index + 1],
+        buffer[index + 2],
+        buffer[index + 3],
+    ])
+}
+
+fn write_u16(buffer: &mut [u8], index: usize, value: u16) {
+    buffer[index..index + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_u32(buffer: &mut [u8], index: usize, value: u32) {
+    buffer[index..index + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Returns the block length, and the first NDP's index, if `ntb`
+/// starts with a valid NTH16
+fn parse_nth(ntb: &[u8]) -> Option<(usize, usize)> {
+    if ntb.len() < NTH16_LEN
+        || read_u32(ntb, 0) != NTH16_SIGNATURE
+        || read_u16(ntb, 4) as usize != NTH16_LEN
+    {
+        return None;
+    }
+    let block_len = read_u16(ntb, 8) as usize;
+    if block_len < NTH16_LEN || block_len > ntb.len() {
+        return None;
+    }
+    let ndp = read_u16(ntb, 10) as usize;
+    if is_valid_ndp(&ntb[..block_len], ndp) {
+        Some((block_len, ndp))
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if there's a valid NDP16 at `index`
+///
+/// The NDP and its entries fit within the `ntb`. Datagrams may not.
+fn is_valid_ndp(ntb: &[u8], index: usize) -> bool {
+    if index < NTH16_LEN || index % 4 != 0 || index + NDP16_LEN > ntb.len() {
+        return false;
+    }
+    let signature = read_u32(ntb, index);
+    let len = read_u16(ntb, index + 4) as usize;
+    (signature == NDP16_SIGNATURE || signature == NDP16_CRC_SIGNATURE)
+        && len >= NDP16_LEN
+        && len % 4 == 0
+        && index + len <= ntb.len()
+}
+
+/// Collects NTBs from the host, and takes their datagrams
+struct Receiver<const N: usize> {
+    ntb: [u8; N],
+    /// Bytes received, or the block length once complete
+    len: usize,
+    /// Set when `ntb` holds a valid NTB
+    complete: bool,
+    /// The current NDP's index
+    ndp: usize,
+    /// The next entry in the current NDP
+    entry: usize,
+}
+
+impl<const N: usize> Receiver<N> {
+    const fn new() -> Self {
+        Receiver {
+            ntb: [0; N],
+            len: 0,
+            complete: false,
+            ndp: 0,
+            entry: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+        self.complete = false;
+    }
+
+    /// Returns the space for the next packet, or `None` if we're
+    /// holding a complete NTB
+    fn space(&mut self) -> Option<&mut [u8]> {
+        if self.complete {
+            None
+        } else {
+            Some(&mut self.ntb[self.len..])
+        }
+    }
+
+    /// Note that we received `count` bytes into the [`space`](Self::space)
+    ///
+    /// A short packet, or a full buffer, ends the NTB. We drop NTBs
+    /// that aren't valid.
+    fn filled(&mut self, count: usize, packet_len: usize) {
+        self.len = (self.len + count).min(N);
+        if count == packet_len && self.len < N {
+            return;
+        }
+        match parse_nth(&self.ntb[..self.len]) {
+            Some((block_len, ndp)) => {
+                self.len = block_len;
+                self.complete = true;
+                self.ndp = ndp;
+                self.entry = 0;
+            }
+            None => self.clear(),
+        }
+    }
+
+    /// Copy the next datagram into `frame`, returning its length
+    ///
+    /// Drops datagrams that don't fit in `frame`, or that aren't
+    /// within the NTB. Returns `None` once we've taken all datagrams,
+    /// then makes space for the next NTB.
+    fn next(&mut self, frame: &mut [u8]) -> Option<usize> {
+        while self.complete {
+            let ntb = &self.ntb[..self.len];
+            let ndp_len = read_u16(ntb, self.ndp + 4) as usize;
+            let offset = 8 + 4 * self.entry;
+            let (index, len) = if offset + 4 <= ndp_len {
+                (
+                    read_u16(ntb, self.ndp + offset) as usize,
+                    read_u16(ntb, self.ndp + offset + 2) as usize,
+                )
+            } else {
+                (0, 0)
+            };
+
+            if index != 0 && len != 0 {
+                self.entry += 1;
+                match ntb.get(index..index + len) {
+                    Some(datagram) if len <= frame.len() => {
+                        frame[..len].copy_from_slice(datagram);
+                        return Some(len);
+                    }
+                    _ => continue,
+                }
+            }
+
+            // End of this NDP. Later NDPs must follow this one, so
+            // that we can't loop.
+            let next = read_u16(ntb, self.ndp + 6) as usize;
+            if next > self.ndp && is_valid_ndp(ntb, next) {
+                self.ndp = next;
+                self.entry = 0;
+            } else {
+                self.clear();
+            }
+        }
+        None
+    }
+}
+
+/// Frames datagrams into NTBs for the host
+///
+/// Each NTB holds one datagram.
+struct Transmitter<const N: usize> {
+    ntb: [u8; N],
+    /// The NTB length, or zero if there's no NTB
+    len: usize,
+    /// Bytes already sent from `ntb`
+    position: usize,
+    /// Set if we owe the host a zero length packet
+    zlp: bool,
+    sequence: u16,
+}
+
+impl<const N: usize> Transmitter<N> {
+    const fn new() -> Self {
+        Transmitter {
+            ntb: [0; N],
+            len: 0,
+            position: 0,
+            zlp: false,
+            sequence: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+        self.position = 0;
+        self.zlp = false;
+    }
+
+    fn is_idle(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Frame `datagram` in the next NTB
+    ///
+    /// Returns `false` if we're still sending the previous NTB, or if
+    /// the datagram doesn't fit.
+    fn load(&mut self, datagram: &[u8]) -> bool {
+        let len = DATAGRAM_INDEX + datagram.len();
+        if !self.is_idle() || datagram.is_empty() || len > N {
+            return false;
+        }
+
+        let ntb = &mut self.ntb;
+        write_u32(ntb, 0, NTH16_SIGNATURE);
+        write_u16(ntb, 4, NTH16_LEN as u16);
+        write_u16(ntb, 6, self.sequence);
+        write_u16(ntb, 8, len as u16);
+        write_u16(ntb, 10, NTH16_LEN as u16);
+
+        let ndp = NTH16_LEN;
+        write_u32(ntb, ndp, NDP16_SIGNATURE);
+        write_u16(ntb, ndp + 4, NDP16_LEN as u16);
+        write_u16(ntb, ndp + 6, 0);
+        write_u16(ntb, ndp + 8, DATAGRAM_INDEX as u16);
+        write_u16(ntb, ndp + 10, datagram.len() as u16);
+        write_u32(ntb, ndp + 12, 0);
+
+        ntb[DATAGRAM_INDEX..len].copy_from_slice(datagram);
+        self.len = len;
+        self.position = 0;
+        self.sequence = self.sequence.wrapping_add(1);
+        true
+    }
+
+    /// Returns the next packet of the NTB, or `None` when we've sent
+    /// the NTB
+    ///
+    /// The packet stays next until you [`advance`](Self::advance). If the
+    /// NTB is a multiple of `packet_len`, the last packet has no data. Once
+    /// this returns `None`, the transmitter is idle.
+    fn next_packet(&mut self, packet_len: usize) -> Option<&[u8]> {
+        if self.is_idle() {
+            return None;
+        }
+        if self.position < self.len {
+            let count = packet_len.min(self.len - self.position);
+            return Some(&self.ntb[self.position..self.position + count]);
+        }
+        if self.zlp {
+            return Some(&[]);
+        }
+        self.clear();
+        None
+    }
+
+    /// Indicate that the packet from `next_packet` was sent
+    fn advance(&mut self, packet_len: usize) {
+        if self.position < self.len {
+            let count = packet_len.min(self.len - self.position);
+            self.position += count;
+            self.zlp = count == packet_len;
+        } else {
+            self.zlp = false;
+        }
+    }
+}
+
+/// Returns the MAC address of the host's interface
+///
+/// This is the Teensy's MAC address, but locally administered.
+const fn host_mac_address(mac: [u8; 6]) -> [u8; 6] {
+    [mac[0] ^ 0x02, mac[1], mac[2], mac[3], mac[4], mac[5]]
+}
+
+/// Formats a MAC address for the iMACAddress string
+fn mac_string(mac: [u8; 6]) -> [u8; 12] {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let mut string = [0; 12];
+    for (digits, byte) in string.chunks_exact_mut(2).zip(mac.iter()) {
+        digits[0] = HEX[(byte >> 4) as usize];
+        digits[1] = HEX[(byte & 0xF) as usize];
+    }
+    string
+}
+
+/// Sends and receives Ethernet frames
+pub struct Net(core::marker::PhantomData<*const ()>);
+
+// Safety: OK to move across execution contexts; never
+// safe to share across those contexts.
+unsafe impl Send for Net {}
+
+impl Net {
+    pub(super) const fn new() -> Self {
+        Net(core::marker::PhantomData)
+    }
+
+    /// Returns the Teensy's MAC address
+    ///
+    /// This is the MAC address [assigned by PJRC](crate::BoardId::mac_address).
+    pub fn mac_address(&self) -> [u8; 6] {
+        crate::board_id().mac_address()
+    }
+
+    /// Returns the MAC address of the host's interface
+    pub fn host_mac_address(&self) -> [u8; 6] {
+        host_mac_address(self.mac_address())
+    }
+
+    /// Returns `true` if the host enabled the network interface
+    ///
+    /// Until then, the Teensy drops frames.
+    pub fn is_connected(&self) -> bool {
+        with_stack(|stack| {
+            stack
+                .classes
+                .net()
+                .map(|net| net.data_alt == DATA_ALT_ENABLED)
+        })
+        .unwrap_or(false)
+    }
+
+    /// Returns `true` if [`send`](Self::send) can take a frame
+    pub fn can_send(&self) -> bool {
+        with_stack(|stack| {
+            stack
+                .classes
+                .net()
+                .map(|net| net.data_alt == DATA_ALT_ENABLED && net.tx.is_idle())
+        })
+        .unwrap_or(false)
+    }
+
+    /// Send an Ethernet frame
+    ///
+    /// Returns `false` if the host hasn't enabled the interface, or if we're
+    /// still sending the previous frame. Try again after `poll` sends more data.
+    /// Frames longer than [`MAX_FRAME_LEN`] are never sent.
+    pub fn send(&mut self, frame: &[u8]) -> Result<bool, Error> {
+        with_stack(|stack| stack.classes.net().map(|net| net.send(frame)))
+    }
+
+    /// Receive the next Ethernet frame into `frame`
+    ///
+    /// Returns the frame's length, or `None` if there are no frames. Drops
+    /// frames that don't fit in `frame`; a [`MAX_FRAME_LEN`] buffer fits all
+    /// frames.
+    pub fn receive(&mut self, frame: &mut [u8]) -> Result<Option<usize>, Error> {
+        with_stack(|stack| stack.classes.net().map(|net| net.receive(frame)))
+    }
+}
+
+/// The notifications that we owe the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Notify {
+    None,
+    Connection,
+    Speed,
+}
+
+/// The USB CDC-NCM class
+pub(super) struct NcmClass<'a> {
+    comm_if: InterfaceNumber,
+    notify_ep: EndpointIn<'a, Bus>,
+    data_if: InterfaceNumber,
+    read_ep: BulkOut<'a>,
+    write_ep: BulkIn<'a>,
+    mac_address: StringIndex,
+    mac_string: [u8; 12],
+    /// The data interface's alternate setting
+    data_alt: u8,
+    notify: Notify,
+    /// Set while there's a transfer on the notification endpoint
+    notify_busy: bool,
+    /// The host's NTB input size
+    ntb_input_size: u32,
+    rx: Receiver<NTB_MAX_LEN>,
+    tx: Transmitter<NTB_MAX_LEN>,
+    /// Set while there's a transfer on the write endpoint
+    tx_busy: bool,
+    /// Set when the host selects an alternate setting, until `poll` resets
+    /// the data endpoints
+    data_reset: bool,
+}
+
+impl<'a> NcmClass<'a> {
+    pub fn new(alloc: &'a UsbBusAllocator<Bus>) -> Self {
+        let mac = crate::board_id().mac_address();
+        NcmClass {
+            comm_if: alloc.interface(),
+            notify_ep: alloc.interrupt(NOTIFY_PACKET_SIZE, 8),
+            data_if: alloc.interface(),
+            read_ep: BulkOut::new(alloc),
+            write_ep: BulkIn::new(alloc),
+            mac_address: alloc.string(),
+            mac_string: mac_string(host_mac_address(mac)),
+            data_alt: 0,
+            notify: Notify::None,
+            notify_busy: false,
+            ntb_input_size: NTB_MAX_LEN as u32,
+            rx: Receiver::new(),
+            tx: Transmitter::new(),
+            tx_busy: false,
+            data_reset: false,
+        }
+    }
+
+    /// Returns the data endpoints if the host selected an alternate setting
+    /// since the last call
+    ///
+    /// Reset the endpoints with [`Bus::reset_endpoints`].
+    pub fn take_data_reset(&mut self) -> Option<[EndpointAddress; 2]> {
+        if core::mem::replace(&mut self.data_reset, false) {
+            Some([self.read_ep.address(), self.write_ep.address()])
+        } else {
+            None
+        }
+    }
+
+    fn send(&mut self, frame: &[u8]) -> bool {
+        if self.data_alt != DATA_ALT_ENABLED || frame.len() > MAX_FRAME_LEN {
+            return false;
+        }
+        if !self.tx.load(frame) {
+            return false;
+        }
+        if !self.tx_busy {
+            self.push_tx();
+        }
+        true
+    }
+
+    fn receive(&mut self, frame: &mut [u8]) -> Option<usize> {
+        let len = self.rx.next(frame);
+        if len.is_none() {
+            self.pull_rx();
+        }
+        len
+    }
+
+    /// Move a packet from the read endpoint into the receiver
+    fn pull_rx(&mut self) {
+        let space = match self.rx.space() {
+            Some(space) => space,
+            None => return,
+        };
+        if let Ok(count) = self.read_ep.read(space) {
+            self.rx.filled(count, bus::bulk_packet_len());
+        }
+    }
+
+    /// Schedule the next packet of the transmitted NTB
+    fn push_tx(&mut self) {
+        let packet_len = bus::bulk_packet_len();
+        if let Some(packet) = self.tx.next_packet(packet_len) {
+            if self.write_ep.write(packet).is_ok() {
+                self.tx.advance(packet_len);
+                self.tx_busy = true;
+            }
+        }
+    }
+
+    /// Send the next notification
+    fn push_notify(&mut self) {
+        if self.notify_busy {
+            return;
+        }
+        let comm_if = u8::from(self.comm_if);
+        let result = match self.notify {
+            Notify::None => return,
+            Notify::Connection => {
+                self.notify_ep
+                    .write(&[0xA1, NETWORK_CONNECTION, 1, 0, comm_if, 0, 0, 0])
+            }
+            Notify::Speed => {
+                let speed = if bus::is_high_speed() {
+                    480_000_000u32
+                } else {
+                    12_000_000u32
+                }
+                .to_le_bytes();
+                self.notify_ep.write(&[
+                    0xA1,
+                    CONNECTION_SPEED_CHANGE,
+                    0,
+                    0,
+                    comm_if,
+                    0,
+                    8,
+                    0,
+                    speed[0],
+                    speed[1],
+                    speed[2],
+                    speed[3],
+                    speed[0],
+                    speed[1],
+                    speed[2],
+                    speed[3],
+                ])
+            }
+        };
+        if result.is_ok() {
+            self.notify_busy = true;
+            self.notify = match self.notify {
+                Notify::Connection => Notify::Speed,
+                _ => Notify::None,
+            };
+        }
+    }
+
+    fn is_recipient(&self, req: &usb_device::control::Request) -> bool {
+        req.request_type == RequestType::Class
+            && req.recipient == Recipient::Interface
+            && req.index == u8::from(self.comm_if) as u16
+    }
+}
+
+impl UsbClass<Bus> for NcmClass<'_> {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        writer.iad(self.comm_if, 2, USB_CLASS_CDC, CDC_SUBCLASS_NCM, 0, None)?;
+        writer.interface(self.comm_if, USB_CLASS_CDC, CDC_SUBCLASS_NCM, 0)?;
+        // CDC 1.10
+        writer.write(CS_INTERFACE, &[CDC_TYPE_HEADER, 0x10, 0x01])?;
+        writer.write(
+            CS_INTERFACE,
+            &[CDC_TYPE_UNION, self.comm_if.into(), self.data_if.into()],
+        )?;
+        // No statistics, a 1514 byte segment size, no multicast
+        // filters, and no power filters.
+        let segment = (MAX_FRAME_LEN as u16).to_le_bytes();
+        writer.write(
+            CS_INTERFACE,
+            &[
+                CDC_TYPE_ETHERNET,
+                self.mac_address.into(),
+                0,
+                0,
+                0,
+                0,
+                segment[0],
+                segment[1],
+                0,
+                0,
+                0,
+            ],
+        )?;
+        // NCM 1.0, and no optional requests.
+        writer.write(CS_INTERFACE, &[CDC_TYPE_NCM, 0x00, 0x01, 0x00])?;
+        writer.endpoint(&self.notify_ep)?;
+
+        // The host selects the second alternate setting to
+        // enable the network.
+        writer.interface_alt(
+            self.data_if,
+            0,
+            USB_CLASS_CDC_DATA,
+            0,
+            CDC_PROTOCOL_NTB,
+            None,
+        )?;
+        writer.interface_alt(
+            self.data_if,
+            DATA_ALT_ENABLED,
+            USB_CLASS_CDC_DATA,
+            0,
+            CDC_PROTOCOL_NTB,
+            None,
+        )?;
+        self.read_ep.describe(writer)?;
+        self.write_ep.describe(writer)?;
+        Ok(())
+    }
+
+    fn get_string(&self, index: StringIndex, _: LangID) -> Option<&str> {
+        if index == self.mac_address {
+            core::str::from_utf8(&self.mac_string).ok()
+        } else {
+            None
+        }
+    }
+
+    fn reset(&mut self) {
+        self.data_alt = 0;
+        self.notify = Notify::None;
+        self.notify_busy = false;
+        self.ntb_input_size = NTB_MAX_LEN as u32;
+        self.rx.clear();
+        self.tx.clear();
+        self.tx_busy = false;
+        self.data_reset = false;
+    }
+
+    fn get_alt_setting(&mut self, interface: InterfaceNumber) -> Option<u8> {
+        if interface == self.data_if {
+            Some(self.data_alt)
+        } else {
+            None
+        }
+    }
+
+    fn set_alt_setting(&mut self, interface: InterfaceNumber, alternative: u8) -> bool {
+        if interface != self.data_if || alternative > DATA_ALT_ENABLED {
+            return false;
+        }
+        self.data_alt = alternative;
+        self.rx.clear();
+        self.tx.clear();
+        self.tx_busy = false;
+        // The endpoints may still hold the previous setting's transfers.
+        self.data_reset = true;
+        if alternative == DATA_ALT_ENABLED {
+            self.notify = Notify::Connection;
+            self.push_notify();
+        } else {
+            self.notify = Notify::None;
+        }
+        true
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<Bus>) {
+        let req = *xfer.request();
+        if !self.is_recipient(&req) {
+            return;
+        }
+        match req.request {
+            GET_NTB_PARAMETERS => xfer.accept_with(&NTB_PARAMETERS).ok(),
+            GET_NTB_INPUT_SIZE => xfer.accept_with(&self.ntb_input_size.to_le_bytes()).ok(),
+            _ => xfer.reject().ok(),
+        };
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<Bus>) {
+        let req = *xfer.request();
+        if !self.is_recipient(&req) {
+            return;
+        }
+        match req.request {
+            // We send every frame to the host; there's nothing to filter.
+            SET_ETHERNET_PACKET_FILTER => xfer.accept().ok(),
+            SET_NTB_INPUT_SIZE if xfer.data().len() >= 4 => {
+                self.ntb_input_size = read_u32(xfer.data(), 0);
+                xfer.accept().ok()
+            }
+            _ => xfer.reject().ok(),
+        };
+    }
+
+    fn endpoint_out(&mut self, addr: EndpointAddress) {
+        // Until the reset, the packet is from the previous setting.
+        if addr == self.read_ep.address() && !self.data_reset {
+            self.pull_rx();
+        }
+    }
+
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        if addr == self.write_ep.address() {
+            self.tx_busy = false;
+            self.push_tx();
+        } else if addr == self.notify_ep.address() {
+            self.notify_busy = false;
+            self.push_notify();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        host_mac_address, mac_string, parse_nth, read_u16, Receiver, Transmitter, DATAGRAM_INDEX,
+        NTH16_LEN,
+    };
+
+    /// Build an NTB16 from the host with one NDP, using the given
+    /// (index, length) entries, and datagram bytes starting at 32
+    fn host_ntb(entries: &[(u16, u16)], data: &[u8]) -> [u8; 256] {
+        let mut ntb = [0; 256];
+        ntb[..4].copy_from_slice(b"NCMH");
+        ntb[4..6].copy_from_slice(&12u16.to_le_bytes());
+        // The NDP follows the data.
+        let ndp = (32 + data.len() + 3) & !3;
+        ntb[10..12].copy_from_slice(&(ndp as u16).to_le_bytes());
+        ntb[32..32 + data.len()].copy_from_slice(data);
+        ntb[ndp..ndp + 4].copy_from_slice(b"NCM0");
+        let ndp_len = (8 + 4 * (entries.len() + 1)) as u16;
+        ntb[ndp + 4..ndp + 6].copy_from_slice(&ndp_len.to_le_bytes());
+        for (i, (index, len)) in entries.iter().enumerate() {
+            let entry = ndp + 8 + 4 * i;
+            ntb[entry..entry + 2].copy_from_slice(&index.to_le_bytes());
+            ntb[entry + 2..entry + 4].copy_from_slice(&len.to_le_bytes());
+        }
+        // Cover the NDP in the block length.
+        let block_len = (ndp + ndp_len as usize) as u16;
+        ntb[8..10].copy_from_slice(&block_len.to_le_bytes());
+        ntb
+    }
+
+    #[test]
+    fn transmit_one_datagram() {
+        let mut tx: Transmitter<2048> = Transmitter::new();
+        let frame = [0x5A; 100];
+        assert!(tx.load(&frame));
+        assert!(!tx.load(&frame));
+
+        let ntb = tx.next_packet(512).unwrap().to_vec();
+        // The packet doesn't change until it's sent.
+        assert_eq!(tx.next_packet(512).unwrap(), &ntb[..]);
+        tx.advance(512);
+        assert_eq!(ntb.len(), DATAGRAM_INDEX + 100);
+        assert_eq!(&ntb[..4], b"NCMH");
+        assert_eq!(read_u16(&ntb, 6), 0);
+        let (block_len, ndp) = parse_nth(&ntb).unwrap();
+        assert_eq!((block_len, ndp), (ntb.len(), NTH16_LEN));
+        assert_eq!(&ntb[12..16], b"NCM0");
+        assert_eq!(read_u16(&ntb, 20) as usize, DATAGRAM_INDEX);
+        assert_eq!(read_u16(&ntb, 22), 100);
+        assert_eq!(&ntb[DATAGRAM_INDEX..], &frame[..]);
+
+        assert_eq!(tx.next_packet(512), None);
+        assert!(tx.is_idle());
+        assert!(tx.load(&frame));
+        assert_eq!(read_u16(tx.next_packet(512).unwrap(), 6), 1);
+    }
+
+    #[test]
+    fn transmit_packets_and_zlp() {
+        let mut tx: Transmitter<2048> = Transmitter::new();
+        // Exactly two full-speed packets.
+        assert!(tx.load(&[1; 128 - DATAGRAM_INDEX]));
+        for &len in [64, 64, 0].iter() {
+            assert_eq!(tx.next_packet(64).map(|p| p.len()), Some(len));
+            tx.advance(64);
+        }
+        assert_eq!(tx.next_packet(64), None);
+
+        // Datagrams that are too large, or empty, aren't sent.
+        assert!(!tx.load(&[]));
+        assert!(!tx.load(&[0; 2048]));
+        assert!(tx.is_idle());
+    }
+
+    #[test]
+    fn receive_datagrams() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        let ntb = host_ntb(&[(32, 4), (36, 6), (0, 0)], &data);
+        let block_len = read_u16(&ntb, 8) as usize;
+
+        let mut rx: Receiver<2048> = Receiver::new();
+        let mut frame = [0; 16];
+        assert_eq!(rx.next(&mut frame), None);
+
+        // Split across two full-speed packets.
+        rx.space().unwrap()[..64].copy_from_slice(&ntb[..64]);
+        rx.filled(64, 64);
+        assert_eq!(rx.next(&mut frame), None);
+        let rest = block_len - 64;
+        rx.space().unwrap()[..rest].copy_from_slice(&ntb[64..block_len]);
+        rx.filled(rest, 64);
+        assert!(rx.space().is_none());
+
+        assert_eq!(rx.next(&mut frame), Some(4));
+        assert_eq!(&frame[..4], &[1, 2, 3, 4]);
+        assert_eq!(rx.next(&mut frame), Some(6));
+        assert_eq!(&frame[..6], &[5, 6, 7, 8, 9, 10]);
+        assert_eq!(rx.next(&mut frame), None);
+        assert!(rx.space().is_some());
+    }
+
+    #[test]
+    fn receive_drops_invalid() {
+        let data = [0xAA; 20];
+        let mut rx: Receiver<2048> = Receiver::new();
+        let mut frame = [0; 8];
+
+        // Datagrams that are outside the block, or too large, are dropped.
+        let ntb = host_ntb(&[(32, 200), (32, 20), (32, 8), (0, 0)], &data);
+        let block_len = read_u16(&ntb, 8) as usize;
+        rx.space().unwrap()[..block_len].copy_from_slice(&ntb[..block_len]);
+        rx.filled(block_len, 512);
+        assert_eq!(rx.next(&mut frame), Some(8));
+        assert_eq!(rx.next(&mut frame), None);
+
+        // A bad signature drops the NTB.
+        let mut ntb = host_ntb(&[(32, 4), (0, 0)], &data);
+        ntb[0] = b'X';
+        rx.space().unwrap()[..64].copy_from_slice(&ntb[..64]);
+        rx.filled(64, 512);
+        assert!(rx.space().is_some());
+        assert_eq!(rx.next(&mut frame), None);
+
+        // So does a truncated NTB.
+        let ntb = host_ntb(&[(32, 4), (0, 0)], &data);
+        rx.space().unwrap()[..30].copy_from_slice(&ntb[..30]);
+        rx.filled(30, 512);
+        assert_eq!(rx.next(&mut frame), None);
+
+        // An NDP that points to itself ends the NTB.
+        let mut ntb = host_ntb(&[(32, 4), (0, 0)], &data);
+        let ndp = read_u16(&ntb, 10);
+        let next = ndp as usize + 6;
+        ntb[next..next + 2].copy_from_slice(&ndp.to_le_bytes());
+        let block_len = read_u16(&ntb, 8) as usize;
+        rx.space().unwrap()[..block_len].copy_from_slice(&ntb[..block_len]);
+        rx.filled(block_len, 512);
+        assert_eq!(rx.next(&mut frame), Some(4));
+        assert_eq!(rx.next(&mut frame), None);
+    }
+
+    #[test]
+    fn mac_addresses() {
+        let mac = [0x04, 0xE9, 0xE5, 0x01, 0x23, 0xAB];
+        let host = host_mac_address(mac);
+        assert_eq!(host, [0x06, 0xE9, 0xE5, 0x01, 0x23, 0xAB]);
+        assert_eq!(&mac_string(host), b"06E9E50123AB");
+    }
+}
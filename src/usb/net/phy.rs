@@ -0,0 +1,85 @@
+//! A smoltcp network device over the USB network interface
+
+use super::{Net, MAX_FRAME_LEN};
+use smoltcp::phy::{self, DeviceCapabilities, Medium};
+use smoltcp::time::Instant;
+
+/// Receives an Ethernet frame
+///
+/// The token holds the frame, which [`Net`] already took from the USB
+/// interface.
+pub struct RxToken {
+    frame: [u8; MAX_FRAME_LEN],
+    len: usize,
+}
+
+impl phy::RxToken for RxToken {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(&mut self.frame[..self.len])
+    }
+}
+
+/// Sends an Ethernet frame
+pub struct TxToken<'a>(&'a mut Net);
+
+impl phy::TxToken for TxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut frame = [0; MAX_FRAME_LEN];
+        let len = len.min(MAX_FRAME_LEN);
+        let result = f(&mut frame[..len]);
+        // The device only hands out tokens when it can send. If the host
+        // disconnected since then, the frame is lost, like it would be
+        // on a wire.
+        self.0.send(&frame[..len]).ok();
+        result
+    }
+}
+
+/// `Net` is a smoltcp Ethernet device
+///
+/// The device only receives a frame when it can also send a frame, so that
+/// smoltcp can always reply. Call [`poll`](crate::usb::poll()) between
+/// smoltcp polls to move frames to and from the host.
+impl phy::Device for Net {
+    type RxToken<'a>
+        = RxToken
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = TxToken<'a>
+    where
+        Self: 'a;
+
+    fn receive(&mut self, _: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        if !self.can_send() {
+            return None;
+        }
+        let mut frame = [0; MAX_FRAME_LEN];
+        match Net::receive(self, &mut frame) {
+            Ok(Some(len)) => Some((RxToken { frame, len }, TxToken(self))),
+            _ => None,
+        }
+    }
+
+    fn transmit(&mut self, _: Instant) -> Option<Self::TxToken<'_>> {
+        if self.can_send() {
+            Some(TxToken(self))
+        } else {
+            None
+        }
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut capabilities = DeviceCapabilities::default();
+        capabilities.medium = Medium::Ethernet;
+        capabilities.max_transmission_unit = MAX_FRAME_LEN;
+        capabilities.max_burst_size = Some(1);
+        capabilities
+    }
+}
@@ -0,0 +1,242 @@
+//! Endpoint queue heads (dQH) and transfer descriptors (dTD)
+//!
+//! The USB controller finds each endpoint's queue head in a list, starting
+//! at ENDPTLISTADDR. A queue head points to a transfer descriptor, and the
+//! transfer descriptor points to the endpoint's data buffer.
+//!
+//! Our driver schedules at most one transfer descriptor per endpoint.
+//! Software and the controller share these structures, so all accesses
+//! are volatile.
+
+use core::ptr;
+
+/// The number of endpoints supported by the USB controller
+pub const MAX_ENDPOINTS: usize = 8;
+
+/// The number of queue heads; one for each endpoint direction
+pub const QH_COUNT: usize = MAX_ENDPOINTS * 2;
+
+/// Terminate bit for a transfer descriptor's next pointer
+const TERMINATE: u32 = 1;
+
+const TOKEN_ACTIVE: u32 = 1 << 7;
+const TOKEN_IOC: u32 = 1 << 15;
+const TOKEN_TOTAL_BYTES_SHIFT: u32 = 16;
+const TOKEN_TOTAL_BYTES_MASK: u32 = 0x7FFF;
+
+/// Interrupt on setup
+const CAPABILITIES_IOS: u32 = 1 << 15;
+const CAPABILITIES_MAX_PACKET_LEN_SHIFT: u32 = 16;
+/// Zero length termination select. When set, the controller does not
+/// send a zero length packet after a transfer that's a multiple of the
+/// max packet size. `usb-device` classes send their own ZLPs.
+const CAPABILITIES_ZLT: u32 = 1 << 29;
+const CAPABILITIES_MULT_SHIFT: u32 = 30;
+
+/// A transfer descriptor
+///
+/// See section 42.5.6.3 of the i.MX RT1060 reference manual.
+#[repr(C, align(32))]
+pub struct Td {
+    next: u32,
+    token: u32,
+    buffers: [u32; 5],
+    _reserved: u32,
+}
+
+impl Td {
+    pub const fn new() -> Self {
+        Td {
+            next: TERMINATE,
+            token: 0,
+            buffers: [0; 5],
+            _reserved: 0,
+        }
+    }
+
+    /// Prepare a transfer of `len` bytes from, or into, `buffer`
+    ///
+    /// The transfer is active, and it interrupts on completion.
+    pub fn prepare(&mut self, buffer: *const u8, len: usize) {
+        let addr = buffer as usize as u32;
+        let buffers = [
+            addr,
+            (addr & !0xFFF).wrapping_add(0x1000),
+            (addr & !0xFFF).wrapping_add(0x2000),
+            (addr & !0xFFF).wrapping_add(0x3000),
+            (addr & !0xFFF).wrapping_add(0x4000),
+        ];
+        unsafe {
+            ptr::write_volatile(&mut self.next, TERMINATE);
+            ptr::write_volatile(&mut self.buffers, buffers);
+            ptr::write_volatile(
+                &mut self.token,
+                ((len as u32) << TOKEN_TOTAL_BYTES_SHIFT) | TOKEN_IOC | TOKEN_ACTIVE,
+            );
+        }
+    }
+
+    /// Mark the transfer inactive
+    ///
+    /// Use this after flushing the endpoint.
+    pub fn clear(&mut self) {
+        unsafe { ptr::write_volatile(&mut self.token, 0) };
+    }
+
+    fn token(&self) -> u32 {
+        unsafe { ptr::read_volatile(&self.token) }
+    }
+
+    /// Returns `true` if the controller still owns this transfer
+    pub fn is_active(&self) -> bool {
+        self.token() & TOKEN_ACTIVE != 0
+    }
+
+    /// Returns the number of bytes that were not transferred
+    pub fn remaining(&self) -> usize {
+        ((self.token() >> TOKEN_TOTAL_BYTES_SHIFT) & TOKEN_TOTAL_BYTES_MASK) as usize
+    }
+}
+
+/// An endpoint queue head
+///
+/// See section 42.5.6.2 of the i.MX RT1060 reference manual.
+#[repr(C, align(64))]
+pub struct Qh {
+    capabilities: u32,
+    current: u32,
+    /// The controller's working copy of the current transfer descriptor.
+    /// It has the same layout as a `Td`, without the alignment.
+    overlay: [u32; 8],
+    setup: [u32; 2],
+    _reserved: [u32; 4],
+}
+
+impl Qh {
+    pub const fn new() -> Self {
+        Qh {
+            capabilities: 0,
+            current: 0,
+            overlay: [TERMINATE, 0, 0, 0, 0, 0, 0, 0],
+            setup: [0; 2],
+            _reserved: [0; 4],
+        }
+    }
+
+    /// Set the endpoint's max packet length
+    ///
+    /// `interrupt_on_setup` should only be set for the control OUT endpoint.
+    /// `mult` is only meaningful for isochronous endpoints; otherwise, use zero.
+    pub fn configure(&mut self, max_packet_len: u16, interrupt_on_setup: bool, mult: u32) {
+        let mut capabilities = ((max_packet_len as u32) << CAPABILITIES_MAX_PACKET_LEN_SHIFT)
+            | CAPABILITIES_ZLT
+            | (mult << CAPABILITIES_MULT_SHIFT);
+        if interrupt_on_setup {
+            capabilities |= CAPABILITIES_IOS;
+        }
+        unsafe {
+            ptr::write_volatile(&mut self.capabilities, capabilities);
+            ptr::write_volatile(&mut self.current, 0);
+            ptr::write_volatile(&mut self.overlay, [TERMINATE, 0, 0, 0, 0, 0, 0, 0]);
+        }
+    }
+
+    /// Point the queue head at `td`, so the controller will execute it
+    /// once the endpoint is primed
+    pub fn attach(&mut self, td: &Td) {
+        unsafe {
+            ptr::write_volatile(&mut self.overlay[0], td as *const Td as usize as u32);
+            ptr::write_volatile(&mut self.overlay[1], 0);
+        }
+    }
+
+    /// Read the setup packet
+    ///
+    /// Only valid for the control OUT queue head. The caller is responsible for
+    /// the setup tripwire.
+    pub fn setup(&self) -> [u32; 2] {
+        unsafe { ptr::read_volatile(&self.setup) }
+    }
+}
+
+/// All of the queue heads, in the order expected by the controller
+///
+/// OUT queue heads have an even index, and IN queue heads have an odd index.
+#[repr(C, align(2048))]
+pub struct QueueHeads(pub [Qh; QH_COUNT]);
+
+impl QueueHeads {
+    pub const fn new() -> Self {
+        QueueHeads([
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+            Qh::new(),
+        ])
+    }
+}
+
+/// One transfer descriptor for each queue head
+pub struct TransferDescriptors(pub [Td; QH_COUNT]);
+
+impl TransferDescriptors {
+    pub const fn new() -> Self {
+        TransferDescriptors([
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+            Td::new(),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Qh, QueueHeads, Td};
+    use core::mem::{align_of, size_of};
+
+    #[test]
+    fn layout() {
+        assert_eq!(size_of::<Td>(), 32);
+        assert_eq!(align_of::<Td>(), 32);
+        assert_eq!(size_of::<Qh>(), 64);
+        assert_eq!(size_of::<QueueHeads>(), 2048);
+        assert_eq!(align_of::<QueueHeads>(), 2048);
+    }
+
+    #[test]
+    fn prepare_transfer() {
+        let mut td = Td::new();
+        assert!(!td.is_active());
+        let buffer = [0u8; 64];
+        td.prepare(buffer.as_ptr(), 64);
+        assert!(td.is_active());
+        assert_eq!(td.remaining(), 64);
+        td.clear();
+        assert!(!td.is_active());
+    }
+}
@@ -0,0 +1,133 @@
+//! USB1, USBPHY1, and USB PLL registers
+//!
+//! We only define the registers, and register fields, used by the USB
+//! driver. See the i.MX RT1060 reference manual for more information.
+
+#![allow(dead_code)] // Not every field is used.
+
+const USB1: usize = 0x402E_0000;
+
+pub const USBCMD: *mut u32 = (USB1 + 0x140) as *mut u32;
+pub const USBSTS: *mut u32 = (USB1 + 0x144) as *mut u32;
+pub const USBINTR: *mut u32 = (USB1 + 0x148) as *mut u32;
+pub const DEVICEADDR: *mut u32 = (USB1 + 0x154) as *mut u32;
+pub const ENDPTLISTADDR: *mut u32 = (USB1 + 0x158) as *mut u32;
+pub const BURSTSIZE: *mut u32 = (USB1 + 0x160) as *mut u32;
+pub const PORTSC1: *mut u32 = (USB1 + 0x184) as *mut u32;
+pub const USBMODE: *mut u32 = (USB1 + 0x1A8) as *mut u32;
+pub const ENDPTSETUPSTAT: *mut u32 = (USB1 + 0x1AC) as *mut u32;
+pub const ENDPTPRIME: *mut u32 = (USB1 + 0x1B0) as *mut u32;
+pub const ENDPTFLUSH: *mut u32 = (USB1 + 0x1B4) as *mut u32;
+pub const ENDPTSTAT: *mut u32 = (USB1 + 0x1B8) as *mut u32;
+pub const ENDPTCOMPLETE: *mut u32 = (USB1 + 0x1BC) as *mut u32;
+
+/// Returns the ENDPTCTRL register for endpoint `n`
+pub const fn endptctrl(n: usize) -> *mut u32 {
+    (USB1 + 0x1C0 + 4 * n) as *mut u32
+}
+
+pub const USBCMD_RS: u32 = 1 << 0;
+pub const USBCMD_RST: u32 = 1 << 1;
+pub const USBCMD_SUTW: u32 = 1 << 13;
+pub const USBCMD_ATDTW: u32 = 1 << 14;
+
+pub const USBSTS_UI: u32 = 1 << 0;
+pub const USBSTS_UEI: u32 = 1 << 1;
+pub const USBSTS_PCI: u32 = 1 << 2;
+pub const USBSTS_URI: u32 = 1 << 6;
+pub const USBSTS_SRI: u32 = 1 << 7;
+pub const USBSTS_SLI: u32 = 1 << 8;
+
+pub const USBINTR_UE: u32 = 1 << 0;
+pub const USBINTR_UEE: u32 = 1 << 1;
+pub const USBINTR_PCE: u32 = 1 << 2;
+pub const USBINTR_URE: u32 = 1 << 6;
+pub const USBINTR_SRE: u32 = 1 << 7;
+pub const USBINTR_SLE: u32 = 1 << 8;
+
+pub const DEVICEADDR_USBADRA: u32 = 1 << 24;
+pub const DEVICEADDR_USBADR_SHIFT: u32 = 25;
+
+pub const PORTSC1_SUSP: u32 = 1 << 7;
+pub const PORTSC1_PR: u32 = 1 << 8;
+pub const PORTSC1_HSP: u32 = 1 << 9;
+
+pub const USBMODE_CM_DEVICE: u32 = 0b10;
+pub const USBMODE_CM_MASK: u32 = 0b11;
+pub const USBMODE_SLOM: u32 = 1 << 3;
+
+pub const ENDPTCTRL_RXS: u32 = 1 << 0;
+pub const ENDPTCTRL_RXT_SHIFT: u32 = 2;
+pub const ENDPTCTRL_RXR: u32 = 1 << 6;
+pub const ENDPTCTRL_RXE: u32 = 1 << 7;
+pub const ENDPTCTRL_TXS: u32 = 1 << 16;
+pub const ENDPTCTRL_TXT_SHIFT: u32 = 18;
+pub const ENDPTCTRL_TXR: u32 = 1 << 22;
+pub const ENDPTCTRL_TXE: u32 = 1 << 23;
+
+const USBPHY1: usize = 0x400D_9000;
+
+pub const USBPHY1_PWD: *mut u32 = USBPHY1 as *mut u32;
+pub const USBPHY1_CTRL_SET: *mut u32 = (USBPHY1 + 0x34) as *mut u32;
+pub const USBPHY1_CTRL_CLR: *mut u32 = (USBPHY1 + 0x38) as *mut u32;
+
+/// Power-down bits for the PHY's receivers and transmitters
+pub const USBPHY_PWD_MASK: u32 = 0x001E_1C00;
+pub const USBPHY_CTRL_CLKGATE: u32 = 1 << 30;
+pub const USBPHY_CTRL_SFTRST: u32 = 1 << 31;
+
+const CCM_ANALOG: usize = 0x400D_8000;
+
+/// Part of the USB analog registers, which share the CCM analog block
+pub const USB1_VBUS_DETECT_STAT: *const u32 = (CCM_ANALOG + 0x1C0) as *const u32;
+pub const USB1_VBUS_DETECT_STAT_VBUS_VALID: u32 = 1 << 3;
+
+pub const PLL_USB1: *const u32 = (CCM_ANALOG + 0x10) as *const u32;
+pub const PLL_USB1_SET: *mut u32 = (CCM_ANALOG + 0x14) as *mut u32;
+pub const PLL_USB1_CLR: *mut u32 = (CCM_ANALOG + 0x18) as *mut u32;
+
+pub const PLL_USB1_DIV_SELECT: u32 = 1 << 1;
+pub const PLL_USB1_EN_USB_CLKS: u32 = 1 << 6;
+pub const PLL_USB1_POWER: u32 = 1 << 12;
+pub const PLL_USB1_ENABLE: u32 = 1 << 13;
+pub const PLL_USB1_BYPASS_CLK_SRC_MASK: u32 = 0b11 << 14;
+pub const PLL_USB1_BYPASS: u32 = 1 << 16;
+pub const PLL_USB1_LOCK: u32 = 1 << 31;
+
+pub const PMU_REG_3P0: *mut u32 = (CCM_ANALOG + 0x120) as *mut u32;
+/// OUTPUT_TRG = 0x0F, BO_OFFSET = 6, ENABLE_LINREG
+pub const PMU_REG_3P0_USB: u32 = (0x0F << 8) | (6 << 4) | 1;
+
+pub const CCM_CCGR6: *mut u32 = 0x400F_C080 as *mut u32;
+pub const CCGR6_USBOH3_ON: u32 = 0b11;
+
+/// Read a register
+///
+/// # Safety
+///
+/// `reg` must be a valid register address.
+#[inline(always)]
+pub unsafe fn read(reg: *const u32) -> u32 {
+    reg.read_volatile()
+}
+
+/// Write a register
+///
+/// # Safety
+///
+/// `reg` must be a valid register address.
+#[inline(always)]
+pub unsafe fn write(reg: *mut u32, value: u32) {
+    reg.write_volatile(value)
+}
+
+/// Read-modify-write a register
+///
+/// # Safety
+///
+/// `reg` must be a valid register address. Caller must prevent
+/// a concurrent modification of the register.
+#[inline(always)]
+pub unsafe fn modify(reg: *mut u32, f: impl FnOnce(u32) -> u32) {
+    reg.write_volatile(f(reg.read_volatile()))
+}
@@ -0,0 +1,105 @@
+//! A byte ring buffer for USB serial data
+
+/// A fixed-capacity FIFO of bytes
+pub struct Ring<const N: usize> {
+    buffer: [u8; N],
+    /// Index of the next byte to read
+    head: usize,
+    /// Number of bytes in the buffer
+    len: usize,
+}
+
+impl<const N: usize> Ring<N> {
+    pub const fn new() -> Self {
+        Ring {
+            buffer: [0; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of bytes that can be popped
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes that can be pushed
+    pub fn free(&self) -> usize {
+        N - self.len
+    }
+
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    /// Push as many bytes as possible from `data`
+    ///
+    /// Returns the number of bytes pushed.
+    pub fn push(&mut self, data: &[u8]) -> usize {
+        let count = data.len().min(self.free());
+        let mut tail = (self.head + self.len) % N;
+        for &byte in &data[..count] {
+            self.buffer[tail] = byte;
+            tail = (tail + 1) % N;
+        }
+        self.len += count;
+        count
+    }
+
+    /// Pop as many bytes as possible into `data`
+    ///
+    /// Returns the number of bytes popped.
+    pub fn pop(&mut self, data: &mut [u8]) -> usize {
+        let count = data.len().min(self.len);
+        for byte in &mut data[..count] {
+            *byte = self.buffer[self.head];
+            self.head = (self.head + 1) % N;
+        }
+        self.len -= count;
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ring;
+
+    #[test]
+    fn push_pop() {
+        let mut ring = Ring::<8>::new();
+        assert_eq!(ring.free(), 8);
+        assert_eq!(ring.push(b"hello"), 5);
+        assert_eq!(ring.free(), 3);
+
+        let mut out = [0; 3];
+        assert_eq!(ring.pop(&mut out), 3);
+        assert_eq!(&out, b"hel");
+        assert_eq!(ring.free(), 6);
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut ring = Ring::<8>::new();
+        let mut out = [0; 8];
+        assert_eq!(ring.push(b"abcdef"), 6);
+        assert_eq!(ring.pop(&mut out[..4]), 4);
+        assert_eq!(ring.push(b"ghijklmn"), 6);
+        assert_eq!(ring.free(), 0);
+        assert_eq!(ring.pop(&mut out), 8);
+        assert_eq!(&out, b"efghijkl");
+        assert_eq!(ring.pop(&mut out), 0);
+    }
+
+    #[test]
+    fn clear() {
+        let mut ring = Ring::<4>::new();
+        ring.push(b"abcd");
+        ring.clear();
+        assert_eq!(ring.free(), 4);
+    }
+}
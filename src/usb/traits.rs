@@ -0,0 +1,115 @@
+//! `embedded-io` and `embedded-hal` implementations for the USB serial port
+//!
+//! The `embedded-io` traits block, so they need a USB interrupt handler
+//! that calls `poll`. Otherwise, a blocking read or write never finishes.
+//! The `embedded-hal` traits don't block; they return `WouldBlock`.
+
+use super::{with_port, Error, Reader, Writer};
+
+impl embedded_io::Error for Error {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            Error::NotConfigured => embedded_io::ErrorKind::NotConnected,
+            Error::Io => embedded_io::ErrorKind::Other,
+            Error::SetLogger
+            | Error::WrongInstance
+            | Error::InvalidFilters
+            | Error::NoInterfaces => embedded_io::ErrorKind::Other,
+        }
+    }
+}
+
+impl embedded_io::ErrorType for Reader {
+    type Error = Error;
+}
+
+impl embedded_io::Read for Reader {
+    /// Blocks until there's at least one byte to read
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let count = Reader::read(self, &mut *buffer)?;
+            if count > 0 {
+                return Ok(count);
+            }
+        }
+    }
+}
+
+impl embedded_io::ReadReady for Reader {
+    fn read_ready(&mut self) -> Result<bool, Error> {
+        with_port(self.port, |serial| serial.read_available() > 0)
+    }
+}
+
+impl embedded_io::ErrorType for Writer {
+    type Error = Error;
+}
+
+impl embedded_io::Write for Writer {
+    /// Blocks until there's space for at least one byte
+    fn write(&mut self, buffer: &[u8]) -> Result<usize, Error> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let count = Writer::write(self, buffer)?;
+            if count > 0 {
+                return Ok(count);
+            }
+        }
+    }
+
+    /// Blocks until the host receives all written data
+    fn flush(&mut self) -> Result<(), Error> {
+        while with_port(self.port, |serial| {
+            serial.flush();
+            serial.write_pending()
+        })? {}
+        Ok(())
+    }
+}
+
+impl embedded_io::WriteReady for Writer {
+    fn write_ready(&mut self) -> Result<bool, Error> {
+        with_port(self.port, |serial| serial.write_available() > 0)
+    }
+}
+
+impl embedded_hal::serial::Read<u8> for Reader {
+    type Error = Error;
+
+    fn read(&mut self) -> nb::Result<u8, Error> {
+        let mut byte = [0; 1];
+        match Reader::read(self, &mut byte)? {
+            0 => Err(nb::Error::WouldBlock),
+            _ => Ok(byte[0]),
+        }
+    }
+}
+
+impl embedded_hal::serial::Write<u8> for Writer {
+    type Error = Error;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Error> {
+        match Writer::write(self, [byte])? {
+            0 => Err(nb::Error::WouldBlock),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns `WouldBlock` until the host receives all written data
+    fn flush(&mut self) -> nb::Result<(), Error> {
+        let pending = with_port(self.port, |serial| {
+            serial.flush();
+            serial.write_pending()
+        })?;
+        if pending {
+            Err(nb::Error::WouldBlock)
+        } else {
+            Ok(())
+        }
+    }
+}
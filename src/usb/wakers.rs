@@ -0,0 +1,177 @@
+//! Async USB serial I/O
+//!
+//! Each port has a read waker and a write waker. A future registers its
+//! waker before it checks the serial port, so it can't miss a wakeup.
+//! `poll` wakes a port's reader when it receives data, and its writer when
+//! it finishes a transfer. `poll` wakes everything when the host configures,
+//! or deconfigures, the device.
+
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use cortex_m::interrupt::{self, Mutex};
+
+use super::{cdc, with_port, Error, Port, Reader, Writer};
+
+/// Holds the waker for one task
+struct WakerSlot(Mutex<RefCell<Option<Waker>>>);
+
+impl WakerSlot {
+    const fn new() -> Self {
+        WakerSlot(Mutex::new(RefCell::new(None)))
+    }
+
+    /// Replace the waker, unless it already wakes the same task
+    fn register(&self, waker: &Waker) {
+        interrupt::free(|cs| {
+            let mut slot = self.0.borrow(cs).borrow_mut();
+            match slot.as_ref() {
+                Some(registered) if registered.will_wake(waker) => {}
+                _ => *slot = Some(waker.clone()),
+            }
+        });
+    }
+
+    /// Wake the task, if there is one
+    fn wake(&self) {
+        if let Some(waker) = interrupt::free(|cs| self.0.borrow(cs).borrow_mut().take()) {
+            waker.wake();
+        }
+    }
+}
+
+/// Indexed by [`index`]
+static READERS: [WakerSlot; 2] = [WakerSlot::new(), WakerSlot::new()];
+/// Indexed by [`index`]
+static WRITERS: [WakerSlot; 2] = [WakerSlot::new(), WakerSlot::new()];
+
+fn index(port: Port) -> usize {
+    match port {
+        Port::Primary => 0,
+        Port::Secondary => 1,
+    }
+}
+
+/// Wake the tasks waiting on `port`'s events
+///
+/// `events` are the CDC event flags.
+pub(super) fn wake(port: Port, events: u32) {
+    if events & cdc::RX_COMPLETE != 0 {
+        READERS[index(port)].wake();
+    }
+    if events & cdc::TX_COMPLETE != 0 {
+        WRITERS[index(port)].wake();
+    }
+}
+
+/// Wake all tasks
+pub(super) fn wake_all() {
+    for slot in READERS.iter().chain(WRITERS.iter()) {
+        slot.wake();
+    }
+}
+
+/// A future that calls a function until it's ready
+struct PollFn<F>(F);
+
+impl<F> Unpin for PollFn<F> {}
+
+impl<T, F: FnMut(&mut Context<'_>) -> Poll<T>> Future for PollFn<F> {
+    type Output = T;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        (self.0)(cx)
+    }
+}
+
+/// Wait while `f` returns `None`, or while the device isn't configured
+async fn wait<T>(
+    slot: &WakerSlot,
+    mut f: impl FnMut() -> Result<Option<T>, Error>,
+) -> Result<T, Error> {
+    PollFn(|cx: &mut Context<'_>| {
+        slot.register(cx.waker());
+        match f() {
+            Ok(Some(value)) => Poll::Ready(Ok(value)),
+            Ok(None) | Err(Error::NotConfigured) => Poll::Pending,
+            Err(err) => Poll::Ready(Err(err)),
+        }
+    })
+    .await
+}
+
+impl Reader {
+    /// Read data from the host
+    ///
+    /// Waits until there's data, then returns the number of bytes read. Also
+    /// waits for the host to configure the device. Returns zero only if
+    /// `buffer` is empty.
+    ///
+    /// The future needs `poll` to make progress; call `poll` from the
+    /// `USB_OTG1` interrupt. The future works with any executor.
+    pub async fn read_async(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        let port = self.port;
+        wait(&READERS[index(port)], || {
+            with_port(port, |serial| {
+                Some(serial.read(buffer)).filter(|&count| count > 0)
+            })
+        })
+        .await
+    }
+}
+
+impl Writer {
+    /// Write data to the host
+    ///
+    /// Waits until there's space for some of `buffer`, then returns the number
+    /// of bytes written. Also waits for the host to configure the device.
+    /// Returns zero only if `buffer` is empty.
+    ///
+    /// See [`Reader::read_async`] for more information.
+    pub async fn write_async(&mut self, buffer: &[u8]) -> Result<usize, Error> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        let port = self.port;
+        wait(&WRITERS[index(port)], || {
+            with_port(port, |serial| {
+                Some(serial.write(buffer)).filter(|&count| count > 0)
+            })
+        })
+        .await
+    }
+
+    /// Write all of `buffer` to the host
+    ///
+    /// See [`write_async`](Writer::write_async) for more information.
+    pub async fn write_all_async(&mut self, mut buffer: &[u8]) -> Result<(), Error> {
+        while !buffer.is_empty() {
+            let count = self.write_async(buffer).await?;
+            buffer = &buffer[count..];
+        }
+        Ok(())
+    }
+
+    /// Wait until the host receives all written data
+    ///
+    /// See [`Reader::read_async`] for more information.
+    pub async fn flush_async(&mut self) -> Result<(), Error> {
+        let port = self.port;
+        wait(&WRITERS[index(port)], || {
+            with_port(port, |serial| {
+                serial.flush();
+                if serial.write_pending() {
+                    None
+                } else {
+                    Some(())
+                }
+            })
+        })
+        .await
+    }
+}
@@ -67,8 +67,8 @@ SECTIONS
         /* --------- */
         KEEP(*(.boot.start))        /* Shim reset handler for TCM init */
 
-        *(.flashmem);               /* Compatibility with USB stack */
-        *(.progmem);                /* Compat with USB stack */
+        *(.flashmem);               /* Compatibility with C libraries */
+        *(.progmem);                /* Compat with C libraries */
     } > FLASH
 
         /* The following are used to compute the FlexRAM banks for ITCM / DTCM */
@@ -76,6 +76,9 @@ SECTIONS
     __flexram_bank_config = 0xAAAAAAAA | ((1 << (__itcm_block_count * 2)) - 1);
     PROVIDE(__stack_start = ORIGIN(DTCM) + ((16 - __itcm_block_count) << 15));
 
+    /* Reserved at the top of RAM for the .retained section */
+    __retained_len = 1K;
+
     /* ## Sections in FLASH */
     /* ### Vector table */
     .vector_table : ALIGN(1024)
@@ -166,7 +169,7 @@ SECTIONS
 
     .dma (NOLOAD) :
     {
-        *(.dmabuffers); /* Compat with USB */
+        *(.dmabuffers); /* Compat with C libraries */
         . = ALIGN(16);
     } > RAM
 
@@ -174,10 +177,17 @@ SECTIONS
     {
         . = ALIGN(4);
         __sheap = .;
-        . = ORIGIN(RAM) + LENGTH(RAM);
+        . = ORIGIN(RAM) + LENGTH(RAM) - __retained_len;
         __eheap = .;
     } > RAM
 
+    /* Memory that survives a software reset. The runtime never initializes */
+    /* this section. Keep it at a fixed address so that it's found after reset. */
+    .retained ORIGIN(RAM) + LENGTH(RAM) - __retained_len (NOLOAD) :
+    {
+        KEEP(*(.retained .retained.*));
+    } > RAM
+
     /* ## .got */
     /* Dynamic relocations are unsupported. This section is only used to detect relocatable code in
         the input files and raise an error if relocatable code is found */
@@ -215,5 +225,8 @@ ERROR(cortex-m-rt): .bss is not 4-byte aligned");
 ASSERT(__stext % 4 == 0 && __etext % 4 == 0, "
 ERROR(cortex-m-rt): .text is not 4-byte aligned");
 
+ASSERT(SIZEOF(.retained) <= __retained_len, "
+ERROR(teensy4-bsp): .retained exceeds its reserved space");
+
 ASSERT(__sheap % 4 == 0, "
 ERROR(cortex-m-rt): start of heap is not 4-byte aligned");
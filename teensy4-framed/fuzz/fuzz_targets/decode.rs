@@ -0,0 +1,18 @@
+//! The decoder never panics, and never produces more data than it receives.
+//! Anything that decodes also re-encodes to the same bytes.
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use teensy4_framed::cobs;
+
+fuzz_target!(|data: &[u8]| {
+    let mut decoded = vec![0; data.len()];
+    if let Ok(len) = cobs::decode(data, &mut decoded) {
+        assert!(len <= data.len());
+        let mut encoded = vec![0; cobs::max_encoded_len(len)];
+        let encoded_len = cobs::encode(&decoded[..len], &mut encoded).unwrap();
+        let mut roundtrip = vec![0; encoded_len];
+        let roundtrip_len = cobs::decode(&encoded[..encoded_len], &mut roundtrip).unwrap();
+        assert_eq!(&roundtrip[..roundtrip_len], &decoded[..len]);
+    }
+});
@@ -0,0 +1,18 @@
+//! Any payload survives encoding, then decoding, with any checksum.
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use teensy4_framed::{decode_frame, encode_frame, max_frame_len, Checksum};
+
+fuzz_target!(|data: &[u8]| {
+    for &checksum in [Checksum::None, Checksum::Crc16, Checksum::Crc32].iter() {
+        let mut frame = vec![0; max_frame_len(data.len(), checksum)];
+        let len = encode_frame(data, checksum, &mut frame).unwrap();
+        assert_eq!(frame[len - 1], 0);
+        assert!(!frame[..len - 1].contains(&0));
+
+        let mut payload = vec![0; len];
+        let decoded = decode_frame(&frame[..len - 1], checksum, &mut payload).unwrap();
+        assert_eq!(&payload[..decoded], data);
+    }
+});
@@ -0,0 +1,202 @@
+//! Consistent overhead byte stuffing (COBS)
+//!
+//! COBS removes all zeros from the data, so that a zero can
+//! delimit frames. The encoded data is at most one byte larger
+//! than the data, plus one byte for every 254 bytes of data.
+
+use crate::Error;
+
+/// The largest block of non-zero bytes in the encoding
+const MAX_BLOCK: usize = 254;
+
+/// Returns the largest encoded size of `len` bytes, not including
+/// the frame delimiter
+pub const fn max_encoded_len(len: usize) -> usize {
+    len + len / MAX_BLOCK + 1
+}
+
+/// A streaming COBS encoder
+///
+/// `push` data bytes, then `finish` the frame. The encoder emits
+/// encoded blocks through the `emit` callbacks. The encoder doesn't
+/// emit the frame delimiter.
+pub struct Encoder {
+    /// The code byte, followed by up to 254 non-zero bytes
+    block: [u8; MAX_BLOCK + 1],
+    len: usize,
+}
+
+impl Encoder {
+    pub const fn new() -> Self {
+        Encoder {
+            block: [0; MAX_BLOCK + 1],
+            len: 0,
+        }
+    }
+
+    /// Encode one byte
+    pub fn push(&mut self, byte: u8, emit: &mut impl FnMut(&[u8])) {
+        if byte == 0 {
+            self.emit_block(emit);
+        } else {
+            self.len += 1;
+            self.block[self.len] = byte;
+            if self.len == MAX_BLOCK {
+                self.emit_block(emit);
+            }
+        }
+    }
+
+    /// Encode all bytes in `data`
+    pub fn extend(&mut self, data: &[u8], emit: &mut impl FnMut(&[u8])) {
+        for &byte in data {
+            self.push(byte, emit);
+        }
+    }
+
+    /// Emit the last block, and prepare for the next frame
+    pub fn finish(&mut self, emit: &mut impl FnMut(&[u8])) {
+        self.emit_block(emit);
+    }
+
+    fn emit_block(&mut self, emit: &mut impl FnMut(&[u8])) {
+        self.block[0] = (self.len + 1) as u8;
+        emit(&self.block[..=self.len]);
+        self.len = 0;
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Encoder::new()
+    }
+}
+
+/// Encode `data` into `out`, returning the size of the encoded data
+///
+/// `out` should be at least [`max_encoded_len`] bytes. The encoded data
+/// doesn't include the frame delimiter.
+pub fn encode(data: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+    let mut len = 0;
+    let mut overflow = false;
+    let mut emit = |block: &[u8]| {
+        match out.get_mut(len..len + block.len()) {
+            Some(dst) if !overflow => dst.copy_from_slice(block),
+            _ => overflow = true,
+        }
+        len += block.len();
+    };
+    let mut encoder = Encoder::new();
+    encoder.extend(data, &mut emit);
+    encoder.finish(&mut emit);
+    if overflow {
+        Err(Error::BufferTooSmall)
+    } else {
+        Ok(len)
+    }
+}
+
+/// Decode `encoded` into `out`, returning the size of the data
+///
+/// `encoded` should not include the frame delimiter. The data is never
+/// larger than the encoded data.
+pub fn decode(encoded: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+    let mut src = 0;
+    let mut dst = 0;
+    while src < encoded.len() {
+        let code = encoded[src] as usize;
+        if code == 0 {
+            return Err(Error::Malformed);
+        }
+        src += 1;
+        let block = encoded.get(src..src + code - 1).ok_or(Error::Malformed)?;
+        if block.contains(&0) {
+            return Err(Error::Malformed);
+        }
+        out.get_mut(dst..dst + block.len())
+            .ok_or(Error::BufferTooSmall)?
+            .copy_from_slice(block);
+        src += block.len();
+        dst += block.len();
+        // A short block implies a zero, unless it's the last block.
+        if code <= MAX_BLOCK && src < encoded.len() {
+            *out.get_mut(dst).ok_or(Error::BufferTooSmall)? = 0;
+            dst += 1;
+        }
+    }
+    Ok(dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, max_encoded_len};
+    use crate::Error;
+
+    fn roundtrip(data: &[u8], expected: &[u8]) {
+        let mut encoded = [0; 600];
+        let len = encode(data, &mut encoded).unwrap();
+        assert_eq!(&encoded[..len], expected);
+        assert!(len <= max_encoded_len(data.len()));
+        assert!(!encoded[..len].contains(&0));
+
+        let mut decoded = [0; 600];
+        let len = decode(&encoded[..len], &mut decoded).unwrap();
+        assert_eq!(&decoded[..len], data);
+    }
+
+    #[test]
+    fn examples() {
+        // From the COBS paper, and Wikipedia.
+        roundtrip(&[], &[0x01]);
+        roundtrip(&[0x00], &[0x01, 0x01]);
+        roundtrip(&[0x00, 0x00], &[0x01, 0x01, 0x01]);
+        roundtrip(&[0x11, 0x22, 0x00, 0x33], &[0x03, 0x11, 0x22, 0x02, 0x33]);
+        roundtrip(&[0x11, 0x22, 0x33, 0x44], &[0x05, 0x11, 0x22, 0x33, 0x44]);
+        roundtrip(&[0x11, 0x00, 0x00, 0x00], &[0x02, 0x11, 0x01, 0x01, 0x01]);
+    }
+
+    #[test]
+    fn long_blocks() {
+        let data: [u8; 254] = {
+            let mut data = [0; 254];
+            for (idx, byte) in data.iter_mut().enumerate() {
+                *byte = idx as u8 + 1;
+            }
+            data
+        };
+        let mut expected = [0; 256];
+        expected[0] = 0xFF;
+        expected[1..255].copy_from_slice(&data);
+        expected[255] = 0x01;
+        roundtrip(&data, &expected);
+
+        let mut data = [0x42; 600 / 2];
+        data[100] = 0;
+        let mut encoded = [0; 600];
+        let len = encode(&data, &mut encoded).unwrap();
+        let mut decoded = [0; 600];
+        assert_eq!(decode(&encoded[..len], &mut decoded), Ok(data.len()));
+        assert_eq!(&decoded[..data.len()], &data[..]);
+    }
+
+    #[test]
+    fn errors() {
+        let mut out = [0; 4];
+        assert_eq!(encode(&[1, 2, 3, 4], &mut out), Err(Error::BufferTooSmall));
+        assert_eq!(decode(&[0x05, 1, 2, 3, 4], &mut out), Ok(4));
+        // The trailing block implies a zero after the data.
+        assert_eq!(
+            decode(&[0x05, 1, 2, 3, 4, 0x01], &mut out),
+            Err(Error::BufferTooSmall)
+        );
+        assert_eq!(
+            decode(&[0x02, 1, 0x02, 2, 0x02, 3], &mut out),
+            Err(Error::BufferTooSmall)
+        );
+        // Zeros aren't allowed in the encoding.
+        assert_eq!(decode(&[0x00], &mut out), Err(Error::Malformed));
+        assert_eq!(decode(&[0x03, 1, 0], &mut out), Err(Error::Malformed));
+        // The block is longer than the data.
+        assert_eq!(decode(&[0x04, 1, 2], &mut out), Err(Error::Malformed));
+    }
+}
@@ -0,0 +1,59 @@
+//! Frame checksums
+//!
+//! These are bitwise implementations, which trade speed for code size.
+
+/// CRC-16/CCITT-FALSE
+///
+/// Polynomial `0x1021`, initial value `0xFFFF`, not reflected, no final XOR.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// CRC-32 (IEEE 802.3)
+///
+/// The checksum used by Ethernet, zlib, and PNG.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc16, crc32};
+
+    /// The standard check input
+    const CHECK: &[u8] = b"123456789";
+
+    #[test]
+    fn check_values() {
+        assert_eq!(crc16(CHECK), 0x29B1);
+        assert_eq!(crc32(CHECK), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(crc16(&[]), 0xFFFF);
+        assert_eq!(crc32(&[]), 0);
+    }
+}
@@ -0,0 +1,136 @@
+//! Frames over `std::io`
+
+use std::{
+    io::{self, Read, Write},
+    vec::Vec,
+};
+
+use crate::{decode_frame, max_frame_len, write_frame, Checksum, DELIMITER};
+
+/// Sends and receives frames over a byte stream
+///
+/// Wrap a serial port, or any other `Read + Write` type, to talk to a
+/// Teensy 4 that uses the BSP's `usb::framed` module. Use the same
+/// [`Checksum`] on both ends.
+///
+/// ```no_run
+/// use teensy4_framed::{Checksum, FramedPort};
+/// # fn open_serial_port() -> std::fs::File { unimplemented!() }
+///
+/// let mut port = FramedPort::new(open_serial_port(), Checksum::Crc32);
+/// port.send(b"ping")?;
+/// let response = port.recv()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct FramedPort<T> {
+    inner: T,
+    checksum: Checksum,
+    /// Received bytes that aren't yet part of a frame
+    received: Vec<u8>,
+}
+
+impl<T> FramedPort<T> {
+    pub fn new(inner: T, checksum: Checksum) -> Self {
+        FramedPort {
+            inner,
+            checksum,
+            received: Vec::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Write> FramedPort<T> {
+    /// Send one frame, then flush the stream
+    pub fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        let mut frame = Vec::with_capacity(max_frame_len(payload.len(), self.checksum));
+        write_frame(payload, self.checksum, |bytes| {
+            frame.extend_from_slice(bytes)
+        });
+        self.inner.write_all(&frame)?;
+        self.inner.flush()
+    }
+}
+
+impl<T: Read> FramedPort<T> {
+    /// Receive the next frame's payload
+    ///
+    /// Blocks until there's a frame, or until the stream returns an error.
+    /// Returns an `InvalidData` error if the frame is malformed, or if its
+    /// checksum doesn't match; the next call receives the next frame.
+    pub fn recv(&mut self) -> io::Result<Vec<u8>> {
+        loop {
+            if let Some(end) = self.received.iter().position(|&byte| byte == DELIMITER) {
+                let frame: Vec<u8> = self.received.drain(..=end).collect();
+                let encoded = &frame[..end];
+                if encoded.is_empty() {
+                    // Consecutive delimiters; there's no frame.
+                    continue;
+                }
+                let mut payload = std::vec![0; encoded.len()];
+                let len = decode_frame(encoded, self.checksum, &mut payload)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                payload.truncate(len);
+                return Ok(payload);
+            }
+
+            let mut buffer = [0; 1024];
+            let count = self.inner.read(&mut buffer)?;
+            if count == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.received.extend_from_slice(&buffer[..count]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FramedPort;
+    use crate::Checksum;
+    use std::io::{self, Cursor};
+
+    #[test]
+    fn send_recv() {
+        let mut port = FramedPort::new(Cursor::new(Vec::new()), Checksum::Crc16);
+        port.send(b"first").unwrap();
+        port.send(b"").unwrap();
+        port.send(b"\0third\0").unwrap();
+
+        let mut stream = port.into_inner();
+        stream.set_position(0);
+        let mut port = FramedPort::new(stream, Checksum::Crc16);
+        assert_eq!(port.recv().unwrap(), b"first");
+        assert_eq!(port.recv().unwrap(), b"");
+        assert_eq!(port.recv().unwrap(), b"\0third\0");
+        assert_eq!(
+            port.recv().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn resynchronize() {
+        let mut port = FramedPort::new(Cursor::new(Vec::new()), Checksum::Crc32);
+        port.send(b"lost").unwrap();
+        port.send(b"found").unwrap();
+
+        // Lose the start of the first frame.
+        let mut stream = port.into_inner();
+        stream.set_position(2);
+        let mut port = FramedPort::new(stream, Checksum::Crc32);
+        assert_eq!(port.recv().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(port.recv().unwrap(), b"found");
+    }
+}
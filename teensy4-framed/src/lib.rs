@@ -0,0 +1,218 @@
+//! COBS-framed packets for the Teensy 4's USB serial port
+//!
+//! A frame is a payload, followed by an optional checksum, encoded with
+//! [consistent overhead byte stuffing](cobs) (COBS), and terminated with a
+//! zero. Since the encoding never contains a zero, a reader can always find
+//! the start of the next frame, even after it loses data.
+//!
+//! The `teensy4-bsp` uses this crate to send and receive frames over USB
+//! serial; see its `usb::framed` module. Use this crate on the host to talk
+//! to your Teensy 4. Enable the `"std"` feature for [`FramedPort`], which
+//! frames any `std::io::Read + Write`, like a serial port.
+//!
+//! ```
+//! use teensy4_framed::{decode_frame, encode_frame, Checksum};
+//!
+//! let mut frame = [0; 32];
+//! let len = encode_frame(b"hello\0world", Checksum::Crc16, &mut frame).unwrap();
+//! assert_eq!(frame[len - 1], 0);
+//! assert!(!frame[..len - 1].contains(&0));
+//!
+//! let mut payload = [0; 32];
+//! let len = decode_frame(&frame[..len - 1], Checksum::Crc16, &mut payload).unwrap();
+//! assert_eq!(&payload[..len], b"hello\0world");
+//! ```
+//!
+//! # Features
+//!
+//! | Feature | Description                                       | Default feature? |
+//! | ------- | ------------------------------------------------- | ---------------- |
+//! | `std`   | Adds [`FramedPort`], for host-side communication  |                  |
+
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+pub mod cobs;
+pub mod crc;
+#[cfg(feature = "std")]
+mod io;
+
+#[cfg(feature = "std")]
+pub use io::FramedPort;
+
+use core::fmt;
+
+/// Terminates every frame
+pub const DELIMITER: u8 = 0;
+
+/// A checksum that's appended to the payload
+///
+/// The checksum is little endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checksum {
+    /// No checksum
+    None,
+    /// CRC-16/CCITT-FALSE; see [`crc::crc16`]
+    Crc16,
+    /// CRC-32; see [`crc::crc32`]
+    Crc32,
+}
+
+impl Checksum {
+    /// Returns the size of the checksum, in bytes
+    pub const fn size(self) -> usize {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc16 => 2,
+            Checksum::Crc32 => 4,
+        }
+    }
+
+    /// Compute the checksum of `data`
+    ///
+    /// Returns the first [`size`](Checksum::size) bytes of the array.
+    fn compute(self, data: &[u8]) -> [u8; 4] {
+        match self {
+            Checksum::None => [0; 4],
+            Checksum::Crc16 => {
+                let crc = crc::crc16(data).to_le_bytes();
+                [crc[0], crc[1], 0, 0]
+            }
+            Checksum::Crc32 => crc::crc32(data).to_le_bytes(),
+        }
+    }
+}
+
+/// A framing error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The output buffer is too small
+    BufferTooSmall,
+    /// The frame isn't a valid COBS encoding, or it's too small for its checksum
+    Malformed,
+    /// The frame's checksum doesn't match its payload
+    ChecksumMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BufferTooSmall => f.write_str("buffer too small"),
+            Error::Malformed => f.write_str("malformed frame"),
+            Error::ChecksumMismatch => f.write_str("checksum mismatch"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Returns the largest frame for a `len` byte payload, including the
+/// checksum and delimiter
+pub const fn max_frame_len(len: usize, checksum: Checksum) -> usize {
+    cobs::max_encoded_len(len + checksum.size()) + 1
+}
+
+/// Encode a frame, and emit it through `emit`
+///
+/// The frame includes the delimiter. `emit` may be called many times
+/// for a single frame.
+pub fn write_frame(payload: &[u8], checksum: Checksum, mut emit: impl FnMut(&[u8])) {
+    let mut encoder = cobs::Encoder::new();
+    encoder.extend(payload, &mut emit);
+    encoder.extend(&checksum.compute(payload)[..checksum.size()], &mut emit);
+    encoder.finish(&mut emit);
+    emit(&[DELIMITER]);
+}
+
+/// Encode a frame into `out`, returning the size of the frame
+///
+/// The frame includes the delimiter. `out` should be at least
+/// [`max_frame_len`] bytes.
+pub fn encode_frame(payload: &[u8], checksum: Checksum, out: &mut [u8]) -> Result<usize, Error> {
+    let mut len = 0;
+    let mut overflow = false;
+    write_frame(payload, checksum, |bytes| {
+        match out.get_mut(len..len + bytes.len()) {
+            Some(dst) if !overflow => dst.copy_from_slice(bytes),
+            _ => overflow = true,
+        }
+        len += bytes.len();
+    });
+    if overflow {
+        Err(Error::BufferTooSmall)
+    } else {
+        Ok(len)
+    }
+}
+
+/// Decode a frame into `out`, returning the size of the payload
+///
+/// `encoded` should not include the delimiter. `out` needs room for the
+/// payload, and its checksum; the encoded frame's size is always enough.
+pub fn decode_frame(encoded: &[u8], checksum: Checksum, out: &mut [u8]) -> Result<usize, Error> {
+    let len = cobs::decode(encoded, out)?;
+    let payload_len = len.checked_sub(checksum.size()).ok_or(Error::Malformed)?;
+    let (payload, received) = out[..len].split_at(payload_len);
+    if received != &checksum.compute(payload)[..checksum.size()] {
+        return Err(Error::ChecksumMismatch);
+    }
+    Ok(payload_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_frame, encode_frame, max_frame_len, Checksum, Error};
+
+    const CHECKSUMS: [Checksum; 3] = [Checksum::None, Checksum::Crc16, Checksum::Crc32];
+
+    #[test]
+    fn roundtrip() {
+        let payloads: [&[u8]; 4] = [b"", b"\0", b"hello world", &[0xFF; 300]];
+        for &checksum in CHECKSUMS.iter() {
+            for payload in payloads.iter() {
+                let mut frame = [0; 512];
+                let len = encode_frame(payload, checksum, &mut frame).unwrap();
+                assert!(len <= max_frame_len(payload.len(), checksum));
+                assert_eq!(frame[len - 1], 0);
+                assert!(!frame[..len - 1].contains(&0));
+
+                let mut out = [0; 512];
+                let decoded = decode_frame(&frame[..len - 1], checksum, &mut out).unwrap();
+                assert_eq!(&out[..decoded], *payload);
+            }
+        }
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        for &checksum in CHECKSUMS[1..].iter() {
+            let mut frame = [0; 32];
+            let len = encode_frame(b"hello world", checksum, &mut frame).unwrap();
+            // Flip a payload bit, without introducing a zero.
+            frame[3] ^= 0x01;
+            let mut out = [0; 32];
+            assert_eq!(
+                decode_frame(&frame[..len - 1], checksum, &mut out),
+                Err(Error::ChecksumMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn too_small_for_checksum() {
+        let mut out = [0; 4];
+        assert_eq!(
+            decode_frame(&[0x02, 0x42], Checksum::Crc16, &mut out),
+            Err(Error::Malformed)
+        );
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let mut frame = [0; 4];
+        assert_eq!(
+            encode_frame(b"hello", Checksum::None, &mut frame),
+            Err(Error::BufferTooSmall)
+        );
+    }
+}
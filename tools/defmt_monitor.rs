@@ -0,0 +1,65 @@
+//! Prints `defmt` logs from a Teensy 4's USB serial port.
+//!
+//! ```text
+//! defmt-monitor path/to/elf /dev/ttyACM0
+//! ```
+//!
+//! The ELF is the program that's running on your Teensy 4. The
+//! program must use the BSP's `"defmt"` feature.
+
+use std::{env, error, fs, io::Read, path::PathBuf, time::Duration};
+
+use defmt_decoder::{DecodeError, Table};
+
+fn main() -> Result<(), Box<dyn error::Error>> {
+    let mut args = env::args().skip(1);
+    let elf_path = args
+        .next()
+        .map(PathBuf::from)
+        .ok_or("Supply the path to a Teensy 4 ELF program")?;
+    let port_path = args
+        .next()
+        .ok_or("Supply the path to the Teensy 4 serial port")?;
+
+    let elf = fs::read(&elf_path)?;
+    let table = Table::parse(&elf)?.ok_or("The ELF program doesn't have defmt data")?;
+    let locations = table.get_locations(&elf)?;
+
+    // The baud rate doesn't matter for USB serial. Don't use 134 baud,
+    // which reboots the Teensy into its bootloader.
+    let mut port = serialport::new(&port_path, 115_200)
+        .timeout(Duration::from_millis(100))
+        .open()?;
+    // The BSP's logger doesn't care about DTR, but some hosts
+    // expect it when opening a terminal.
+    port.write_data_terminal_ready(true)?;
+
+    let mut decoder = table.new_stream_decoder();
+    let mut buffer = [0; 1024];
+    loop {
+        let count = match port.read(&mut buffer) {
+            Ok(count) => count,
+            Err(err) if err.kind() == std::io::ErrorKind::TimedOut => continue,
+            Err(err) => return Err(err.into()),
+        };
+        decoder.received(&buffer[..count]);
+
+        loop {
+            match decoder.decode() {
+                Ok(frame) => {
+                    println!("{}", frame.display(true));
+                    if let Some(location) = locations.get(&frame.index()) {
+                        println!("└─ {}:{}", location.file.display(), location.line);
+                    }
+                }
+                Err(DecodeError::UnexpectedEof) => break,
+                Err(DecodeError::Malformed) if table.encoding().can_recover() => {
+                    eprintln!("(skipped a malformed frame)");
+                }
+                Err(DecodeError::Malformed) => {
+                    return Err("Malformed frame; can't recover with this encoding".into())
+                }
+            }
+        }
+    }
+}